@@ -0,0 +1,34 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Build script to embed the git commit hash in the version, if built from a repository
+*/
+
+use std::process::Command;
+
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| String::from(s.trim()))
+        .filter(|s| !s.is_empty());
+
+    let version = env!("CARGO_PKG_VERSION");
+    match hash {
+        Some(h) => println!(
+            "cargo:rustc-env=YT_API_VIDEOSUM_VERSION={} ({})",
+            version, h
+        ),
+        None => println!("cargo:rustc-env=YT_API_VIDEOSUM_VERSION={}", version),
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
@@ -0,0 +1,173 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to cache the resolved channels, saving the channel query on later runs
+
+    The cache file has one tab-separated line per handle: handle, channel ID, uploads playlist ID, video count, time of
+    caching (Unix epoch seconds) and title. Entries older than the maximum age are ignored and re-resolved, as the handle
+    may have been moved to another channel since. Note the video count is as of the time of caching.
+*/
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::ChannelInfo;
+
+pub const DEFAULT_MAX_AGE_DAYS: i64 = 30;
+
+#[derive(Clone, Debug)]
+pub struct ChannelCache {
+    path: PathBuf,
+    max_age: TimeDelta,
+}
+
+struct Entry {
+    handle: String,
+    info: ChannelInfo,
+    cached: DateTime<Utc>,
+}
+
+impl ChannelCache {
+    pub fn new(path: impl Into<PathBuf>, max_age: TimeDelta) -> Self {
+        Self {
+            path: path.into(),
+            max_age,
+        }
+    }
+
+    pub(crate) fn get(&self, handle: &str, now: DateTime<Utc>) -> Option<ChannelInfo> {
+        let handle = handle.to_lowercase();
+        self.load()
+            .into_iter()
+            .find(|e| e.handle == handle && self.is_fresh(e, now))
+            .map(|e| e.info)
+    }
+
+    //Also drops the expired entries
+    pub(crate) fn put(
+        &self,
+        handle: &str,
+        info: &ChannelInfo,
+        now: DateTime<Utc>,
+    ) -> std::io::Result<()> {
+        let handle = handle.to_lowercase();
+        let mut entries: Vec<Entry> = self
+            .load()
+            .into_iter()
+            .filter(|e| e.handle != handle && self.is_fresh(e, now))
+            .collect();
+        entries.push(Entry {
+            handle,
+            info: info.clone(),
+            cached: now,
+        });
+
+        let mut content = String::new();
+        for e in entries {
+            content.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                e.handle,
+                e.info.id,
+                e.info.uploads_playlist_id,
+                e.info.video_count,
+                e.cached.timestamp(),
+                e.info.title.replace(['\t', '\r', '\n'], " ")
+            ));
+        }
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, content)
+    }
+
+    fn is_fresh(&self, entry: &Entry, now: DateTime<Utc>) -> bool {
+        entry.cached <= now && now - entry.cached < self.max_age
+    }
+
+    //A missing or damaged cache is not an error, the channels are just resolved again
+    fn load(&self) -> Vec<Entry> {
+        let content = std::fs::read_to_string(&self.path).unwrap_or_default();
+        content.lines().filter_map(parse_line).collect()
+    }
+}
+
+fn parse_line(line: &str) -> Option<Entry> {
+    let [handle, id, uploads, count, cached, title]: [&str; 6] =
+        line.splitn(6, '\t').collect::<Vec<_>>().try_into().ok()?;
+    Some(Entry {
+        handle: String::from(handle),
+        info: ChannelInfo {
+            id: String::from(id),
+            title: String::from(title),
+            uploads_playlist_id: String::from(uploads),
+            video_count: count.parse().ok()?,
+        },
+        cached: DateTime::from_timestamp(cached.parse().ok()?, 0)?,
+    })
+}
+
+#[cfg(test)]
+mod cache_test {
+    use super::*;
+
+    #[test]
+    fn ttl_test() {
+        let path =
+            std::env::temp_dir().join(format!("yt_api_videosum_cache_{}", std::process::id()));
+        let cache = ChannelCache::new(&path, TimeDelta::days(DEFAULT_MAX_AGE_DAYS));
+        let info = |id: &str| ChannelInfo {
+            id: String::from(id),
+            title: String::from("Some\tChannel"),
+            uploads_playlist_id: format!("UU{}", id),
+            video_count: 42,
+        };
+        let day = |d: i64| DateTime::from_timestamp(1704067200 + d * 86400, 0).unwrap();
+
+        assert_eq!(cache.get("foo", day(0)), None);
+        cache.put("Foo", &info("a"), day(0)).unwrap();
+        cache.put("bar", &info("b"), day(20)).unwrap();
+
+        let hit = cache.get("foo", day(29)).unwrap();
+        assert_eq!(hit.uploads_playlist_id, "UUa");
+        assert_eq!(hit.title, "Some Channel");
+        assert_eq!(
+            cache.get("FOO", day(29)).map(|i| i.id),
+            Some(String::from("a"))
+        );
+        assert_eq!(cache.get("foo", day(30)), None);
+        assert_eq!(
+            cache.get("bar", day(49)).map(|i| i.id),
+            Some(String::from("b"))
+        );
+        //Clock went backwards
+        assert_eq!(cache.get("bar", day(19)), None);
+
+        //Re-resolved after moving, the expired entry is dropped
+        cache.put("foo", &info("c"), day(31)).unwrap();
+        assert_eq!(
+            cache.get("foo", day(31)).map(|i| i.id),
+            Some(String::from("c"))
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        cache.put("baz", &info("d"), day(55)).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        let short = ChannelCache::new(&path, TimeDelta::hours(1));
+        assert_eq!(
+            short.get("baz", day(55)).map(|i| i.id),
+            Some(String::from("d"))
+        );
+        assert_eq!(short.get("foo", day(55)), None);
+
+        std::fs::write(&path, "damaged\nfoo\tx\tUUx\tnot a number\t0\tt\n").unwrap();
+        assert_eq!(cache.get("foo", day(0)), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to color the messages on a terminal
+
+    Whether to color is decided once per stream by 'init': always, never, or (by default) only for a terminal and if
+    the NO_COLOR environment variable is not set to a non-empty value (see https://no-color.org). The messages are
+    styled by their prefix in Status::print, so the callers never deal with escape codes. Anything not printed as a
+    message, i.e. the porcelain line and the output files, is never colored.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STDOUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Choice {
+    Auto,
+    Always,
+    Never,
+}
+impl std::str::FromStr for Choice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "Invalid color choice '{}', expected 'auto', 'always' or 'never'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    Warning,
+    Error,
+    Total,
+    Dim,
+}
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Warning => "\x1b[33m",
+            Style::Error => "\x1b[31m",
+            Style::Total => "\x1b[1;32m",
+            Style::Dim => "\x1b[2m",
+        }
+    }
+
+    //Of a message line, by its prefix
+    pub fn of_line(line: &str) -> Option<Style> {
+        if line.starts_with("Warning: ") {
+            Some(Style::Warning)
+        } else if line.starts_with("Error: ") {
+            Some(Style::Error)
+        } else if line.starts_with("Sum total: ") {
+            Some(Style::Total)
+        } else if line.starts_with("Note: ") || line.starts_with("Info: ") {
+            Some(Style::Dim)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn enabled(choice: Choice, terminal: bool, no_color: Option<&str>) -> bool {
+    match choice {
+        Choice::Always => true,
+        Choice::Never => false,
+        Choice::Auto => terminal && no_color.is_none_or(str::is_empty),
+    }
+}
+
+pub fn init(choice: Choice) {
+    use std::io::IsTerminal;
+
+    let no_color = std::env::var("NO_COLOR").ok();
+    let no_color = no_color.as_deref();
+    STDOUT.store(
+        enabled(choice, std::io::stdout().is_terminal(), no_color),
+        Ordering::Relaxed,
+    );
+    STDERR.store(
+        enabled(choice, std::io::stderr().is_terminal(), no_color),
+        Ordering::Relaxed,
+    );
+}
+
+pub fn stdout() -> bool {
+    STDOUT.load(Ordering::Relaxed)
+}
+
+pub fn stderr() -> bool {
+    STDERR.load(Ordering::Relaxed)
+}
+
+//Each line styled by its prefix, keeping the line breaks outside of the escape codes
+pub fn paint_lines(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let (content, newline) = match line.strip_suffix('\n') {
+            Some(c) => (c, "\n"),
+            None => (line, ""),
+        };
+        match Style::of_line(content) {
+            Some(style) => {
+                out.push_str(style.code());
+                out.push_str(content);
+                out.push_str(RESET);
+            }
+            None => out.push_str(content),
+        }
+        out.push_str(newline);
+    }
+    out
+}
+
+#[cfg(test)]
+mod color_test {
+    use super::*;
+
+    #[test]
+    fn enabled_test() {
+        let tests = [
+            (Choice::Auto, true, None, true),
+            (Choice::Auto, false, None, false),
+            (Choice::Auto, true, Some("1"), false),
+            (Choice::Auto, true, Some(""), true),
+            (Choice::Always, false, Some("1"), true),
+            (Choice::Never, true, None, false),
+        ];
+        for (choice, terminal, no_color, on) in tests {
+            assert_eq!(
+                enabled(choice, terminal, no_color),
+                on,
+                "{:?} terminal={} NO_COLOR={:?}",
+                choice,
+                terminal,
+                no_color
+            );
+        }
+        assert_eq!("always".parse(), Ok(Choice::Always));
+        assert!("yes".parse::<Choice>().is_err());
+    }
+
+    #[test]
+    fn paint_lines_test() {
+        let tests = [
+            ("Querying channel info...", "Querying channel info..."),
+            (
+                "Warning: Skipping video 'a'\n",
+                "\x1b[33mWarning: Skipping video 'a'\x1b[0m\n",
+            ),
+            (
+                "Sum total: 60 seconds, or 1 minute\n",
+                "\x1b[1;32mSum total: 60 seconds, or 1 minute\x1b[0m\n",
+            ),
+            (
+                "Video count: 3\nNote: Only CSV output can be verified.\n",
+                "Video count: 3\n\x1b[2mNote: Only CSV output can be verified.\x1b[0m\n",
+            ),
+            ("\n", "\n"),
+            ("", ""),
+        ];
+        for (text, painted) in tests {
+            assert_eq!(paint_lines(text), painted, "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,715 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to write and re-load the CSV output
+
+    Lines starting with '#' outside of a quoted field are comments, this is used for the column header and the summary
+    footer, so CSV parsers configured to skip comments will ignore them. Fields containing separators, quotes or line
+    breaks are quoted as per RFC 4180.
+
+    With a custom date format, a leading 'date' column is added (see HEADER_DATED), this is skipped when re-loading.
+    With '--captions-column', a trailing 'has_captions' column is added, either 'true', 'false' or empty if unknown.
+    With '--definition-column', a trailing 'definition' column is added after it, either 'hd', 'sd' or empty if unknown.
+    As an empty value would be ambiguous between the two, the latter is recognized by the column header when re-loading.
+    With '--immersive-columns', trailing 'dimension' ('2d' or '3d') and 'projection' ('rectangular' or '360') columns
+    are added after these, empty if unknown, and likewise recognized by the column header. With '--with-thumbnail', a
+    trailing 'thumbnail_url' column is added last, empty if the video has no thumbnail of the quality or below.
+
+    The merged output of several channels has a leading 'channel' column instead (see HEADER_MERGED).
+*/
+
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use crate::lang::Lang;
+use crate::output::{DateFormat, Metadata, RowWriter};
+use crate::{dissect_delta, TimeBase, Video};
+
+const FLUSH_INTERVAL: usize = 10;
+
+pub const HEADER: &str = "#publishedAt,title,videoId,duration,duration_seconds";
+pub const HEADER_DATED: &str = "#date,publishedAt,title,videoId,duration,duration_seconds";
+pub const HEADER_MERGED: &str = "#channel,publishedAt,title,videoId,duration,duration_seconds";
+const CAPTIONS_COLUMN: &str = ",has_captions";
+const DEFINITION_COLUMN: &str = ",definition";
+const IMMERSIVE_COLUMNS: &str = ",dimension,projection";
+const THUMBNAIL_COLUMN: &str = ",thumbnail_url";
+
+pub fn escape(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+//Writes each row as a whole and flushes periodically, so an interrupted run still leaves a valid file with the
+//completed rows
+pub struct Writer<W: Write> {
+    out: W,
+    date: Option<DateFormat>,
+    captions: bool,
+    definition: bool,
+    immersive: bool,
+    thumbnail: bool,
+    count: usize,
+    total: TimeDelta,
+}
+impl<W: Write> Writer<W> {
+    pub fn new(
+        mut out: W,
+        date: Option<DateFormat>,
+        metadata: Option<&Metadata>,
+        captions: bool,
+        definition: bool,
+        immersive: bool,
+        thumbnail: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let header = match date {
+            Some(_) => HEADER_DATED,
+            None => HEADER,
+        };
+        writeln!(
+            out,
+            "{}{}{}{}{}",
+            header,
+            if captions { CAPTIONS_COLUMN } else { "" },
+            if definition { DEFINITION_COLUMN } else { "" },
+            if immersive { IMMERSIVE_COLUMNS } else { "" },
+            if thumbnail { THUMBNAIL_COLUMN } else { "" }
+        )?;
+        for line in metadata.map(Metadata::lines).unwrap_or_default() {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()?;
+        Ok(Self {
+            out,
+            date,
+            captions,
+            definition,
+            immersive,
+            thumbnail,
+            count: 0,
+            total: TimeDelta::zero(),
+        })
+    }
+}
+impl<W: Write> RowWriter<W> for Writer<W> {
+    fn write(&mut self, video: &Video) -> Result<(), Box<dyn Error>> {
+        let mut row = match self.date {
+            Some(ref d) => format!("{},{}", escape(&d.render(video.date)), video),
+            None => video.to_string(),
+        };
+        if self.captions {
+            row.push(',');
+            row.push_str(match video.has_captions {
+                Some(true) => "true",
+                Some(false) => "false",
+                None => "",
+            });
+        }
+        if self.definition {
+            row.push(',');
+            if let Some(d) = video.definition {
+                row.push_str(&d.to_string());
+            }
+        }
+        if self.immersive {
+            row.push(',');
+            if let Some(d) = video.dimension {
+                row.push_str(&d.to_string());
+            }
+            row.push(',');
+            if let Some(p) = video.projection {
+                row.push_str(&p.to_string());
+            }
+        }
+        if self.thumbnail {
+            row.push(',');
+            if let Some(ref url) = video.thumbnail_url {
+                row.push_str(&escape(url));
+            }
+        }
+        row.push('\n');
+        self.out.write_all(row.as_bytes())?;
+        self.count += 1;
+        if self.count.is_multiple_of(FLUSH_INTERVAL) {
+            self.out.flush()?;
+        }
+        self.total += video.delta;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, footer: Option<DateTime<Utc>>) -> Result<W, Box<dyn Error>> {
+        if let Some(generated) = footer {
+            writeln!(
+                self.out,
+                "{}",
+                footer_line(self.count, self.total, generated)
+            )?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+//Videos of each channel in turn, the channel given as its handle
+pub fn write_merged<W: Write>(
+    mut out: W,
+    channels: &[(&str, &[Video])],
+) -> Result<W, Box<dyn Error>> {
+    writeln!(out, "{}", HEADER_MERGED)?;
+    for (channel, videos) in channels {
+        for video in *videos {
+            writeln!(out, "{},{}", escape(channel), video)?;
+        }
+    }
+    out.flush()?;
+    Ok(out)
+}
+
+pub fn footer_line(count: usize, total: TimeDelta, generated: DateTime<Utc>) -> String {
+    format!(
+        "#video_count={},total_seconds={},total={},generated={}",
+        count,
+        total.num_seconds(),
+        dissect_delta(total, TimeBase::Hours, Lang::En),
+        generated.to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+pub fn read_csv(reader: impl BufRead) -> Result<Vec<Video>, Box<dyn Error>> {
+    let mut videos = Vec::new();
+    let mut record = String::new();
+    let mut line_no = 0;
+    let mut start_no = 0;
+    let mut definition = false;
+    let mut immersive = false;
+    let mut thumbnail = false;
+
+    for line in reader.lines() {
+        let line = line?;
+        line_no += 1;
+
+        if record.is_empty() {
+            if line.starts_with(HEADER) || line.starts_with(HEADER_DATED) {
+                let columns = line.strip_suffix(THUMBNAIL_COLUMN);
+                thumbnail = columns.is_some();
+                let line = columns.unwrap_or(&line);
+                let columns = line.strip_suffix(IMMERSIVE_COLUMNS);
+                immersive = columns.is_some();
+                definition = columns.unwrap_or(line).ends_with(DEFINITION_COLUMN);
+            }
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            start_no = line_no;
+        } else {
+            record.push('\n');
+        }
+        record.push_str(&line);
+
+        match split_record(&record) {
+            Some(fields) => {
+                videos.push(
+                    parse_record(fields, definition, immersive, thumbnail)
+                        .map_err(|e| format!("Line {}: {}", start_no, e))?,
+                );
+                record.clear();
+            }
+            None => continue,
+        }
+    }
+    if !record.is_empty() {
+        return Err(format!("Line {}: Unterminated quoted field", start_no).into());
+    }
+
+    Ok(videos)
+}
+
+//Returns None if a quoted field is still open at the end of the record
+fn split_record(record: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            (false, '"') if field.is_empty() => quoted = true,
+            (true, '"') => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            (_, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+
+    Some(fields)
+}
+
+fn parse_record(
+    mut fields: Vec<String>,
+    definition: bool,
+    immersive: bool,
+    thumbnail: bool,
+) -> Result<Video, Box<dyn Error>> {
+    let thumbnail_url = match thumbnail {
+        true => fields.pop().filter(|f| !f.is_empty()),
+        false => None,
+    };
+    let (dimension, projection) = match immersive {
+        true => {
+            let mut field = || fields.pop().filter(|f| !f.is_empty());
+            let projection = field();
+            let dimension = field();
+            (
+                dimension
+                    .map(|d| d.parse::<crate::Dimension>())
+                    .transpose()?,
+                projection
+                    .map(|p| p.parse::<crate::Projection>())
+                    .transpose()?,
+            )
+        }
+        false => (None, None),
+    };
+    let definition = match definition {
+        true => match fields.pop().as_deref() {
+            Some("") => None,
+            Some(d) => Some(d.parse::<crate::Definition>()?),
+            None => None,
+        },
+        false => None,
+    };
+    //Never mistaken for 'duration_seconds', which is always a number
+    let has_captions = match fields.last().map(String::as_str) {
+        _ if fields.len() < 6 => None,
+        Some("true") => Some(Some(true)),
+        Some("false") => Some(Some(false)),
+        Some("") => Some(None),
+        _ => None,
+    };
+    if has_captions.is_some() {
+        fields.pop();
+    }
+    //The leading 'date' column is for display only
+    if fields.len() == 6 {
+        fields.remove(0);
+    }
+    let [date, title, id, duration, seconds]: [String; 5] = fields
+        .try_into()
+        .map_err(|f: Vec<String>| format!("Expected 5 fields, found {}", f.len()))?;
+
+    let date = match DateTime::parse_from_rfc3339(&date) {
+        Ok(d) => DateTime::<Utc>::from(d),
+        Err(e) => return Err(format!("Could not parse 'publishedAt' timestamp: {}", e))?,
+    };
+    let video = Video {
+        has_captions: has_captions.flatten(),
+        definition,
+        dimension,
+        projection,
+        thumbnail_url,
+        ..Video::new(date, title, id, duration)?
+    };
+    if seconds != video.delta.num_seconds().to_string() {
+        return Err("Field 'duration_seconds' does not match 'duration'".into());
+    }
+
+    Ok(video)
+}
+
+#[cfg(test)]
+mod csv_test {
+    use super::*;
+    use crate::output::{write_videos, Layout};
+
+    fn write_csv(out: &mut Vec<u8>, videos: &[Video], footer: Option<DateTime<Utc>>) {
+        write_videos(out, videos, &Layout::default(), footer).unwrap();
+    }
+
+    fn fixture() -> Vec<Video> {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        vec![
+            Video::new(
+                date("2024-01-02T10:00:00Z"),
+                "Plain".into(),
+                "id0".into(),
+                "PT1M".into(),
+            )
+            .unwrap(),
+            Video::new(
+                date("2024-02-03T11:22:33Z"),
+                "A, \"quoted\" one".into(),
+                "id1".into(),
+                "PT1H2M3S".into(),
+            )
+            .unwrap(),
+            Video::new(
+                date("2024-03-04T00:00:00Z"),
+                "#Two\nlines".into(),
+                "id2".into(),
+                "P1DT4S".into(),
+            )
+            .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn footer_test() {
+        let generated =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-12-27T17:00:00Z").unwrap());
+        let mut out = Vec::new();
+        write_csv(&mut out, &fixture(), Some(generated));
+        let s = String::from_utf8(out).unwrap();
+
+        assert!(s.starts_with(HEADER));
+        assert_eq!(
+            s.lines().last().unwrap(),
+            "#video_count=3,total_seconds=90187,total=25 hours 3 minutes 7 seconds,generated=2024-12-27T17:00:00Z"
+        );
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &fixture(), None);
+        assert!(!String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with('#'));
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &[], Some(generated));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "{}\n#video_count=0,total_seconds=0,total=0 seconds,generated=2024-12-27T17:00:00Z\n",
+                HEADER
+            )
+        );
+    }
+
+    #[test]
+    fn interrupted_test() {
+        let videos = fixture();
+        let mut out = Vec::new();
+        {
+            //Never finished, as if the run was interrupted
+            let mut writer =
+                Box::new(Writer::new(&mut out, None, None, false, false, false, false).unwrap());
+            writer.write(&videos[0]).unwrap();
+            writer.write(&videos[1]).unwrap();
+        }
+
+        let reloaded = read_csv(out.as_slice()).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded[1].to_string(), videos[1].to_string());
+    }
+
+    #[test]
+    fn reload_test() {
+        let videos = fixture();
+        for footer in [None, Some(Utc::now())] {
+            let mut out = Vec::new();
+            write_csv(&mut out, &videos, footer);
+            let reloaded = read_csv(out.as_slice()).unwrap();
+            assert_eq!(reloaded.len(), videos.len());
+            for (a, b) in reloaded.iter().zip(&videos) {
+                assert_eq!(a.to_string(), b.to_string());
+            }
+        }
+
+        let tests = [
+            "2024-01-02T10:00:00Z,t,id,PT1M",
+            "2024-01-02T10:00:00Z,t,id,PT1M,61",
+            "2024-01-02T10:00:00Z,t,id,1M,60",
+            "2024-01-02,t,id,PT1M,60",
+            "2024-01-02T10:00:00Z,\"t,id,PT1M,60",
+        ];
+        for t in tests {
+            assert!(read_csv(t.as_bytes()).is_err(), "record=\"{}\"", t);
+        }
+    }
+
+    #[test]
+    fn date_column_test() {
+        let videos = fixture();
+        let date = DateFormat::new(Some("%d.%m.%Y"), Some("America/Los_Angeles")).unwrap();
+        let mut out = Vec::new();
+        let layout = Layout {
+            date: Some(&date),
+            ..Layout::default()
+        };
+        write_videos(&mut out, &videos, &layout, None).unwrap();
+        let s = String::from_utf8(out).unwrap();
+
+        let lines: Vec<_> = s.lines().collect();
+        assert_eq!(lines[0], HEADER_DATED);
+        assert_eq!(
+            lines[1],
+            "02.01.2024,2024-01-02T10:00:00Z,Plain,id0,PT1M,60"
+        );
+        //Previous day in local time
+        assert!(lines[3].starts_with("03.03.2024,2024-03-04T00:00:00Z,"));
+
+        let reloaded = read_csv(s.as_bytes()).unwrap();
+        assert_eq!(reloaded.len(), videos.len());
+        for (a, b) in reloaded.iter().zip(&videos) {
+            assert_eq!(a.date, b.date);
+            assert_eq!(a.to_string(), b.to_string());
+        }
+    }
+
+    #[test]
+    fn captions_column_test() {
+        let mut videos = fixture();
+        for (v, c) in videos.iter_mut().zip([Some(true), Some(false), None]) {
+            v.has_captions = c;
+        }
+        let date = DateFormat::new(Some("%d.%m.%Y"), None).unwrap();
+        for dated in [false, true] {
+            let mut out = Vec::new();
+            let layout = Layout {
+                date: dated.then_some(&date),
+                captions: true,
+                ..Layout::default()
+            };
+            write_videos(&mut out, &videos, &layout, None).unwrap();
+            let s = String::from_utf8(out).unwrap();
+
+            let lines: Vec<_> = s.lines().collect();
+            assert!(lines[0].ends_with(",duration_seconds,has_captions"));
+            assert!(lines[1].ends_with(",PT1M,60,true"), "{}", lines[1]);
+            assert!(lines[2].ends_with(",false"), "{}", lines[2]);
+            assert!(lines[4].ends_with(",P1DT4S,86404,"), "{}", lines[4]);
+
+            let reloaded = read_csv(s.as_bytes()).unwrap();
+            assert_eq!(reloaded.len(), videos.len());
+            for (a, b) in reloaded.iter().zip(&videos) {
+                assert_eq!(a.to_string(), b.to_string());
+                assert_eq!(a.has_captions, b.has_captions);
+            }
+        }
+    }
+
+    #[test]
+    fn definition_column_test() {
+        use crate::Definition;
+
+        let mut videos = fixture();
+        for (v, d) in videos
+            .iter_mut()
+            .zip([Some(Definition::Hd), Some(Definition::Sd), None])
+        {
+            v.definition = d;
+        }
+        for captions in [false, true] {
+            let mut out = Vec::new();
+            let layout = Layout {
+                captions,
+                definition: true,
+                ..Layout::default()
+            };
+            write_videos(&mut out, &videos, &layout, None).unwrap();
+            let s = String::from_utf8(out).unwrap();
+
+            let lines: Vec<_> = s.lines().collect();
+            assert!(lines[0].ends_with(",duration_seconds,has_captions,definition") == captions);
+            assert!(lines[0].ends_with(",definition"));
+            assert!(lines[1].ends_with(",hd"), "{}", lines[1]);
+            assert!(lines[2].ends_with(",sd"), "{}", lines[2]);
+            assert!(lines[lines.len() - 1].ends_with(','), "{}", s);
+
+            let reloaded = read_csv(s.as_bytes()).unwrap();
+            assert_eq!(reloaded.len(), videos.len());
+            for (a, b) in reloaded.iter().zip(&videos) {
+                assert_eq!(a.to_string(), b.to_string());
+                assert_eq!(a.definition, b.definition);
+                assert_eq!(a.has_captions, None);
+            }
+        }
+
+        let s = format!(
+            "{},definition\n2024-01-01T00:00:00Z,a,x,PT1M,60,uhd\n",
+            HEADER
+        );
+        assert!(read_csv(s.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn immersive_columns_test() {
+        use crate::{Definition, Dimension, Projection};
+
+        let mut videos = fixture();
+        for (v, (d, p)) in videos.iter_mut().zip([
+            (Some(Dimension::ThreeD), Some(Projection::Spherical)),
+            (Some(Dimension::TwoD), None),
+            (None, None),
+        ]) {
+            v.dimension = d;
+            v.projection = p;
+            v.definition = Some(Definition::Hd);
+        }
+        for definition in [false, true] {
+            let mut out = Vec::new();
+            let layout = Layout {
+                definition,
+                immersive: true,
+                ..Layout::default()
+            };
+            write_videos(&mut out, &videos, &layout, None).unwrap();
+            let s = String::from_utf8(out).unwrap();
+
+            let lines: Vec<_> = s.lines().collect();
+            assert!(lines[0].ends_with(",definition,dimension,projection") == definition);
+            assert!(lines[0].ends_with(",duration_seconds,dimension,projection") != definition);
+            assert!(lines[1].ends_with(",3d,360"), "{}", lines[1]);
+            assert!(lines[2].ends_with(",2d,"), "{}", lines[2]);
+            assert!(lines[lines.len() - 1].ends_with(",,"), "{}", s);
+
+            let reloaded = read_csv(s.as_bytes()).unwrap();
+            assert_eq!(reloaded.len(), videos.len());
+            for (a, b) in reloaded.iter().zip(&videos) {
+                assert_eq!(a.to_string(), b.to_string());
+                assert_eq!((a.dimension, a.projection), (b.dimension, b.projection));
+                assert_eq!(a.definition.is_some(), definition);
+            }
+        }
+
+        let s = format!(
+            "{},dimension,projection\n2024-01-01T00:00:00Z,a,x,PT1M,60,3d,180\n",
+            HEADER
+        );
+        assert!(read_csv(s.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn thumbnail_column_test() {
+        use crate::Dimension;
+
+        let mut videos = fixture();
+        for (v, url) in
+            videos
+                .iter_mut()
+                .zip([Some("https://i.ytimg.com/vi/a/hqdefault.jpg"), None, None])
+        {
+            v.thumbnail_url = url.map(String::from);
+            v.dimension = Some(Dimension::TwoD);
+        }
+        for immersive in [false, true] {
+            let mut out = Vec::new();
+            let layout = Layout {
+                immersive,
+                thumbnail: true,
+                ..Layout::default()
+            };
+            write_videos(&mut out, &videos, &layout, None).unwrap();
+            let s = String::from_utf8(out).unwrap();
+
+            let lines: Vec<_> = s.lines().collect();
+            assert!(lines[0].ends_with(",projection,thumbnail_url") == immersive);
+            assert!(lines[0].ends_with(",duration_seconds,thumbnail_url") != immersive);
+            assert!(lines[1].ends_with(",https://i.ytimg.com/vi/a/hqdefault.jpg"));
+            assert!(lines[2].ends_with(','), "{}", lines[2]);
+
+            let reloaded = read_csv(s.as_bytes()).unwrap();
+            assert_eq!(reloaded.len(), videos.len());
+            for (a, b) in reloaded.iter().zip(&videos) {
+                assert_eq!(a.to_string(), b.to_string());
+                assert_eq!(a.thumbnail_url, b.thumbnail_url);
+                assert_eq!(a.dimension.is_some(), immersive);
+            }
+        }
+    }
+
+    #[test]
+    fn merged_test() {
+        let videos = fixture();
+        let out = write_merged(
+            Vec::new(),
+            &[
+                ("@one", &videos[..2]),
+                ("@t,wo", &videos[2..]),
+                ("@three", &[]),
+            ],
+        )
+        .unwrap();
+        let s = String::from_utf8(out).unwrap();
+
+        let lines: Vec<_> = s.lines().collect();
+        assert_eq!(lines[0], HEADER_MERGED);
+        assert_eq!(lines[1], "@one,2024-01-02T10:00:00Z,Plain,id0,PT1M,60");
+        assert!(lines[2].starts_with("@one,2024-02-03T11:22:33Z,"));
+        assert!(lines[3].starts_with("\"@t,wo\",2024-03-04T00:00:00Z,"));
+        assert_eq!(s.matches(",id").count(), 3);
+    }
+
+    #[test]
+    fn metadata_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let metadata = Metadata {
+            generated: date("2025-01-10T12:34:56Z"),
+            channel: String::from("Some\nChannel"),
+            channel_id: String::from("UCxyz"),
+            playlist_id: String::from("UULFxyz"),
+            start: Some(date("2024-01-01T00:00:00+01:00")),
+            end: None,
+            channel_videos: 123,
+            matched_videos: 3,
+        };
+        let layout = Layout {
+            metadata: Some(&metadata),
+            ..Layout::default()
+        };
+        let mut out = Vec::new();
+        write_videos(&mut out, &fixture(), &layout, None).unwrap();
+        let s = String::from_utf8(out).unwrap();
+
+        let lines: Vec<_> = s.lines().collect();
+        assert_eq!(lines[0], HEADER);
+        let fields: Vec<_> = lines[1..10]
+            .iter()
+            .map(|l| l.strip_prefix('#').unwrap().split_once('=').unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (
+                    "tool",
+                    format!("yt-api-videosum {}", crate::version()).as_str()
+                ),
+                ("generated", "2025-01-10T12:34:56Z"),
+                ("channel", "@SomeChannel"),
+                ("channel_id", "UCxyz"),
+                ("playlist_id", "UULFxyz"),
+                ("start", "2023-12-31T23:00:00Z"),
+                ("end", "-"),
+                ("channel_video_count", "123"),
+                ("matched_video_count", "3"),
+            ]
+        );
+        assert!(!lines[10].starts_with('#'));
+        assert!(crate::version().starts_with(env!("CARGO_PKG_VERSION")));
+
+        let reloaded = read_csv(s.as_bytes()).unwrap();
+        assert_eq!(reloaded.len(), 3);
+        assert_eq!(reloaded[0].to_string(), fixture()[0].to_string());
+    }
+}
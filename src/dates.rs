@@ -0,0 +1,503 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to parse the date filters
+
+    Besides RFC3339 timestamps and '@'-prefixed Unix epoch seconds, calendar dates ('2024-01-31'), months ('2024-01')
+    and years ('2024') are accepted, meaning their first second as a start date and their last second as an end date,
+    so both bounds are inclusive. The words 'today' and 'yesterday' work the same way, while relative forms ('30d',
+    '2w', '6m', '1y') are the exact instant that long before the current time.
+
+    Timestamps without an offset ('2024-01-31T18:00', '2024-01-31 18:00:00') and the calendar forms are in the given
+    timezone. Around daylight saving time transitions, a local time occurring twice is taken as the earlier one, and
+    one skipped is moved forward by the length of the gap (e.g. 02:30 to 03:30), both with a warning.
+
+    A start date after the end date is an error, as nothing could match. Equal ones (a single instant) and a range
+    entirely in the future are allowed, with a note or a warning.
+*/
+
+use chrono::{
+    DateTime, LocalResult, Months, NaiveDate, NaiveDateTime, Offset, TimeDelta, TimeZone, Utc,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Start,
+    End,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParsedDate {
+    pub date: DateTime<Utc>,
+    //About the resolution of a local time around a daylight saving time transition
+    pub warning: Option<String>,
+}
+impl From<DateTime<Utc>> for ParsedDate {
+    fn from(date: DateTime<Utc>) -> Self {
+        Self {
+            date,
+            warning: None,
+        }
+    }
+}
+
+pub fn parse_date<Z: TimeZone>(
+    s: &str,
+    bound: Bound,
+    now: DateTime<Utc>,
+    zone: &Z,
+) -> Result<ParsedDate, String> {
+    if let Some(epoch) = s.strip_prefix('@') {
+        return match epoch.parse::<i64>() {
+            Ok(secs) => DateTime::from_timestamp(secs, 0)
+                .map(ParsedDate::from)
+                .ok_or(format!("Epoch timestamp out of range: {}", secs)),
+            Err(e) => Err(format!("Invalid epoch timestamp: {}", e)),
+        };
+    }
+    match DateTime::parse_from_rfc3339(s) {
+        Ok(d) => return Ok(DateTime::<Utc>::from(d).into()),
+        Err(e) if s.contains(['T', ':']) => {
+            let naive = [
+                "%Y-%m-%dT%H:%M:%S",
+                "%Y-%m-%d %H:%M:%S",
+                "%Y-%m-%dT%H:%M",
+                "%Y-%m-%d %H:%M",
+            ]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok());
+            return match naive {
+                Some(n) => Ok(resolve_local(n, zone)),
+                //Looks like a timestamp, the details are more helpful than the list of forms
+                None => Err(e.to_string()),
+            };
+        }
+        Err(_) => (),
+    }
+
+    let today = now.with_timezone(zone).date_naive();
+    match s {
+        "today" => return Ok(resolve_local(day_bound(today, today, bound), zone)),
+        "yesterday" => {
+            let day = today.pred_opt().ok_or("Date out of range")?;
+            return Ok(resolve_local(day_bound(day, day, bound), zone));
+        }
+        _ => (),
+    }
+    if let Some(d) = parse_relative(s, now) {
+        return d.map(ParsedDate::from);
+    }
+    if let Some((first, last)) = parse_calendar(s)? {
+        return Ok(resolve_local(day_bound(first, last, bound), zone));
+    }
+
+    Err(String::from(
+        "Expected an RFC3339 timestamp, '@' followed by Unix epoch seconds, a date like '2024-01-31', \
+        '2024-01' or '2024', a relative one like '30d', '2w', '6m' or '1y', or 'today' or 'yesterday'",
+    ))
+}
+
+//The message to display for a range that is valid but unlikely to be meant
+pub fn check_range(
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<Option<String>, String> {
+    match (start, end) {
+        (Some(s), Some(e)) if s > e => Err(format!(
+            "The start date ({}) is after the end date ({})",
+            s.to_rfc3339(),
+            e.to_rfc3339()
+        )),
+        (Some(s), Some(e)) if s == e => Ok(Some(String::from(
+            "Note: The start and end dates are the same instant, only videos published exactly then match.",
+        ))),
+        (Some(s), _) if s > now => Ok(Some(String::from(
+            "Warning: The date range lies entirely in the future, no videos can match yet.",
+        ))),
+        _ => Ok(None),
+    }
+}
+
+//The 'publishedAt' of the API, which is always RFC3339 in UTC (e.g. '2024-01-31T18:00:00Z'), but a single odd one
+//should not stop the run
+pub fn parse_published(s: &str) -> Result<DateTime<Utc>, String> {
+    let strict = match DateTime::parse_from_rfc3339(s) {
+        Ok(d) => return Ok(d.into()),
+        Err(e) => e,
+    };
+
+    //Surrounding whitespace, a space or a lowercase 't' as the separator, a lowercase 'z'
+    let s = s.trim().replacen(' ', "T", 1).replacen('t', "T", 1);
+    let s = match s.strip_suffix('z') {
+        Some(rest) => format!("{}Z", rest),
+        None => s,
+    };
+    if let Ok(d) = DateTime::parse_from_rfc3339(&s) {
+        return Ok(d.into());
+    }
+    //An offset without a colon (e.g. '+0000'), or no offset at all taken as UTC
+    DateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .map(DateTime::<Utc>::from)
+        .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f").map(|d| d.and_utc()))
+        .map_err(|_| strict.to_string())
+}
+
+//First second of the first day, or last second of the last day
+fn day_bound(first: NaiveDate, last: NaiveDate, bound: Bound) -> NaiveDateTime {
+    match bound {
+        Bound::Start => first.and_hms_opt(0, 0, 0).unwrap(),
+        Bound::End => last.and_hms_opt(23, 59, 59).unwrap(),
+    }
+}
+
+fn resolve_local<Z: TimeZone>(naive: NaiveDateTime, zone: &Z) -> ParsedDate {
+    match zone.from_local_datetime(&naive) {
+        LocalResult::Single(d) => d.with_timezone(&Utc).into(),
+        LocalResult::Ambiguous(a, b) => ParsedDate {
+            date: a.with_timezone(&Utc).min(b.with_timezone(&Utc)),
+            warning: Some(format!(
+                "Local time '{}' occurs twice in the timezone, the earlier one is used",
+                naive
+            )),
+        },
+        LocalResult::None => {
+            //The offset in effect before the gap, a day earlier to be safe
+            let before = zone
+                .offset_from_utc_datetime(&(naive - TimeDelta::days(1)))
+                .fix();
+            let date = (naive - TimeDelta::seconds(before.local_minus_utc().into())).and_utc();
+            ParsedDate {
+                date,
+                warning: Some(format!(
+                    "Local time '{}' does not exist in the timezone, moved forward to '{}'",
+                    naive,
+                    date.with_timezone(zone).naive_local()
+                )),
+            }
+        }
+    }
+}
+
+//Counted back from now, where months and years keep the day of month if possible, or else take the last day
+fn parse_relative(s: &str, now: DateTime<Utc>) -> Option<Result<DateTime<Utc>, String>> {
+    let unit = s.chars().last()?;
+    let count = &s[..s.len() - unit.len_utf8()];
+    if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let count: u32 = match count.parse() {
+        Ok(n) => n,
+        Err(e) => return Some(Err(format!("Invalid relative date '{}': {}", s, e))),
+    };
+
+    let date = match unit {
+        'd' => TimeDelta::try_days(count.into()).and_then(|d| now.checked_sub_signed(d)),
+        'w' => TimeDelta::try_weeks(count.into()).and_then(|d| now.checked_sub_signed(d)),
+        'm' => now.checked_sub_months(Months::new(count)),
+        'y' => count
+            .checked_mul(12)
+            .and_then(|m| now.checked_sub_months(Months::new(m))),
+        _ => return None,
+    };
+    Some(date.ok_or(format!("Relative date '{}' out of range", s)))
+}
+
+//First and last day of 'yyyy-mm-dd', 'yyyy-mm' or 'yyyy', or None if not in such form
+fn parse_calendar(s: &str) -> Result<Option<(NaiveDate, NaiveDate)>, String> {
+    let parts: Vec<&str> = s.split('-').collect();
+    let digits = |p: &&str| p.bytes().all(|b| b.is_ascii_digit());
+    if parts.len() > 3
+        || !parts.iter().all(digits)
+        || parts[0].len() != 4
+        || parts[1..].iter().any(|p| p.len() != 2)
+    {
+        return Ok(None);
+    }
+    let n: Vec<u32> = parts.iter().map(|p| p.parse().unwrap()).collect();
+    let year = n[0] as i32;
+
+    let invalid = || format!("No such date: '{}'", s);
+    let day = |m, d| NaiveDate::from_ymd_opt(year, m, d).ok_or_else(invalid);
+    let range = match n[1..] {
+        [] => (day(1, 1)?, day(12, 31)?),
+        [m] => {
+            let first = day(m, 1)?;
+            let last = first
+                .checked_add_months(Months::new(1))
+                .and_then(|d| d.pred_opt())
+                .ok_or_else(invalid)?;
+            (first, last)
+        }
+        [m, d] => (day(m, d)?, day(m, d)?),
+        _ => unreachable!(),
+    };
+    Ok(Some(range))
+}
+
+#[cfg(test)]
+mod dates_test {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let noon = date("2024-03-31T12:00:00Z");
+        let leap = date("2024-03-01T08:30:00Z");
+        use Bound::{End, Start};
+
+        let tests = [
+            //Unchanged forms
+            (
+                "2024-01-01T02:00:00+02:00",
+                End,
+                noon,
+                Ok("2024-01-01T00:00:00Z"),
+            ),
+            ("@1704067200", End, noon, Ok("2024-01-01T00:00:00Z")),
+            ("2024-01-01T25:00:00Z", Start, noon, Err(())),
+            ("2024-01-01T25:00", Start, noon, Err(())),
+            ("2024-01-01T18:30", Start, noon, Ok("2024-01-01T18:30:00Z")),
+            (
+                "2024-01-01 18:30:15",
+                Start,
+                noon,
+                Ok("2024-01-01T18:30:15Z"),
+            ),
+            ("1704067200", Start, noon, Err(())),
+            //Calendar dates, months and years
+            ("2024-01-31", Start, noon, Ok("2024-01-31T00:00:00Z")),
+            ("2024-01-31", End, noon, Ok("2024-01-31T23:59:59Z")),
+            ("2024-02-29", End, noon, Ok("2024-02-29T23:59:59Z")),
+            ("2023-02-29", Start, noon, Err(())),
+            ("2024-04-31", End, noon, Err(())),
+            ("2024-02", Start, noon, Ok("2024-02-01T00:00:00Z")),
+            ("2024-02", End, noon, Ok("2024-02-29T23:59:59Z")),
+            ("2023-02", End, noon, Ok("2023-02-28T23:59:59Z")),
+            ("2100-02", End, noon, Ok("2100-02-28T23:59:59Z")),
+            ("2000-02", End, noon, Ok("2000-02-29T23:59:59Z")),
+            ("2024-04", End, noon, Ok("2024-04-30T23:59:59Z")),
+            ("2024-12", End, noon, Ok("2024-12-31T23:59:59Z")),
+            //The last second, as a publish date within it is compared to the second
+            ("2024-12-31", End, noon, Ok("2024-12-31T23:59:59Z")),
+            ("2024", Start, noon, Ok("2024-01-01T00:00:00Z")),
+            ("2024", End, noon, Ok("2024-12-31T23:59:59Z")),
+            ("2024-13", Start, noon, Err(())),
+            ("2024-00", Start, noon, Err(())),
+            ("2024-01-00", Start, noon, Err(())),
+            ("2024-1-1", Start, noon, Err(())),
+            ("2024-01-01-01", Start, noon, Err(())),
+            ("24", Start, noon, Err(())),
+            ("12345", Start, noon, Err(())),
+            ("2024-", Start, noon, Err(())),
+            //Words
+            ("today", Start, noon, Ok("2024-03-31T00:00:00Z")),
+            ("today", End, noon, Ok("2024-03-31T23:59:59Z")),
+            ("yesterday", Start, noon, Ok("2024-03-30T00:00:00Z")),
+            ("yesterday", End, leap, Ok("2024-02-29T23:59:59Z")),
+            ("Today", Start, noon, Err(())),
+            //Relative
+            ("0d", Start, noon, Ok("2024-03-31T12:00:00Z")),
+            ("30d", Start, noon, Ok("2024-03-01T12:00:00Z")),
+            ("30d", End, noon, Ok("2024-03-01T12:00:00Z")),
+            ("1d", Start, leap, Ok("2024-02-29T08:30:00Z")),
+            ("2w", Start, noon, Ok("2024-03-17T12:00:00Z")),
+            ("1m", Start, noon, Ok("2024-02-29T12:00:00Z")),
+            ("13m", Start, noon, Ok("2023-02-28T12:00:00Z")),
+            ("6m", Start, noon, Ok("2023-09-30T12:00:00Z")),
+            ("1y", Start, noon, Ok("2023-03-31T12:00:00Z")),
+            (
+                "1y",
+                Start,
+                date("2024-02-29T00:00:00Z"),
+                Ok("2023-02-28T00:00:00Z"),
+            ),
+            (
+                "4y",
+                Start,
+                date("2024-02-29T00:00:00Z"),
+                Ok("2020-02-29T00:00:00Z"),
+            ),
+            ("99999999999y", Start, noon, Err(())),
+            ("999999999y", Start, noon, Err(())),
+            ("30x", Start, noon, Err(())),
+            ("d", Start, noon, Err(())),
+            ("-1d", Start, noon, Err(())),
+            ("1.5d", Start, noon, Err(())),
+            ("", Start, noon, Err(())),
+        ];
+
+        for (s, bound, now, r) in tests {
+            assert_eq!(
+                parse_date(s, bound, now, &Utc)
+                    .map(|d| d.date)
+                    .map_err(|_| ()),
+                r.map(date),
+                "input=\"{}\" bound={:?}",
+                s,
+                bound
+            );
+        }
+    }
+
+    #[test]
+    fn range_test() {
+        let date = |s| {
+            Some(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let now = date("2024-06-15T12:00:00Z").unwrap();
+        let tests = [
+            (None, None, Ok(None)),
+            (date("2024-01-01T00:00:00Z"), None, Ok(None)),
+            (None, date("2024-01-01T00:00:00Z"), Ok(None)),
+            (None, date("2025-01-01T00:00:00Z"), Ok(None)),
+            (
+                date("2024-01-01T00:00:00Z"),
+                date("2024-06-01T00:00:00Z"),
+                Ok(None),
+            ),
+            (
+                date("2024-06-01T00:00:00Z"),
+                date("2024-01-01T00:00:00Z"),
+                Err("is after the end date"),
+            ),
+            (
+                date("2024-06-01T00:00:01Z"),
+                date("2024-06-01T00:00:00Z"),
+                Err("is after the end date"),
+            ),
+            (
+                date("2024-06-01T00:00:00Z"),
+                date("2024-06-01T00:00:00Z"),
+                Ok(Some("Note: The start and end dates are the same instant")),
+            ),
+            (date("2024-07-01T00:00:00Z"), None, Ok(Some("Warning:"))),
+            (
+                date("2024-07-01T00:00:00Z"),
+                date("2024-08-01T00:00:00Z"),
+                Ok(Some("Warning: The date range lies entirely in the future")),
+            ),
+            (date("2024-06-15T12:00:00Z"), None, Ok(None)),
+        ];
+
+        for (start, end, result) in tests {
+            match (check_range(start, end, now), result) {
+                (Ok(None), Ok(None)) => (),
+                (Ok(Some(m)), Ok(Some(prefix))) => assert!(m.starts_with(prefix), "{}", m),
+                (Err(e), Err(msg)) => assert!(e.contains(msg), "{}", e),
+                (r, _) => panic!("start={:?} end={:?}: unexpected {:?}", start, end, r),
+            }
+        }
+    }
+
+    #[test]
+    fn timezone_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        //Daylight saving time in Budapest started on 2024-03-31 at 02:00 (to 03:00), and ended on 2024-10-27 at 03:00
+        let zone = chrono_tz::Europe::Budapest;
+        let now = date("2024-03-31T22:30:00Z");
+        use Bound::{End, Start};
+
+        let tests = [
+            (
+                "2024-01-31T18:00",
+                Start,
+                Ok(("2024-01-31T17:00:00Z", false)),
+            ),
+            (
+                "2024-07-31 18:00:00",
+                Start,
+                Ok(("2024-07-31T16:00:00Z", false)),
+            ),
+            (
+                "2024-03-31T01:59:59",
+                Start,
+                Ok(("2024-03-31T00:59:59Z", false)),
+            ),
+            (
+                "2024-03-31T02:30",
+                Start,
+                Ok(("2024-03-31T01:30:00Z", true)),
+            ),
+            (
+                "2024-03-31T03:00",
+                Start,
+                Ok(("2024-03-31T01:00:00Z", false)),
+            ),
+            ("2024-10-27T02:30", End, Ok(("2024-10-27T00:30:00Z", true))),
+            ("2024-10-27T03:30", End, Ok(("2024-10-27T02:30:00Z", false))),
+            //Offsets, epoch and relative dates are exact regardless
+            (
+                "2024-03-31T02:30:00Z",
+                Start,
+                Ok(("2024-03-31T02:30:00Z", false)),
+            ),
+            (
+                "2024-03-31T02:30:00+05:00",
+                Start,
+                Ok(("2024-03-30T21:30:00Z", false)),
+            ),
+            ("@1711848600", Start, Ok(("2024-03-31T01:30:00Z", false))),
+            ("1d", Start, Ok(("2024-03-30T22:30:00Z", false))),
+            //Local days, where 2024-03-31 is only 23 hours long
+            ("2024-03-31", Start, Ok(("2024-03-30T23:00:00Z", false))),
+            ("2024-03-31", End, Ok(("2024-03-31T21:59:59Z", false))),
+            ("2024-10", End, Ok(("2024-10-31T22:59:59Z", false))),
+            ("2024", Start, Ok(("2023-12-31T23:00:00Z", false))),
+            ("today", Start, Ok(("2024-03-31T22:00:00Z", false))),
+            ("yesterday", End, Ok(("2024-03-31T21:59:59Z", false))),
+            ("2024-03-31T24:00", Start, Err(())),
+        ];
+
+        for (s, bound, r) in tests {
+            let parsed = parse_date(s, bound, now, &zone);
+            assert_eq!(
+                parsed
+                    .map(|d| (d.date, d.warning.is_some()))
+                    .map_err(|_| ()),
+                r.map(|(d, w)| (date(d), w)),
+                "input=\"{}\" bound={:?}",
+                s,
+                bound
+            );
+        }
+    }
+
+    #[test]
+    fn published_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let tests = [
+            ("2024-01-31T18:00:00Z", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31T18:00:00+00:00", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31T19:00:00+01:00", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31T18:00:00.123Z", Some("2024-01-31T18:00:00.123Z")),
+            (
+                "2024-01-31T18:00:00.123456+00:00",
+                Some("2024-01-31T18:00:00.123456Z"),
+            ),
+            //Recoverable
+            (" 2024-01-31T18:00:00Z\n", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31 18:00:00Z", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31t18:00:00z", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31T18:00:00+0000", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31T18:00:00", Some("2024-01-31T18:00:00Z")),
+            ("2024-01-31T18:00:00.5", Some("2024-01-31T18:00:00.5Z")),
+            //Not a timestamp
+            ("", None),
+            ("yesterday", None),
+            ("2024-01-31", None),
+            ("2024-02-30T18:00:00Z", None),
+            ("2024-01-31T18:00Z", None),
+        ];
+        for (s, parsed) in tests {
+            assert_eq!(parse_published(s).ok(), parsed.map(date), "{:?}", s);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to express the total runtime in relatable units, for '--equivalent'
+
+    A reference is a name and the span of one unit, e.g. 40 hours for a work week. Custom references are given as a
+    comma-separated list of 'name=hours' pairs, e.g. "work weeks=40,lectures=1.5", the hours being positive and possibly
+    fractional.
+*/
+
+use chrono::TimeDelta;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub span: TimeDelta,
+}
+
+pub fn defaults() -> Vec<Reference> {
+    let hours = |name: &str, h: i64| Reference {
+        name: String::from(name),
+        span: TimeDelta::hours(h),
+    };
+    vec![
+        hours("8-hour work days", 8),
+        hours("40-hour work weeks", 40),
+        //A semester taken as 15 weeks
+        hours("semester courses at 3h/week", 3 * 15),
+    ]
+}
+
+pub fn parse_references(s: &str) -> Result<Vec<Reference>, String> {
+    let references = s
+        .split(',')
+        .filter(|r| !r.trim().is_empty())
+        .map(|r| {
+            let (name, hours) = r.split_once('=').ok_or(format!(
+                "Invalid reference '{}', expected 'name=hours'",
+                r.trim()
+            ))?;
+            let seconds = hours
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .map(|h| (h * 3600.0).round())
+                .filter(|s| s.is_finite() && *s >= 1.0 && *s <= i64::MAX as f64)
+                .ok_or(format!(
+                    "Invalid hours '{}' of reference '{}'",
+                    hours.trim(),
+                    name.trim()
+                ))?;
+            match name.trim() {
+                "" => Err(format!("Missing name of reference '{}'", r.trim())),
+                n => Ok(Reference {
+                    name: String::from(n),
+                    span: TimeDelta::seconds(seconds as i64),
+                }),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match references.is_empty() {
+        true => Err(String::from("No references given")),
+        false => Ok(references),
+    }
+}
+
+//How many of each reference the total makes, with the numbers aligned
+pub fn equivalent_table(total: TimeDelta, references: &[Reference]) -> String {
+    let counts: Vec<String> = references
+        .iter()
+        .map(|r| {
+            format!(
+                "{:.1}",
+                total.num_seconds() as f64 / r.span.num_seconds() as f64
+            )
+        })
+        .collect();
+    let width = counts.iter().map(|c| c.len()).max().unwrap_or(0);
+
+    let mut out = String::from("Equivalent to:\n");
+    for (count, r) in counts.iter().zip(references) {
+        out.push_str(&format!("  {:>w$} {}\n", count, r.name, w = width));
+    }
+    out
+}
+
+#[cfg(test)]
+mod equivalent_test {
+    use super::*;
+
+    #[test]
+    fn parse_references_test() {
+        let reference = |name: &str, seconds| Reference {
+            name: String::from(name),
+            span: TimeDelta::seconds(seconds),
+        };
+        let tests = [
+            (
+                "work weeks=40",
+                Ok(vec![reference("work weeks", 40 * 3600)]),
+            ),
+            (
+                " lectures = 1.5 ,movies=2,",
+                Ok(vec![reference("lectures", 5400), reference("movies", 7200)]),
+            ),
+            ("weeks", Err("expected 'name=hours'")),
+            ("=2", Err("Missing name")),
+            ("weeks=0", Err("Invalid hours '0'")),
+            ("weeks=-1", Err("Invalid hours '-1'")),
+            ("weeks=lots", Err("Invalid hours 'lots'")),
+            ("weeks=inf", Err("Invalid hours 'inf'")),
+            (",", Err("No references")),
+        ];
+        for (s, result) in tests {
+            match (parse_references(s), result) {
+                (Ok(r), Ok(expected)) => assert_eq!(r, expected, "{:?}", s),
+                (Err(e), Err(msg)) => assert!(e.contains(msg), "{:?}: {}", s, e),
+                (r, _) => panic!("{:?}: unexpected {:?}", s, r),
+            }
+        }
+    }
+
+    #[test]
+    fn equivalent_table_test() {
+        assert_eq!(
+            equivalent_table(TimeDelta::hours(92), &defaults()),
+            "Equivalent to:\n  11.5 8-hour work days\n   2.3 40-hour work weeks\n   2.0 semester courses at 3h/week\n"
+        );
+        assert_eq!(
+            equivalent_table(TimeDelta::zero(), &defaults()[..1]),
+            "Equivalent to:\n  0.0 8-hour work days\n"
+        );
+    }
+}
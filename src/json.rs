@@ -0,0 +1,249 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to write the videos as a JSON document
+
+    The document is an object with the run metadata (same fields as the metadata lines of the CSV output), the
+    'videos' array, and with the footer enabled, a 'summary' object. As a partial document would not be valid JSON
+    anyway, the rows are kept in memory and everything is written at the end.
+
+    The optional fields of a video (views, thumbnail_url, has_captions, definition, dimension and projection) are only
+    written if known, so no column options are needed as for the CSV output.
+*/
+
+use std::error::Error;
+use std::io::Write;
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::output::{Metadata, RowWriter};
+use crate::Video;
+
+pub struct Writer<W: Write> {
+    out: W,
+    metadata: Option<Value>,
+    videos: Vec<Value>,
+    total: TimeDelta,
+}
+impl<W: Write> Writer<W> {
+    pub fn new(out: W, metadata: Option<&Metadata>) -> Self {
+        Self {
+            out,
+            metadata: metadata.map(|m| {
+                Value::Object(
+                    m.fields()
+                        .into_iter()
+                        .map(|(k, v)| (String::from(k), Value::String(v)))
+                        .collect::<Map<_, _>>(),
+                )
+            }),
+            videos: Vec::new(),
+            total: TimeDelta::zero(),
+        }
+    }
+}
+impl<W: Write> RowWriter<W> for Writer<W> {
+    fn write(&mut self, video: &Video) -> Result<(), Box<dyn Error>> {
+        let mut row = json!({
+            "publishedAt": video.date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "title": video.title,
+            "title_raw": video.raw_title,
+            "videoId": video.id,
+            "duration": video.duration,
+            "duration_seconds": video.delta.num_seconds(),
+        });
+        if let Some(views) = video.views {
+            row["views"] = json!(views);
+        }
+        if let Some(ref url) = video.thumbnail_url {
+            row["thumbnail_url"] = json!(url);
+        }
+        if let Some(captions) = video.has_captions {
+            row["has_captions"] = json!(captions);
+        }
+        if let Some(definition) = video.definition {
+            row["definition"] = json!(definition.to_string());
+        }
+        if let Some(dimension) = video.dimension {
+            row["dimension"] = json!(dimension.to_string());
+        }
+        if let Some(projection) = video.projection {
+            row["projection"] = json!(projection.to_string());
+        }
+        self.videos.push(row);
+        self.total += video.delta;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, footer: Option<DateTime<Utc>>) -> Result<W, Box<dyn Error>> {
+        let mut doc = Map::new();
+        if let Some(m) = self.metadata.take() {
+            doc.insert(String::from("metadata"), m);
+        }
+        if let Some(generated) = footer {
+            doc.insert(
+                String::from("summary"),
+                json!({
+                    "video_count": self.videos.len(),
+                    "total_seconds": self.total.num_seconds(),
+                    "generated": generated.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    "version": crate::version(),
+                }),
+            );
+        }
+        doc.insert(
+            String::from("videos"),
+            Value::Array(std::mem::take(&mut self.videos)),
+        );
+
+        serde_json::to_writer_pretty(&mut self.out, &doc)?;
+        writeln!(self.out)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod json_test {
+    use super::*;
+    use crate::{Definition, Dimension, Projection};
+
+    fn write_json(
+        videos: &[Video],
+        metadata: Option<&Metadata>,
+        footer: Option<DateTime<Utc>>,
+    ) -> Value {
+        let mut writer = Box::new(Writer::new(Vec::new(), metadata));
+        for video in videos {
+            writer.write(video).unwrap();
+        }
+        serde_json::from_slice(&writer.finish(footer).unwrap()).unwrap()
+    }
+
+    fn date(s: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap())
+    }
+
+    #[test]
+    fn document_test() {
+        let metadata = Metadata {
+            generated: date("2024-12-27T17:00:00Z"),
+            channel: String::from("foo"),
+            channel_id: String::from("UCxyz"),
+            playlist_id: String::from("UUxyz"),
+            start: Some(date("2024-01-01T00:00:00Z")),
+            end: None,
+            channel_videos: 5,
+            matched_videos: 2,
+        };
+        let videos = [
+            Video::new(
+                date("2024-01-02T10:00:00Z"),
+                "Plain".into(),
+                "id0".into(),
+                "PT1M".into(),
+            )
+            .unwrap(),
+            Video::new(
+                date("2024-02-03T11:22:33Z"),
+                "Other".into(),
+                "id1".into(),
+                "PT1H".into(),
+            )
+            .unwrap(),
+        ];
+
+        let doc = write_json(&videos, Some(&metadata), Some(date("2024-12-27T17:00:00Z")));
+        let mut keys: Vec<&String> = doc.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["metadata", "summary", "videos"]);
+        assert_eq!(doc["metadata"]["channel"], "@foo");
+        assert_eq!(doc["metadata"]["start"], "2024-01-01T00:00:00Z");
+        assert_eq!(doc["metadata"]["end"], "-");
+        assert_eq!(doc["metadata"]["matched_video_count"], "2");
+        assert_eq!(doc["summary"]["video_count"], 2);
+        assert_eq!(doc["summary"]["total_seconds"], 3660);
+        assert_eq!(doc["summary"]["generated"], "2024-12-27T17:00:00Z");
+        assert_eq!(
+            doc["videos"][1],
+            json!({
+                "publishedAt": "2024-02-03T11:22:33Z",
+                "title": "Other",
+                "title_raw": "Other",
+                "videoId": "id1",
+                "duration": "PT1H",
+                "duration_seconds": 3600,
+            })
+        );
+
+        let doc = write_json(&videos, None, None);
+        assert_eq!(
+            doc.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["videos"]
+        );
+    }
+
+    #[test]
+    fn optional_fields_test() {
+        let video = Video {
+            views: Some(1234),
+            thumbnail_url: Some(String::from("https://i.ytimg.com/vi/id0/default.jpg")),
+            has_captions: Some(false),
+            definition: Some(Definition::Hd),
+            dimension: Some(Dimension::TwoD),
+            projection: Some(Projection::Spherical),
+            ..Video::new(
+                date("2024-01-02T10:00:00Z"),
+                "Plain".into(),
+                "id0".into(),
+                "PT1M".into(),
+            )
+            .unwrap()
+        };
+        let plain = Video::new(
+            date("2024-01-02T10:00:00Z"),
+            "Plain".into(),
+            "id1".into(),
+            "PT1M".into(),
+        )
+        .unwrap();
+
+        let doc = write_json(&[video, plain], None, None);
+        let tests = [
+            ("views", json!(1234)),
+            (
+                "thumbnail_url",
+                json!("https://i.ytimg.com/vi/id0/default.jpg"),
+            ),
+            ("has_captions", json!(false)),
+            ("definition", json!("hd")),
+            ("dimension", json!("2d")),
+            ("projection", json!("360")),
+        ];
+        for (key, known) in tests {
+            assert_eq!(doc["videos"][0][key], known, "{}", key);
+            assert!(!doc["videos"][1].as_object().unwrap().contains_key(key));
+        }
+    }
+
+    #[test]
+    fn empty_test() {
+        let generated = date("2024-12-27T17:00:00Z");
+        let doc = write_json(&[], None, Some(generated));
+        assert_eq!(doc["videos"], json!([]));
+        assert_eq!(doc["summary"]["video_count"], 0);
+        assert_eq!(doc["summary"]["total_seconds"], 0);
+
+        let mut out = Box::new(Writer::new(Vec::new(), None))
+            .finish(None)
+            .unwrap();
+        out.retain(|c| !c.is_ascii_whitespace());
+        assert_eq!(String::from_utf8(out).unwrap(), r#"{"videos":[]}"#);
+    }
+}
@@ -0,0 +1,441 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to locate and read the API key file
+
+    Unless a path is given, the first existing one of these is used:
+    - $XDG_CONFIG_HOME/yt-api-videosum/key.txt (~/.config/yt-api-videosum/key.txt if the variable is not set)
+    - config/key.txt relative to the working directory, as in earlier versions
+
+    The file either contains a single key on its first line (anything after whitespace is ignored), or one 'name = KEY'
+    profile per line. In both cases, empty lines and lines starting with '#' are ignored, as are a leading UTF-8 byte
+    order mark and the line endings of any platform. A profile is selected by name, which may be omitted if there is
+    only one, or one called 'default'.
+
+    A key not looking like a Google API key (39 characters starting with 'AIza') is only warned about, as the API is
+    the one to tell.
+*/
+
+use std::path::{Path, PathBuf};
+
+pub const LEGACY_PATH: &str = "config/key.txt";
+
+//Room for a few comment lines besides the key
+const MAX_SINGLE_LEN: u64 = 4 * 1024;
+const MAX_PROFILES_LEN: u64 = 64 * 1024;
+
+pub struct LoadedKey {
+    pub key: String,
+    pub profile: Option<String>,
+    pub warnings: Vec<String>,
+}
+
+//The directory of the program in the config home, also used for the state file
+pub fn config_dir(xdg_config_home: Option<&str>, home: Option<&str>) -> Option<PathBuf> {
+    //Relative values are to be ignored as per the XDG Base Directory Specification
+    let config_home = match xdg_config_home {
+        Some(d) if Path::new(d).is_absolute() => PathBuf::from(d),
+        _ => PathBuf::from(home.filter(|h| !h.is_empty())?).join(".config"),
+    };
+    Some(config_home.join("yt-api-videosum"))
+}
+
+//Paths to look for the key file at, in order
+pub fn candidates(xdg_config_home: Option<&str>, home: Option<&str>) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(d) = config_dir(xdg_config_home, home) {
+        paths.push(d.join("key.txt"));
+    }
+    paths.push(PathBuf::from(LEGACY_PATH));
+    paths
+}
+
+pub fn locate(explicit: Option<&str>, candidates: &[PathBuf]) -> Result<PathBuf, String> {
+    if let Some(p) = explicit {
+        return Ok(PathBuf::from(p));
+    }
+    match candidates.iter().find(|p| p.exists()) {
+        Some(p) => Ok(p.clone()),
+        None => Err(format!(
+            "No API key supplied, and no key file found at {}",
+            candidates
+                .iter()
+                .map(|p| format!("'{}'", p.display()))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        )),
+    }
+}
+
+pub fn read_key(path: &Path, profile: Option<&str>) -> Result<LoadedKey, String> {
+    let name = path.display();
+    let meta = std::fs::metadata(path)
+        .map_err(|e| format!("Could not open key file '{}': {}", name, e))?;
+    if !meta.is_file() {
+        return Err(format!("Key file '{}' is not a regular file", name));
+    }
+    if meta.len() == 0 {
+        return Err(format!("Key file '{}' is empty", name));
+    }
+    if meta.len() > MAX_PROFILES_LEN {
+        return Err(format!(
+            "Key file '{}' looks too large to only contain keys [len={}]",
+            name,
+            meta.len()
+        ));
+    }
+    let s = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read key file '{}': {}", name, e))?;
+    //E.g. as saved by some Windows editors
+    let s = s.strip_prefix('\u{feff}').unwrap_or(&s);
+
+    let mut warnings = Vec::new();
+    if let Some(w) = permission_warning(&meta, path) {
+        warnings.push(w);
+    }
+
+    let (key, profile) = match is_profiles(s) {
+        true => {
+            let (name, key) = select_profile(&parse_profiles(s, path)?, profile, path)?;
+            (key, Some(name))
+        }
+        false => {
+            if let Some(p) = profile {
+                return Err(format!(
+                    "Key file '{}' contains a single key without profiles, cannot select '{}'",
+                    name, p
+                ));
+            }
+            if meta.len() >= MAX_SINGLE_LEN {
+                return Err(format!(
+                    "Key file '{}' looks too large to only contain the key [len={}]",
+                    name,
+                    meta.len()
+                ));
+            }
+            let first = s
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty() && !l.starts_with('#'));
+            let key = match first {
+                Some(l) => l.split(char::is_whitespace).next().unwrap_or_default(),
+                None => return Err(format!("Key file '{}' has no key", name)),
+            };
+            (String::from(key), None)
+        }
+    };
+    if let Some(w) = shape_warning(&key) {
+        warnings.push(format!("{} in key file '{}'", w, name));
+    }
+    Ok(LoadedKey {
+        key,
+        profile,
+        warnings,
+    })
+}
+
+//Of a key not in the usual format, e.g. one copied only partially
+fn shape_warning(key: &str) -> Option<String> {
+    let alphabet = key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    (key.len() != 39 || !key.starts_with("AIza") || !alphabet).then(|| {
+        String::from(
+            "The API key does not look like one (expected 39 letters, digits, '-' or '_' starting with 'AIza')",
+        )
+    })
+}
+
+//API keys never contain '=', so any such line means the profile format
+fn is_profiles(s: &str) -> bool {
+    s.lines()
+        .map(str::trim)
+        .any(|l| !l.starts_with('#') && l.contains('='))
+}
+
+fn parse_profiles(s: &str, path: &Path) -> Result<Vec<(String, String)>, String> {
+    let mut profiles = Vec::<(String, String)>::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, key) = match line.split_once('=') {
+            Some((n, k)) if !n.trim().is_empty() && !k.trim().is_empty() => (n.trim(), k.trim()),
+            _ => {
+                return Err(format!(
+                    "Invalid line {} in key file '{}', expected 'name = KEY'",
+                    i + 1,
+                    path.display()
+                ))
+            }
+        };
+        if profiles.iter().any(|(n, _)| n == name) {
+            return Err(format!(
+                "Profile '{}' is given more than once in key file '{}'",
+                name,
+                path.display()
+            ));
+        }
+        profiles.push((String::from(name), String::from(key)));
+    }
+    Ok(profiles)
+}
+
+fn select_profile(
+    profiles: &[(String, String)],
+    profile: Option<&str>,
+    path: &Path,
+) -> Result<(String, String), String> {
+    let names = || {
+        profiles
+            .iter()
+            .map(|(n, _)| format!("'{}'", n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let found = match profile {
+        Some(p) => profiles.iter().find(|(n, _)| n == p).ok_or(format!(
+            "No profile '{}' in key file '{}', found {}",
+            p,
+            path.display(),
+            names()
+        ))?,
+        None => match profiles {
+            [only] => only,
+            _ => profiles
+                .iter()
+                .find(|(n, _)| n == "default")
+                .ok_or(format!(
+                    "Key file '{}' has several profiles ({}), select one with '--key-profile'",
+                    path.display(),
+                    names()
+                ))?,
+        },
+    };
+    Ok(found.clone())
+}
+
+#[cfg(unix)]
+fn permission_warning(meta: &std::fs::Metadata, path: &Path) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    (meta.permissions().mode() & 0o004 != 0).then(|| {
+        format!(
+            "Key file '{}' is readable by all users, consider restricting it (e.g. 'chmod 600')",
+            path.display()
+        )
+    })
+}
+
+#[cfg(not(unix))]
+fn permission_warning(_meta: &std::fs::Metadata, _path: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod keyfile_test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "yt_api_videosum_key_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn candidates_test() {
+        let legacy = PathBuf::from(LEGACY_PATH);
+        let tests = [
+            (
+                Some("/xdg"),
+                Some("/home/u"),
+                vec![
+                    PathBuf::from("/xdg/yt-api-videosum/key.txt"),
+                    legacy.clone(),
+                ],
+            ),
+            (
+                None,
+                Some("/home/u"),
+                vec![
+                    PathBuf::from("/home/u/.config/yt-api-videosum/key.txt"),
+                    legacy.clone(),
+                ],
+            ),
+            (
+                Some("relative"),
+                Some("/home/u"),
+                vec![
+                    PathBuf::from("/home/u/.config/yt-api-videosum/key.txt"),
+                    legacy.clone(),
+                ],
+            ),
+            (None, Some(""), vec![legacy.clone()]),
+            (None, None, vec![legacy.clone()]),
+        ];
+
+        for (xdg, home, paths) in tests {
+            assert_eq!(
+                candidates(xdg, home),
+                paths,
+                "xdg={:?} home={:?}",
+                xdg,
+                home
+            );
+        }
+    }
+
+    #[test]
+    fn locate_test() {
+        let dir = temp_dir("locate");
+        let first = dir.join("first.txt");
+        let second = dir.join("second.txt");
+        let paths = [first.clone(), second.clone()];
+
+        assert!(locate(None, &paths).unwrap_err().contains("second.txt'"));
+        assert_eq!(
+            locate(Some("given.txt"), &paths),
+            Ok(PathBuf::from("given.txt"))
+        );
+
+        std::fs::write(&second, "KEY2").unwrap();
+        assert_eq!(locate(None, &paths), Ok(second.clone()));
+        std::fs::write(&first, "KEY1").unwrap();
+        assert_eq!(locate(None, &paths), Ok(first.clone()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn read_test() {
+        let dir = temp_dir("read");
+        let path = dir.join("key.txt");
+        let tests = [
+            ("KEY\n", None, Ok(("KEY", None))),
+            ("  KEY comment\nsecond line\n", None, Ok(("KEY", None))),
+            ("KEY", Some("work"), Err("cannot select 'work'")),
+            ("\nKEY", None, Ok(("KEY", None))),
+            ("KEY\r\n", None, Ok(("KEY", None))),
+            ("\u{feff}KEY\n", None, Ok(("KEY", None))),
+            ("# My key\n\n  KEY\t\r\n", None, Ok(("KEY", None))),
+            ("# work = KEY1\nKEY2\n", None, Ok(("KEY2", None))),
+            ("# No key yet\n\r\n", None, Err("has no key")),
+            (
+                "\u{feff}work = KEY1\r\nhome = KEY2\r\n",
+                Some("work"),
+                Ok(("KEY1", Some("work"))),
+            ),
+            ("work = KEY1\n", None, Ok(("KEY1", Some("work")))),
+            (
+                "# Keys\nwork = KEY1\n\ndefault=KEY2\n",
+                None,
+                Ok(("KEY2", Some("default"))),
+            ),
+            (
+                "work = KEY1\nhome = KEY2\n",
+                Some("home"),
+                Ok(("KEY2", Some("home"))),
+            ),
+            (
+                "work = KEY1\nhome = KEY2\n",
+                None,
+                Err("several profiles ('work', 'home')"),
+            ),
+            ("work = KEY1\n", Some("home"), Err("No profile 'home'")),
+            ("work = KEY1\nKEY2\n", None, Err("Invalid line 2")),
+            ("work = \n", None, Err("Invalid line 1")),
+            ("a = KEY1\na = KEY2\n", None, Err("more than once")),
+        ];
+
+        for (content, profile, result) in tests {
+            std::fs::write(&path, content).unwrap();
+            match (read_key(&path, profile), result) {
+                (Ok(k), Ok((key, p))) => {
+                    assert_eq!(
+                        (k.key.as_str(), k.profile.as_deref()),
+                        (key, p),
+                        "{:?}",
+                        content
+                    )
+                }
+                (Err(e), Err(msg)) => assert!(e.contains(msg), "{:?}: {}", content, e),
+                (Ok(k), Err(_)) => panic!("{:?}: unexpected key '{}'", content, k.key),
+                (Err(e), Ok(_)) => panic!("{:?}: unexpected error: {}", content, e),
+            }
+        }
+
+        std::fs::write(&path, "").unwrap();
+        assert!(read_key(&path, None).err().unwrap().contains("is empty"));
+        std::fs::write(&path, "K".repeat(5000)).unwrap();
+        assert!(read_key(&path, None).err().unwrap().contains("too large"));
+        let profiles = (0..10)
+            .map(|i| format!("p{} = {}\n", i, "K".repeat(39)))
+            .collect::<String>();
+        std::fs::write(&path, profiles).unwrap();
+        assert_eq!(read_key(&path, Some("p9")).unwrap().key, "K".repeat(39));
+        assert!(read_key(&dir, None)
+            .err()
+            .unwrap()
+            .contains("not a regular file"));
+        assert!(read_key(&dir.join("missing.txt"), None).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shape_test() {
+        let dir = temp_dir("shape");
+        let path = dir.join("key.txt");
+        let key = format!("AIza{}", "Sy-_0aB".repeat(5));
+        let tests = [
+            (format!("{}\r\n", key), false),
+            (format!("\u{feff}# Comment\n{}\n", key), false),
+            (format!("default = {}\n", key), false),
+            (String::from("KEY\n"), true),
+            (format!("{}x\n", key), true),
+            (format!("BIza{}\n", &key[4..]), true),
+            (format!("{}.\n", &key[..38]), true),
+        ];
+        for (content, warned) in tests {
+            std::fs::write(&path, &content).unwrap();
+            let warnings = read_key(&path, None).unwrap().warnings;
+            assert_eq!(
+                warnings.iter().any(|w| w.contains("does not look like")),
+                warned,
+                "{:?}: {:?}",
+                content,
+                warnings
+            );
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permission_test() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("mode");
+        let path = dir.join("key.txt");
+        std::fs::write(&path, format!("AIza{}", "K".repeat(35))).unwrap();
+        for (mode, warned) in [(0o644, true), (0o600, false), (0o640, false)] {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+            let warnings = read_key(&path, None).unwrap().warnings;
+            assert_eq!(warnings.len(), warned as usize, "mode={:o}", mode);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
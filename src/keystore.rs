@@ -0,0 +1,173 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to keep the API key in the keyring of the OS (with the 'keyring' feature only)
+
+    The key is stored under the SERVICE service, with the key profile as the user ('default' if not given). It is looked
+    up after the command line, and before the key file. If the keyring cannot be reached (e.g. no Secret Service daemon
+    is running), the key file is tried instead with a warning. Without the feature, the keyring is never looked at.
+*/
+
+use std::error::Error;
+
+use crate::Status;
+
+pub const ENABLED: bool = cfg!(feature = "keyring");
+pub const SERVICE: &str = "yt-api-videosum";
+
+#[cfg(feature = "keyring")]
+fn user(profile: Option<&str>) -> &str {
+    profile.unwrap_or("default")
+}
+
+//None if there is no key stored
+#[cfg(feature = "keyring")]
+pub fn get(profile: Option<&str>) -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(SERVICE, user(profile)).map_err(|e| e.to_string())?;
+    match entry.get_password() {
+        Ok(k) => Ok(Some(k)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn get(_profile: Option<&str>) -> Result<Option<String>, String> {
+    Ok(None)
+}
+
+#[cfg(feature = "keyring")]
+pub fn store(profile: Option<&str>, key: &str) -> Result<(), String> {
+    keyring::Entry::new(SERVICE, user(profile))
+        .and_then(|entry| entry.set_password(key))
+        .map_err(|e| format!("Could not store the key in the keyring: {}", e))
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn store(_profile: Option<&str>, _key: &str) -> Result<(), String> {
+    Err(String::from(
+        "This build has no keyring support, rebuild it with '--features keyring'",
+    ))
+}
+
+//The key and where it came from, trying the sources in order
+pub fn resolve<K, F>(
+    command_line: Option<String>,
+    keyring: K,
+    file: F,
+    status: Status,
+) -> Result<(String, String), Box<dyn Error>>
+where
+    K: FnOnce() -> Result<Option<String>, String>,
+    F: FnOnce() -> Result<(String, String), Box<dyn Error>>,
+{
+    if let Some(k) = command_line {
+        return Ok((k, String::from("command line")));
+    }
+    if ENABLED {
+        match keyring() {
+            Ok(Some(k)) => return Ok((k, String::from("keyring"))),
+            Ok(None) => (),
+            Err(e) => status.print(format_args!(
+                "Warning: Could not read the keyring, trying the key file: {}\n",
+                e
+            )),
+        }
+    }
+    file()
+}
+
+//Without echoing it on a terminal, so the key does not stay on the screen
+pub fn read_hidden(prompt: &str, status: Status) -> Result<String, String> {
+    use std::io::IsTerminal;
+
+    status.print(format_args!("{} ", prompt));
+    let terminal = std::io::stdin().is_terminal();
+    let stty = |arg: &str| {
+        //Best effort, e.g. there is no 'stty' on Windows
+        let _ = std::process::Command::new("stty")
+            .arg(arg)
+            .stdin(std::process::Stdio::inherit())
+            .status();
+    };
+    if terminal {
+        stty("-echo");
+    }
+    let mut line = String::new();
+    let result = std::io::stdin().read_line(&mut line);
+    if terminal {
+        stty("echo");
+        status.print(format_args!("\n"));
+    }
+    result.map_err(|e| format!("Could not read the key: {}", e))?;
+
+    match line.trim() {
+        "" => Err(String::from("No key given")),
+        k => Ok(String::from(k)),
+    }
+}
+
+#[cfg(test)]
+mod keystore_test {
+    use super::*;
+
+    #[test]
+    fn resolve_test() {
+        let file = || Ok((String::from("FILEKEY"), String::from("file")));
+        let tests = [
+            (
+                Some("CLIKEY"),
+                Ok(Some("RINGKEY")),
+                "CLIKEY",
+                "command line",
+            ),
+            (None, Ok(None), "FILEKEY", "file"),
+            (None, Err("no daemon"), "FILEKEY", "file"),
+            (
+                None,
+                Ok(Some("RINGKEY")),
+                if ENABLED { "RINGKEY" } else { "FILEKEY" },
+                if ENABLED { "keyring" } else { "file" },
+            ),
+        ];
+        for (command_line, keyring, key, source) in tests {
+            let looked = std::cell::Cell::new(false);
+            let resolved = resolve(
+                command_line.map(String::from),
+                || {
+                    looked.set(true);
+                    keyring.map(|k| k.map(String::from)).map_err(String::from)
+                },
+                file,
+                Status::Stderr,
+            )
+            .unwrap();
+            assert_eq!(
+                (resolved.0.as_str(), resolved.1.as_str()),
+                (key, source),
+                "{:?} {:?}",
+                command_line,
+                keyring
+            );
+            //Only between the command line and the file, and only with the feature
+            assert_eq!(looked.get(), ENABLED && command_line.is_none());
+        }
+
+        let failed = resolve(None, || Ok(None), || Err("no file".into()), Status::Stderr);
+        assert_eq!(failed.err().unwrap().to_string(), "no file");
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    #[test]
+    fn disabled_test() {
+        assert_eq!(get(None), Ok(None));
+        assert!(store(None, "KEY")
+            .unwrap_err()
+            .contains("--features keyring"));
+    }
+}
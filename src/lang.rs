@@ -0,0 +1,88 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to localize the human-readable total
+
+    Only the unit words and their plural rules are localized, all other messages stay in English.
+*/
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+    Es,
+    Fr,
+    Hu,
+}
+impl std::str::FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Lang::En),
+            "de" => Ok(Lang::De),
+            "es" => Ok(Lang::Es),
+            "fr" => Ok(Lang::Fr),
+            "hu" => Ok(Lang::Hu),
+            _ => Err(format!(
+                "Invalid language '{}', expected one of 'en', 'de', 'es', 'fr', 'hu'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Unit {
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl Lang {
+    pub(crate) fn unit(self, unit: Unit, count: i64) -> &'static str {
+        //(singular, plural)
+        let (one, other) = match (self, unit) {
+            (Lang::En, Unit::Day) => ("day", "days"),
+            (Lang::En, Unit::Hour) => ("hour", "hours"),
+            (Lang::En, Unit::Minute) => ("minute", "minutes"),
+            (Lang::En, Unit::Second) => ("second", "seconds"),
+            (Lang::De, Unit::Day) => ("Tag", "Tage"),
+            (Lang::De, Unit::Hour) => ("Stunde", "Stunden"),
+            (Lang::De, Unit::Minute) => ("Minute", "Minuten"),
+            (Lang::De, Unit::Second) => ("Sekunde", "Sekunden"),
+            (Lang::Es, Unit::Day) => ("día", "días"),
+            (Lang::Es, Unit::Hour) => ("hora", "horas"),
+            (Lang::Es, Unit::Minute) => ("minuto", "minutos"),
+            (Lang::Es, Unit::Second) => ("segundo", "segundos"),
+            (Lang::Fr, Unit::Day) => ("jour", "jours"),
+            (Lang::Fr, Unit::Hour) => ("heure", "heures"),
+            (Lang::Fr, Unit::Minute) => ("minute", "minutes"),
+            (Lang::Fr, Unit::Second) => ("seconde", "secondes"),
+            //Hungarian does not use the plural after numerals
+            (Lang::Hu, Unit::Day) => ("nap", "nap"),
+            (Lang::Hu, Unit::Hour) => ("óra", "óra"),
+            (Lang::Hu, Unit::Minute) => ("perc", "perc"),
+            (Lang::Hu, Unit::Second) => ("másodperc", "másodperc"),
+        };
+        match self.is_singular(count) {
+            true => one,
+            false => other,
+        }
+    }
+
+    fn is_singular(self, count: i64) -> bool {
+        match self {
+            //French treats zero as singular too
+            Lang::Fr => count == 0 || count == 1,
+            _ => count == 1,
+        }
+    }
+}
@@ -9,30 +9,390 @@
 use std::error::Error;
 use std::fmt::Display;
 use std::fs::File;
-use std::io::{Seek, Write};
+use std::io::Write;
 
-use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use chrono::{DateTime, SecondsFormat, SubsecRound, TimeDelta, Utc};
 
+pub mod cache;
+pub mod color;
+mod csv;
+pub mod dates;
+pub mod equivalent;
+mod json;
+pub mod keyfile;
+pub mod keystore;
+pub mod lang;
+mod m3u;
+mod markdown;
+pub mod output;
 mod period;
+mod progress;
+pub mod report;
+pub mod state;
+pub mod template;
+mod title;
+pub mod verbose;
+pub mod wizard;
+
+pub use crate::csv::read_csv;
+pub use crate::period::format_delta_iso;
+pub use crate::title::{TitleFilter, TitleOptions};
+
+//Crate version, with the git commit hash if it was available at build time
+pub fn version() -> &'static str {
+    env!("YT_API_VIDEOSUM_VERSION")
+}
+
+//Where the progress and summary messages go, e.g. to keep stdout for machine-readable output
+//
+//With Log, each complete line becomes a log record instead, a warning if it starts with "Warning: ", so they can be
+//filtered by level. Prompts and the total are still printed, to stderr if that is where the messages would go.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Stdout,
+    Stderr,
+    Log { stderr: bool },
+}
+impl Status {
+    pub fn print(self, args: std::fmt::Arguments) {
+        match self {
+            Status::Stdout => {
+                let mut out = std::io::stdout();
+                let _ = match crate::color::stdout() {
+                    true => out.write_all(crate::color::paint_lines(&args.to_string()).as_bytes()),
+                    false => out.write_fmt(args),
+                };
+                let _ = out.flush();
+            }
+            Status::Stderr => {
+                let _ = match crate::color::stderr() {
+                    true => std::io::stderr()
+                        .write_all(crate::color::paint_lines(&args.to_string()).as_bytes()),
+                    false => std::io::stderr().write_fmt(args),
+                };
+            }
+            Status::Log { .. } => LOG_LINE.with_borrow_mut(|line| {
+                let _ = std::fmt::Write::write_fmt(line, args);
+                while let Some(i) = line.find('\n') {
+                    let rest = line.split_off(i + 1);
+                    log_line(line.trim_end());
+                    *line = rest;
+                }
+            }),
+        }
+    }
+
+    //For the messages that are not diagnostics, i.e. the prompts and the total
+    pub fn direct(self) -> Status {
+        match self {
+            Status::Log { stderr: true } => Status::Stderr,
+            Status::Log { stderr: false } => Status::Stdout,
+            s => s,
+        }
+    }
+
+    //Stderr instead of stdout, so redirecting stdout captures the messages without the progress
+    pub fn progress(self) -> Status {
+        match self {
+            Status::Stdout => Status::Stderr,
+            s => s,
+        }
+    }
+
+    pub fn is_terminal(self) -> bool {
+        use std::io::IsTerminal;
+
+        match self {
+            Status::Stdout => std::io::stdout().is_terminal(),
+            Status::Stderr => std::io::stderr().is_terminal(),
+            //A progress bar would be a record for each redraw
+            Status::Log { .. } => false,
+        }
+    }
+}
+
+thread_local! {
+    //Text printed since the last complete line, e.g. "Querying channel info..." before the result
+    static LOG_LINE: std::cell::RefCell<String> = const { std::cell::RefCell::new(String::new()) };
+}
+
+fn log_line(line: &str) {
+    match line.strip_prefix("Warning: ") {
+        Some(w) => log::warn!("{}", w),
+        None if line.is_empty() => (),
+        None => log::info!("{}", line),
+    }
+}
+
+macro_rules! status {
+    ($status:expr, $($arg:tt)*) => {
+        $status.print(format_args!($($arg)*))
+    };
+}
+macro_rules! statusln {
+    ($status:expr) => {
+        $status.print(format_args!("\n"))
+    };
+    ($status:expr, $($arg:tt)*) => {
+        $status.print(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+pub type Confirm = Box<dyn Fn(&Estimate) -> bool>;
+
+//Index of the chosen candidate, or None to abort
+pub type Pick = Box<dyn Fn(&[Candidate]) -> Option<usize>>;
 
 pub struct Config {
-    pub key: String,
+    pub key: ApiKey,
     pub channel_name: String,
     pub start_date: Option<DateTime<Utc>>,
     pub end_date: Option<DateTime<Utc>>,
     pub output: Option<File>,
+    //Where the output file is, for the messages
+    pub destination: crate::output::Destination,
+    pub compress: bool,
+    pub format: crate::output::Format,
+    pub footer: bool,
+    pub sort: Option<SortKey>,
+    pub sort_desc: bool,
+    //The videos are only written to the output as they are fetched, not kept for the summary, for a constant memory use
+    pub stream: bool,
+    pub summary_only_if_nonempty: bool,
+    pub status: Status,
+    pub lang: crate::lang::Lang,
+    pub date_format: Option<crate::output::DateFormat>,
+    pub titles: TitleOptions,
+    pub count_only: bool,
+    pub with_stats: bool,
+    //The thumbnail to keep the URL of, in a trailing 'thumbnail_url' column
+    pub thumbnail: Option<ThumbnailQuality>,
+    pub template: Option<crate::template::Template>,
+    pub client: Box<dyn HttpClient>,
+    pub buckets_output: Option<File>,
+    //The videos left out and why, as JSON if so given or as CSV otherwise
+    pub skipped_output: Option<(crate::output::Format, File)>,
+    //Months with no videos are included between the first and the last one if 'fill_gaps' is set
+    pub months_output: Option<File>,
+    pub fill_gaps: bool,
+    pub extra_outputs: Vec<crate::output::ExtraOutput>,
+    pub cache: Option<crate::cache::ChannelCache>,
+    //Playlists to sum instead of the public uploads of the channel
+    pub playlists: Vec<String>,
+    //Asked with the estimate before fetching the videos, the run is aborted if it returns false
+    pub confirm: Option<Confirm>,
+    //Asked if the handle matches more than one channel (without 'first_match'), failing with the list otherwise
+    pub pick: Option<Pick>,
+    //The run is aborted before fetching the videos if it would cost more quota units in total
+    pub max_quota: Option<u64>,
+    //Fewer matching videos than this makes the run fail, after the outputs are written
+    pub min_videos: Option<usize>,
+    //The output file is the partial one of this, moved in place once written
+    pub pending: Option<crate::output::Pending>,
+    pub verbose: crate::verbose::Verbose,
+    //Display the video count and runtime by the hour of publishing, in the timezone of the date format
+    pub by_hour: bool,
+    //Use the first of several channels found for the handle, instead of stopping
+    pub first_match: bool,
+    //Stop if the channel found has a different handle than the one requested, e.g. taken over from another channel
+    pub verify_handle: bool,
+    //Videos not viewable in this region (ISO 3166-1 alpha-2 code) are left out
+    pub exclude_region_blocked: Option<String>,
+    //Videos not reported as public by their status are left out, e.g. an unlisted one still in the playlist
+    pub strict_public: bool,
+    //Videos not matching it by their title are left out
+    pub title_filter: Option<TitleFilter>,
+    //Re-read the CSV output once written, and warn if it does not add up to the total
+    pub verify_output: bool,
+    //Redraw a progress bar in place if the progress goes to a terminal, instead of printing a line at every tenth
+    pub progress_bar: bool,
+    pub retries: Retries,
+    //Display how many of the videos (and how much of the runtime) have captions
+    pub captions_report: bool,
+    //Add a 'has_captions' column to the CSV output
+    pub captions_column: bool,
+    //Display how many of the videos (and how much of the runtime) are in HD and in SD
+    pub quality_report: bool,
+    //Add a 'definition' column to the CSV output
+    pub definition_column: bool,
+    //Display the average number of videos and runtime per week, over the span they were published in
+    pub rate: bool,
+    //Of the durations computed for the reports, e.g. the content per week, while the total stays precise
+    pub round: Option<Rounding>,
+    //Display how many of the videos (and how much of the runtime) are 360° and 3D
+    pub immersive_report: bool,
+    //Add 'dimension' and 'projection' columns to the CSV output
+    pub immersive_columns: bool,
+    //Display the total runtime as a number of each of these
+    pub equivalent: Option<Vec<crate::equivalent::Reference>>,
+    //Record the newest video matched in the state file, if the run completes without warnings
+    pub state: Option<crate::state::LastRun>,
+    //Once set (e.g. on Ctrl-C), no more videos are requested, and the ones fetched so far are the result
+    pub cancel: Option<&'static std::sync::atomic::AtomicBool>,
+    //Videos of an earlier output, taken as they are instead of requesting them again
+    pub resume: Vec<Video>,
+    //Videos of an earlier output, to display the ones new and removed since, and the change of the runtime
+    pub diff: Option<Vec<Video>>,
+}
+
+impl Config {
+    //Defaults matching the command line interface without any options
+    pub fn new(key: String, channel_name: String) -> Self {
+        Self {
+            key: ApiKey::new(key),
+            channel_name,
+            start_date: None,
+            end_date: None,
+            output: None,
+            destination: crate::output::Destination::default(),
+            compress: false,
+            format: crate::output::Format::Csv,
+            footer: true,
+            sort: None,
+            sort_desc: false,
+            stream: false,
+            summary_only_if_nonempty: false,
+            status: Status::Stdout,
+            lang: crate::lang::Lang::En,
+            date_format: None,
+            titles: TitleOptions::default(),
+            count_only: false,
+            with_stats: false,
+            thumbnail: None,
+            template: None,
+            client: Box::new(UreqClient::default()),
+            buckets_output: None,
+            skipped_output: None,
+            months_output: None,
+            fill_gaps: false,
+            extra_outputs: Vec::new(),
+            cache: None,
+            playlists: Vec::new(),
+            confirm: None,
+            pick: None,
+            max_quota: None,
+            min_videos: None,
+            pending: None,
+            verbose: crate::verbose::Verbose::new(0),
+            by_hour: false,
+            first_match: false,
+            verify_handle: false,
+            exclude_region_blocked: None,
+            strict_public: false,
+            title_filter: None,
+            verify_output: false,
+            progress_bar: false,
+            retries: Retries::default(),
+            captions_report: false,
+            captions_column: false,
+            quality_report: false,
+            definition_column: false,
+            rate: false,
+            round: None,
+            immersive_report: false,
+            immersive_columns: false,
+            equivalent: None,
+            state: None,
+            cancel: None,
+            resume: Vec::new(),
+            diff: None,
+        }
+    }
+}
+
+//Unit the derived durations are rounded to, to the nearest one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    Second,
+    Minute,
+    Hour,
+}
+impl Rounding {
+    pub fn apply(self, delta: TimeDelta) -> TimeDelta {
+        let unit = match self {
+            Rounding::Second => 1,
+            Rounding::Minute => 60,
+            Rounding::Hour => 3600,
+        };
+        //Halves rounded up, counting the fraction of a second too
+        let millis = delta.num_milliseconds() + unit * 1000 / 2;
+        TimeDelta::seconds(millis.div_euclid(unit * 1000) * unit)
+    }
+}
+impl std::str::FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "second" => Ok(Rounding::Second),
+            "minute" => Ok(Rounding::Minute),
+            "hour" => Ok(Rounding::Hour),
+            _ => Err(format!(
+                "Invalid rounding '{}', expected 'second', 'minute' or 'hour'",
+                s
+            )),
+        }
+    }
+}
+impl Display for Rounding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rounding::Second => write!(f, "second"),
+            Rounding::Minute => write!(f, "minute"),
+            Rounding::Hour => write!(f, "hour"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Date,
+    Duration,
+    Title,
+}
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "date" => Ok(SortKey::Date),
+            "duration" => Ok(SortKey::Duration),
+            "title" => Ok(SortKey::Title),
+            _ => Err(format!(
+                "Invalid sort key '{}', expected 'date', 'duration' or 'title'",
+                s
+            )),
+        }
+    }
 }
 
 #[derive(Debug)]
-struct Video {
-    date: DateTime<Utc>,
-    title: String,
-    id: String,
-    duration: String,
-    delta: TimeDelta,
+pub struct Video {
+    pub date: DateTime<Utc>,
+    //Sanitized for display, see 'raw_title' for the one received
+    pub title: String,
+    //As received, only written to the JSON output where it cannot break the format
+    pub raw_title: String,
+    pub id: String,
+    pub duration: String,
+    pub delta: TimeDelta,
+    pub views: Option<u64>,
+    pub region_restriction: Option<RegionRestriction>,
+    //From 'contentDetails.caption', unknown if the response did not include it
+    pub has_captions: Option<bool>,
+    //From 'contentDetails.definition', unknown if the response did not include it
+    pub definition: Option<Definition>,
+    //From 'contentDetails.dimension', unknown if the response did not include it
+    pub dimension: Option<Dimension>,
+    //From 'contentDetails.projection', unknown if the response did not include it
+    pub projection: Option<Projection>,
+    //From 'status.privacyStatus' (e.g. 'public' or 'unlisted'), unknown if the response did not include it
+    pub privacy_status: Option<String>,
+    //From 'snippet.thumbnails', only kept if asked for
+    pub thumbnail_url: Option<String>,
 }
 impl Video {
-    fn new(
+    pub fn new(
         date: DateTime<Utc>,
         title: String,
         id: String,
@@ -42,20 +402,222 @@ impl Video {
             .ok_or("Could not parse 'duration' field")?;
         Ok(Self {
             date,
+            raw_title: title.clone(),
             title,
             id,
             duration,
             delta,
+            views: None,
+            region_restriction: None,
+            has_captions: None,
+            definition: None,
+            dimension: None,
+            projection: None,
+            privacy_status: None,
+            thumbnail_url: None,
         })
     }
 }
+impl Video {
+    pub fn url(&self) -> String {
+        format!("https://www.youtube.com/watch?v={}", self.id)
+    }
+}
+//Either the regions the video is only viewable in, or the ones it is blocked in, as ISO 3166-1 alpha-2 codes
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegionRestriction {
+    Allowed(Vec<String>),
+    Blocked(Vec<String>),
+}
+impl RegionRestriction {
+    pub fn blocks(&self, region: &str) -> bool {
+        let listed = |l: &Vec<String>| l.iter().any(|r| r.eq_ignore_ascii_case(region));
+        match self {
+            RegionRestriction::Allowed(l) => !listed(l),
+            RegionRestriction::Blocked(l) => listed(l),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Definition {
+    Hd,
+    Sd,
+}
+impl std::str::FromStr for Definition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hd" => Ok(Self::Hd),
+            "sd" => Ok(Self::Sd),
+            _ => Err(format!("Invalid definition '{}', expected 'hd' or 'sd'", s)),
+        }
+    }
+}
+impl Display for Definition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Definition::Hd => write!(f, "hd"),
+            Definition::Sd => write!(f, "sd"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dimension {
+    TwoD,
+    ThreeD,
+}
+impl std::str::FromStr for Dimension {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2d" => Ok(Self::TwoD),
+            "3d" => Ok(Self::ThreeD),
+            _ => Err(format!("Invalid dimension '{}', expected '2d' or '3d'", s)),
+        }
+    }
+}
+impl Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dimension::TwoD => write!(f, "2d"),
+            Dimension::ThreeD => write!(f, "3d"),
+        }
+    }
+}
+
+//Spherical for the 360° videos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Rectangular,
+    Spherical,
+}
+impl std::str::FromStr for Projection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rectangular" => Ok(Self::Rectangular),
+            "360" => Ok(Self::Spherical),
+            _ => Err(format!(
+                "Invalid projection '{}', expected 'rectangular' or '360'",
+                s
+            )),
+        }
+    }
+}
+impl Display for Projection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Projection::Rectangular => write!(f, "rectangular"),
+            Projection::Spherical => write!(f, "360"),
+        }
+    }
+}
+
+//Of the thumbnails in the response, from the smallest one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThumbnailQuality {
+    Default,
+    Medium,
+    High,
+    Maxres,
+}
+impl std::str::FromStr for ThumbnailQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::Default),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            "maxres" => Ok(Self::Maxres),
+            _ => Err(format!(
+                "Invalid thumbnail quality '{}', expected 'default', 'medium', 'high' or 'maxres'",
+                s
+            )),
+        }
+    }
+}
+impl Display for ThumbnailQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThumbnailQuality::Default => write!(f, "default"),
+            ThumbnailQuality::Medium => write!(f, "medium"),
+            ThumbnailQuality::High => write!(f, "high"),
+            ThumbnailQuality::Maxres => write!(f, "maxres"),
+        }
+    }
+}
+
+//The URL of the given quality, or of the next lower one present, e.g. 'maxres' is only there for HD uploads
+fn thumbnail_url(
+    thumbnails: &serde_json::Value,
+    quality: ThumbnailQuality,
+) -> Result<Option<String>, Box<dyn Error>> {
+    //'standard' is not offered, but still a fallback between 'maxres' and 'high'
+    let keys = ["default", "medium", "high", "standard", "maxres"];
+    let index = match quality {
+        ThumbnailQuality::Default => 0,
+        ThumbnailQuality::Medium => 1,
+        ThumbnailQuality::High => 2,
+        ThumbnailQuality::Maxres => 4,
+    };
+    for key in keys[..=index].iter().rev() {
+        if let Some(t) = thumbnails.get(key) {
+            let url = t
+                .get("url")
+                .and_then(|u| u.as_str())
+                .ok_or("Invalid 'thumbnails' format")?;
+            return Ok(Some(String::from(url)));
+        }
+    }
+    Ok(None)
+}
+
+//Why a video listed in the playlists is not in the total
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SkipReason {
+    //Not in the details response, e.g. deleted or made private since listed
+    Missing,
+    UnparseableDate,
+    UnparseableDuration,
+    RegionBlocked,
+    NotPublic,
+    TitleFilter,
+}
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Missing => write!(f, "missing"),
+            SkipReason::UnparseableDate => write!(f, "unparseable-date"),
+            SkipReason::UnparseableDuration => write!(f, "unparseable-duration"),
+            SkipReason::RegionBlocked => write!(f, "region-blocked"),
+            SkipReason::NotPublic => write!(f, "not-public"),
+            SkipReason::TitleFilter => write!(f, "title-filter"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skipped {
+    //'?' if not even the ID could be read
+    pub id: String,
+    pub reason: SkipReason,
+    //The message displayed, if any, e.g. the value not parsed
+    pub detail: String,
+}
+
 impl Display for Video {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{},{},{},{},{}",
             self.date.to_rfc3339_opts(SecondsFormat::Secs, true),
-            self.title,
+            crate::csv::escape(&self.title),
             self.id,
             self.duration,
             self.delta.num_seconds(),
@@ -72,454 +634,4766 @@ impl Display for Video {
     4) Aggregation
 */
 pub fn run(mut config: Config) -> Result<(), Box<dyn Error>> {
-    println!("Querying channel info...");
+    let status = config.status;
+    let min_videos = config.min_videos;
+    let reports = ReportOptions::take(&mut config);
 
-    let addr = format!("https://youtube.googleapis.com/youtube/v3/channels?part=id%2Csnippet%2Cstatistics%2CcontentDetails&forHandle={}&key={}",
-        config.channel_name, config.key);
+    let summary = run_collect(config)?;
 
-    let json = request(&addr)?;
-    write_out(&mut config.output, &json)?;
+    print_reports(&summary, &reports, status);
+    check_min_videos(&summary, min_videos)?;
+    check_warnings(&summary)
+}
 
-    let playlist_id = match json
-        .pointer("/pageInfo/totalResults")
-        .ok_or("Could not find 'totalResults' field")?
-        .as_u64()
-        .ok_or("Invalid 'totalResults' format")?
-    {
-        1 => json
-            .pointer("/items/0/contentDetails/relatedPlaylists/uploads")
-            .ok_or("Could not find 'uploads' id field")?
-            .as_str()
-            .ok_or("Invalid 'uploads' id format")?,
-        n => {
-            println!("Warning: More than one result ({})", n);
-            return Ok(());
+//The reports displayed after a run, taken from the config before run_collect() consumes it
+pub struct ReportOptions {
+    pub lang: crate::lang::Lang,
+    pub summary_only_if_nonempty: bool,
+    pub by_hour: Option<chrono_tz::Tz>,
+    pub captions: bool,
+    pub quality: bool,
+    pub immersive: bool,
+    pub rate: bool,
+    pub round: Option<Rounding>,
+    pub equivalent: Option<Vec<crate::equivalent::Reference>>,
+    pub diff: Option<Vec<Video>>,
+}
+impl ReportOptions {
+    //The previous videos are moved out, as they are only needed for the report
+    pub fn take(config: &mut Config) -> Self {
+        ReportOptions {
+            lang: config.lang,
+            summary_only_if_nonempty: config.summary_only_if_nonempty,
+            by_hour: config.by_hour.then(|| match config.date_format {
+                Some(ref f) => f.timezone(),
+                None => chrono_tz::Tz::UTC,
+            }),
+            captions: config.captions_report,
+            quality: config.quality_report,
+            immersive: config.immersive_report,
+            rate: config.rate,
+            round: config.round,
+            equivalent: config.equivalent.clone(),
+            diff: config.diff.take(),
         }
-    };
-
-    //Filtering to public only (ie. excluding shorts, live, private and unlisted) by replacing default "UU" prefix
-    let mut playlist_id_pub = String::new();
-    playlist_id_pub.push_str("UULF");
-    playlist_id_pub.push_str(&playlist_id[2..]);
-    println!("Playlist ID extracted.");
-
-    println!("Querying playlist...");
+    }
+}
 
-    let mut video_ids = Vec::<String>::new();
-    let mut next_page_token: Option<String> = None;
-    loop {
-        let addr = format!("https://youtube.googleapis.com/youtube/v3/playlistItems?part=id%2Csnippet&playlistId={}&maxResults=50&pageToken={}&key={}",
-            playlist_id_pub, next_page_token.unwrap_or_default(), config.key);
+//The summary and the additional reports asked for, in the order of run()
+pub fn print_reports(summary: &Summary, options: &ReportOptions, status: Status) {
+    //Keeping batch logs focused on channels that actually had content
+    if !summary.shown(options.summary_only_if_nonempty) {
+        return;
+    }
+    print_summary(summary, status, options.lang);
+    if let Some(timezone) = options.by_hour {
+        print_by_hour(summary, timezone, status);
+    }
+    if options.captions {
+        print_captions(summary, status);
+    }
+    if options.quality {
+        print_quality(summary, status);
+    }
+    if options.immersive {
+        print_immersive(summary, status);
+    }
+    if options.rate {
+        print_rate(summary, options.round, status);
+    }
+    if let Some(ref references) = options.equivalent {
+        print_equivalent(summary, references, status);
+    }
+    if let Some(ref previous) = options.diff {
+        print_diff(summary, previous, options.lang, status);
+    }
+}
 
-        let json = request(&addr)?;
-        write_out(&mut config.output, &json)?;
+//The run completed, but with the given number of warnings, e.g. skipped videos
+#[derive(Debug)]
+pub struct Partial(pub u32);
+impl Display for Partial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            1 => write!(f, "Completed with 1 warning"),
+            n => write!(f, "Completed with {} warnings", n),
+        }
+    }
+}
+impl Error for Partial {}
 
-        let array = json
-            .get("items")
-            .ok_or("Could not find 'items' array")?
-            .as_array()
-            .ok_or("Invalid 'items' format")?;
-
-        for e in array {
-            let date = match DateTime::parse_from_rfc3339(
-                e.pointer("/snippet/publishedAt")
-                    .ok_or("Could not find 'publishedAt' field")?
-                    .as_str()
-                    .ok_or("Invalid 'publishedAt' format")?,
-            ) {
-                Ok(d) => DateTime::<Utc>::from(d),
-                Err(e) => return Err(format!("Could not parse 'publishedAt' timestamp: {}", e))?,
-            };
+pub fn check_warnings(summary: &Summary) -> Result<(), Box<dyn Error>> {
+    match summary.warning_count() {
+        0 => Ok(()),
+        n => Err(Partial(n).into()),
+    }
+}
 
-            if let Some(start) = config.start_date {
-                if date < start {
-                    continue;
-                }
-            }
-            if let Some(end) = config.end_date {
-                if date > end {
-                    continue;
-                }
-            }
+pub fn check_min_videos(
+    summary: &Summary,
+    min_videos: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    match min_videos {
+        Some(min) if summary.count < min => Err(format!(
+            "Found {} videos, fewer than the expected minimum of {}",
+            summary.count, min
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
 
-            video_ids.push(
-                e.pointer("/snippet/resourceId/videoId")
-                    .ok_or("Could not find 'videoId' field")?
-                    .as_str()
-                    .ok_or("Invalid 'videoId' format")?
-                    .to_string(),
-            );
+pub fn print_summary(summary: &Summary, status: Status, lang: crate::lang::Lang) {
+    statusln!(status, "{}", summary.output_line());
+    for (path, result) in &summary.extra_outputs {
+        match result {
+            Ok(_) => statusln!(status, "Also written to '{}'.", path),
+            Err(e) => statusln!(status, "Warning: Could not write '{}': {}", path, e),
         }
+    }
 
-        next_page_token = match json.get("nextPageToken") {
-            Some(v) => Some(
-                v.as_str()
-                    .ok_or("Invalid 'nextPageToken' format")?
-                    .to_string(),
-            ),
-            None => None,
-        };
+    //The result itself, not a diagnostic
+    let direct = status.direct();
+    match summary.interrupted {
+        true => statusln!(direct, "{} (partial)", total_line(summary.total, lang)),
+        false => statusln!(direct, "{}", total_line(summary.total, lang)),
+    }
 
-        let total_results = json
-            .pointer("/pageInfo/totalResults")
-            .ok_or("Could not find 'totalResults' field")?
-            .as_u64()
-            .ok_or("Invalid 'totalResults' format")?;
+    if let Some(views) = summary.views {
+        statusln!(direct, "Total views: {}", views);
+    }
+    if summary.retries > 0 {
+        statusln!(status, "Failed requests retried: {}", summary.retries);
+    }
+    if let Some(line) = crate::report::skipped_line(&summary.skipped) {
+        statusln!(status, "{}", line);
+    }
+}
 
-        if array.is_empty()
-            || next_page_token.is_none()
-            || video_ids.len() >= total_results.try_into()?
-        {
-            break;
-        };
+//In one piece, so it can be colored as a whole
+pub fn total_line(total: TimeDelta, lang: crate::lang::Lang) -> String {
+    let mut line = format!("Sum total: {} seconds", total.num_seconds());
+    if total >= TimeDelta::minutes(1) {
+        line.push_str(&format!(
+            ", or {}",
+            dissect_delta(total, TimeBase::Hours, lang)
+        ));
     }
-    println!("Video count: {}", video_ids.len());
+    line
+}
 
-    print!("Querying video info");
-    std::io::stdout().flush()?;
+pub fn print_by_hour(summary: &Summary, timezone: chrono_tz::Tz, status: Status) {
+    statusln!(status);
+    statusln!(status, "By hour of publishing ({}):", timezone);
+    let hours = crate::report::hour_buckets(&summary.videos, timezone);
+    status!(status, "{}", crate::report::hour_table(&hours));
+}
 
-    let mut videos = Vec::<Video>::new();
-    for (i, id) in video_ids.iter().enumerate() {
-        let addr = format!("https://youtube.googleapis.com/youtube/v3/videos?part=snippet%2CcontentDetails&id={}&key={}",
-            id, config.key);
+pub fn print_captions(summary: &Summary, status: Status) {
+    statusln!(
+        status,
+        "{}",
+        crate::report::captions_line(&crate::report::captions(&summary.videos))
+    );
+}
 
-        let json = request(&addr)?;
-        write_out(&mut config.output, &json)?;
+pub fn print_quality(summary: &Summary, status: Status) {
+    status!(
+        status,
+        "{}",
+        crate::report::quality_lines(&crate::report::quality(&summary.videos))
+    );
+}
 
-        let date = match DateTime::parse_from_rfc3339(
-            json.pointer("/items/0/snippet/publishedAt")
-                .ok_or("Could not find 'publishedAt' field")?
-                .as_str()
-                .ok_or("Invalid 'publishedAt' format")?,
-        ) {
-            Ok(d) => DateTime::<Utc>::from(d),
-            Err(e) => return Err(format!("Could not parse 'publishedAt' timestamp: {}", e))?,
-        };
+pub fn print_rate(summary: &Summary, round: Option<Rounding>, status: Status) {
+    statusln!(status, "{}", crate::report::rate_line(summary, round));
+}
 
-        let title = json
-            .pointer("/items/0/snippet/title")
-            .ok_or("Could not find 'title' field")?
-            .as_str()
-            .ok_or("Invalid 'title' format")?
-            .to_string();
+pub fn print_immersive(summary: &Summary, status: Status) {
+    status!(
+        status,
+        "{}",
+        crate::report::immersive_lines(&crate::report::immersive(&summary.videos))
+    );
+}
 
-        let duration = json
-            .pointer("/items/0/contentDetails/duration")
-            .ok_or("Could not find 'duration' field")?
-            .as_str()
-            .ok_or("Invalid 'duration' format")?
-            .to_string();
+pub fn print_equivalent(
+    summary: &Summary,
+    references: &[crate::equivalent::Reference],
+    status: Status,
+) {
+    statusln!(status);
+    status!(
+        status,
+        "{}",
+        crate::equivalent::equivalent_table(summary.total, references)
+    );
+}
 
-        videos.push(Video::new(date, title, id.clone(), duration)?);
+pub fn print_diff(summary: &Summary, previous: &[Video], lang: crate::lang::Lang, status: Status) {
+    statusln!(status);
+    status!(
+        status,
+        "{}",
+        crate::report::diff_lines(&crate::report::diff(previous, &summary.videos), lang)
+    );
+}
 
-        if ((i + 1) * 10 / video_ids.len()) > (i * 10 / video_ids.len()) {
-            print!(".");
-            std::io::stdout().flush()?;
-        }
+pub struct Summary {
+    pub channel: ChannelInfo,
+    //Empty if streamed, the count and the totals are kept regardless
+    pub videos: Vec<Video>,
+    pub count: usize,
+    pub total: TimeDelta,
+    //Of the videos with a view count, if any had one
+    pub views: Option<u64>,
+    pub output: Option<OutputInfo>,
+    //Path and size, or the error with it
+    pub extra_outputs: Vec<(String, Result<u64, String>)>,
+    //Failed requests retried during the run
+    pub retries: u32,
+    //Warnings displayed during the run, not counting the additional outputs
+    pub warnings: u32,
+    //Earliest and latest publish date in the playlists, including the videos not matching the dates
+    pub uploads: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    //Earliest and latest publish date of the videos counted, kept even if streamed
+    pub published: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    //Listed in the playlists, but left out of the total
+    pub skipped: Vec<Skipped>,
+    //Stopped by the cancel flag, so the videos and the total are partial
+    pub interrupted: bool,
+}
+impl Summary {
+    //Left out for a run that matched no videos, if only a non-empty one is to be displayed
+    pub fn shown(&self, only_if_nonempty: bool) -> bool {
+        self.count > 0 || !only_if_nonempty
     }
-    println!();
 
-    if let Some(ref mut out) = config.output {
-        out.set_len(0)?;
-        out.rewind()?;
-        writeln!(out, "#publishedAt,title,videoId,duration,duration_seconds")?;
-        for v in &videos {
-            writeln!(out, "{}", v)?
-        }
-        println!("Success, output written to 'output.txt'.");
-    } else {
-        println!("Success.");
+    pub fn total_seconds(&self) -> i64 {
+        self.total.num_seconds()
     }
 
-    let total = videos
-        .iter()
-        .fold(TimeDelta::zero(), |acc, v| acc + v.delta);
-    print!("Sum total: {} seconds", total.num_seconds());
-    if total >= TimeDelta::minutes(1) {
-        print!(", or {}", dissect_delta(total, TimeBase::Hours));
+    //In English, e.g. "1 hour 30 minutes", see 'total_line' for the localized summary line
+    pub fn total_human(&self, base: TimeBase) -> String {
+        dissect_delta(self.total, base, crate::lang::Lang::En)
     }
-    println!();
-
-    Ok(())
-}
-
-fn request(address: &str) -> Result<serde_json::Value, Box<dyn Error>> {
-    let req: ureq::Request = ureq::get(address).set("Accept", "application/json");
 
-    match req.call() {
-        Ok(res) => match res.into_json() {
-            Ok(json) => Ok(json),
-            Err(e) => return Err(format!("Failed to read JSON: {}", e))?,
-        },
-        Err(e) => {
-            if let ureq::Error::Status(status, _r) = e {
-                return Err(format!(
-                    "Received HTTP status code: {}",
-                    http::StatusCode::from_u16(status).unwrap(),
-                ))?;
-            } else {
-                return Err(format!("HTTP transfer failure: {}", e))?;
+    //E.g. "Success, output written to 'out.csv'.", naming the destination only if it is known
+    pub fn output_line(&self) -> String {
+        let mut line = String::from(match self.interrupted {
+            true => "Interrupted",
+            false => "Success",
+        });
+        if let Some(ref out) = self.output {
+            line.push_str(", output written");
+            if out.destination != crate::output::Destination::Unnamed {
+                line.push_str(&format!(" to {}", out.destination));
+            }
+            if out.compressed {
+                line.push_str(&format!(" ({} bytes compressed)", out.size));
             }
         }
+        line.push('.');
+        line
     }
-}
 
-fn write_out(out: &mut Option<File>, item: &impl Display) -> Result<(), Box<dyn Error>> {
-    if let Some(ref mut out) = out {
-        out.set_len(0)?;
-        out.rewind()?;
-        write!(out, "{}", item)?
+    //Including the additional outputs that could not be written
+    pub fn warning_count(&self) -> u32 {
+        self.warnings
+            + self
+                .extra_outputs
+                .iter()
+                .filter(|(_, r)| r.is_err())
+                .count() as u32
     }
-    Ok(())
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd)]
-enum TimeBase {
-    _Seconds,
-    Minutes,
-    Hours,
-    Days,
+pub struct OutputInfo {
+    pub destination: crate::output::Destination,
+    pub size: u64,
+    pub compressed: bool,
 }
-fn dissect_delta(mut delta: TimeDelta, base: TimeBase) -> String {
-    let plural = |x: i64| -> &str {
-        match x {
-            1 => "",
-            _ => "s",
-        }
+
+//Same as run(), but instead of displaying the summary, it is returned to the caller
+pub fn run_collect(mut config: Config) -> Result<Summary, Box<dyn Error>> {
+    //Before any request, as an inverted range would cost quota for nothing
+    crate::dates::check_range(config.start_date, config.end_date, Utc::now())?;
+
+    let mut output = match config.output.take() {
+        Some(f) => Some(crate::output::Sink::new(f, config.compress)?),
+        None => None,
     };
 
-    let mut out = String::new();
+    let status = config.status;
+    statusln!(status, "Querying channel info...");
 
-    if delta >= TimeDelta::days(1) && base >= TimeBase::Days {
-        let d = delta.num_days();
-        out.push_str(format!("{} day{}", d, plural(d)).as_str());
-        delta -= TimeDelta::days(d);
-    }
-    if delta >= TimeDelta::hours(1) && base >= TimeBase::Hours {
-        let h = delta.num_hours();
-        if h > 0 && !out.is_empty() {
-            out.push(' ');
+    //Only the query returns the handle to verify
+    let cached = match config.cache {
+        Some(ref c) if !config.verify_handle => c.get(&config.channel_name, Utc::now()),
+        _ => None,
+    };
+    //Quota units used before fetching the videos
+    let mut spent = 0;
+    //Problems the run continued after, e.g. a skipped video
+    let mut warnings = 0;
+    let channel = match cached {
+        Some(c) => {
+            statusln!(status, "Channel info loaded from cache.");
+            c
         }
-        out.push_str(format!("{} hour{}", h, plural(h)).as_str());
-        delta -= TimeDelta::hours(h);
-    }
-    if delta >= TimeDelta::minutes(1) && base >= TimeBase::Minutes {
-        let m = delta.num_minutes();
-        if m > 0 && !out.is_empty() {
-            out.push(' ');
+        None => {
+            let c = query_channel(&config, &config.channel_name)?;
+            spent += 1;
+            if let Some(ref cache) = config.cache {
+                if let Err(e) = cache.put(&config.channel_name, &c, Utc::now()) {
+                    statusln!(status, "Warning: Could not update the channel cache: {}", e);
+                    warnings += 1;
+                }
+            }
+            c
         }
-        out.push_str(format!("{} minute{}", m, plural(m)).as_str());
-        delta -= TimeDelta::minutes(m);
-    }
+    };
+    let playlist_ids = match config.playlists.is_empty() {
+        true => {
+            let playlist_id_pub = public_playlist_id(&channel.uploads_playlist_id);
+            statusln!(status, "Playlist ID extracted.");
+            vec![playlist_id_pub]
+        }
+        false => config.playlists.clone(),
+    };
 
-    let s = delta.num_seconds();
-    if s > 0 || out.is_empty() {
-        if !out.is_empty() {
-            out.push(' ');
+    let mut video_ids = Vec::<PlaylistItem>::new();
+    //The IDs in 'video_ids', as a channel may have tens of thousands of them
+    let mut seen = std::collections::HashSet::<String>::new();
+    let mut pages = 0;
+    let mut uploads = None;
+    let mut skipped = Vec::<Skipped>::new();
+    let walk_start = std::time::Instant::now();
+    for playlist_id in &playlist_ids {
+        statusln!(status, "Querying playlist...");
+        let PlaylistWalk {
+            items,
+            pages: n,
+            uploads: span,
+            skipped: unparseable,
+        } = query_playlist(&config, playlist_id, output.is_some())?;
+        pages += n;
+        warnings += unparseable.len() as u32;
+        skipped.extend(unparseable);
+        if let Some((first, last)) = span {
+            uploads = widen(widen(uploads, first), last);
+        }
+        let count = items.len();
+        let before = video_ids.len();
+        for item in items {
+            //Videos are commonly added to several playlists of the channel
+            if seen.insert(item.0.clone()) {
+                video_ids.push(item);
+            }
+        }
+        if playlist_ids.len() > 1 {
+            statusln!(
+                status,
+                "Playlist '{}': {} videos, {} not in the previous playlists",
+                playlist_id,
+                count,
+                video_ids.len() - before
+            );
         }
-        out.push_str(format!("{} second{}", s, plural(s)).as_str());
     }
-    delta -= TimeDelta::seconds(s);
-    debug_assert!(delta < TimeDelta::seconds(1));
+    statusln!(status, "Video count: {}", video_ids.len());
+    spent += pages;
+    //Only the ones still in the playlists, in their order
+    let mut resumed: std::collections::HashMap<String, Video> = std::mem::take(&mut config.resume)
+        .into_iter()
+        .filter(|v| seen.contains(&v.id))
+        .map(|v| (v.id.clone(), v))
+        .collect();
+    if !resumed.is_empty() {
+        statusln!(
+            status,
+            "Resuming with {} videos of the earlier output, {} left to fetch.",
+            resumed.len(),
+            video_ids.len() - resumed.len()
+        );
+    }
+    if let Some(message) = no_match_message(&video_ids, uploads, config.start_date, config.end_date)
+    {
+        statusln!(status, "Warning: {}", message);
+        warnings += 1;
+    }
 
-    out
-}
+    let estimate = estimate(
+        (video_ids.len() - resumed.len()) as u64,
+        spent,
+        walk_start.elapsed() / pages.max(1) as u32,
+    );
+    if let Some(max) = config.max_quota {
+        if estimate.spent + estimate.units > max {
+            return Err(format!(
+                "Fetching the {} videos would bring the quota cost of the run to ~{} units, above the maximum of {}",
+                estimate.videos,
+                estimate.spent + estimate.units,
+                max
+            )
+            .into());
+        }
+    }
+    if let Some(ref confirm) = config.confirm {
+        if !confirm(&estimate) {
+            return Err("Aborted before fetching the videos".into());
+        }
+    }
+
+    let metadata = crate::output::Metadata {
+        generated: Utc::now(),
+        channel: config.channel_name.clone(),
+        channel_id: channel.id.clone(),
+        playlist_id: playlist_ids.join(","),
+        start: config.start_date,
+        end: config.end_date,
+        channel_videos: channel.video_count,
+        matched_videos: video_ids.len(),
+    };
+    let layout = crate::output::Layout {
+        format: config.format,
+        date: config.date_format.as_ref(),
+        template: config.template.as_ref(),
+        metadata: Some(&metadata),
+        captions: config.captions_column,
+        definition: config.definition_column,
+        immersive: config.immersive_columns,
+        thumbnail: config.thumbnail.is_some(),
+    };
+
+    //Rows are streamed to the output as they are fetched, unless they need to be reordered at the end
+    let streaming = config.sort.is_none();
+    if config.stream && !streaming {
+        return Err("Streamed videos cannot be sorted".into());
+    }
+    let mut writer = match output {
+        Some(ref mut out) if streaming => Some(crate::output::writer(out.rewrite()?, &layout)?),
+        Some(_) => {
+            statusln!(
+                status,
+                "Note: Sorting requires all videos first, output will be written at the end."
+            );
+            None
+        }
+        None => None,
+    };
+
+    //The verbose messages would break up the redrawn line
+    let progress_status = status.progress();
+    let bar = config.progress_bar && progress_status.is_terminal() && !config.verbose.enabled(1);
+    let mut progress = crate::progress::Progress::start(
+        "Querying video info",
+        video_ids.len(),
+        bar,
+        progress_status,
+    );
+
+    let mut videos = Vec::<Video>::new();
+    let mut count = 0;
+    let mut total = TimeDelta::zero();
+    let mut views = None;
+    let mut region_blocked = 0;
+    let mut not_public = 0;
+    let mut title_excluded = 0;
+    let mut interrupted = false;
+    let mut published_span = None;
+    let parts = video_parts(&config);
+    for (i, (id, published)) in video_ids.iter().enumerate() {
+        if cancelled(&config) {
+            progress.clear();
+            statusln!(
+                status,
+                "Warning: Interrupted, stopped after {} of {} videos.",
+                i,
+                video_ids.len()
+            );
+            warnings += 1;
+            interrupted = true;
+            break;
+        }
+        let started = std::time::Instant::now();
+        let found = match resumed.remove(id) {
+            Some(v) => {
+                config.verbose.log(
+                    1,
+                    format_args!("Video {}/{} '{}' resumed", i + 1, video_ids.len(), id),
+                );
+                (id.clone(), Ok(v))
+            }
+            None => {
+                let addr = api_url("videos", &[("part", &parts), ("id", id)]);
+
+                config.verbose.log(
+                    1,
+                    format_args!("Video {}/{} '{}'", i + 1, video_ids.len(), id),
+                );
+                let json = fetch(&config, &addr)?;
+
+                let parse_start = std::time::Instant::now();
+                let items = parse_video_items(&json, Some(*published), config.thumbnail)
+                    .map_err(|e| keep_failed(&config, output.is_some(), &addr, &json, e))?;
+                let video = map_items(&[id.as_str()], items).remove(id).unwrap();
+                let parsed = (id.clone(), video);
+                config.verbose.log(
+                    2,
+                    format_args!("  Parsed in {} us", parse_start.elapsed().as_micros()),
+                );
+                parsed
+            }
+        };
+        match found {
+            (_, Ok(v))
+                if config
+                    .exclude_region_blocked
+                    .as_ref()
+                    .is_some_and(|region| {
+                        v.region_restriction
+                            .as_ref()
+                            .is_some_and(|r| r.blocks(region))
+                    }) =>
+            {
+                config
+                    .verbose
+                    .log(1, format_args!("  Blocked in the region, excluded"));
+                region_blocked += 1;
+                skipped.push(Skipped {
+                    id: id.clone(),
+                    reason: SkipReason::RegionBlocked,
+                    detail: format!(
+                        "Blocked in region '{}'",
+                        config.exclude_region_blocked.as_deref().unwrap_or_default()
+                    ),
+                });
+            }
+            (_, Ok(v)) if config.strict_public && v.privacy_status.as_deref() != Some("public") => {
+                config.verbose.log(
+                    1,
+                    format_args!(
+                        "  Privacy status '{}', excluded",
+                        v.privacy_status.as_deref().unwrap_or("unknown")
+                    ),
+                );
+                not_public += 1;
+                skipped.push(Skipped {
+                    id: id.clone(),
+                    reason: SkipReason::NotPublic,
+                    detail: format!(
+                        "Privacy status '{}'",
+                        v.privacy_status.as_deref().unwrap_or("unknown")
+                    ),
+                });
+            }
+            (_, Ok(mut v)) => {
+                v.title = crate::title::sanitize(&v.title, config.titles);
+                if config
+                    .title_filter
+                    .as_ref()
+                    .is_some_and(|f| !f.matches(&v.title))
+                {
+                    config
+                        .verbose
+                        .log(1, format_args!("  Not matching the title filter, excluded"));
+                    title_excluded += 1;
+                    skipped.push(Skipped {
+                        id: id.clone(),
+                        reason: SkipReason::TitleFilter,
+                        detail: String::from("Not matching the title filter"),
+                    });
+                    progress.tick(started.elapsed());
+                    continue;
+                }
+                if let Some(ref mut w) = writer {
+                    w.write(&v)?;
+                }
+                count += 1;
+                total += v.delta;
+                published_span = widen(published_span, v.date);
+                if let Some(n) = v.views {
+                    views = Some(views.unwrap_or(0) + n);
+                }
+                if !config.stream {
+                    videos.push(v);
+                }
+            }
+            (_, Err((reason, e))) => {
+                progress.clear();
+                statusln!(status, "Warning: Skipping video '{}': {}", id, e);
+                warnings += 1;
+                skipped.push(Skipped {
+                    id: id.clone(),
+                    reason,
+                    detail: e,
+                });
+            }
+        }
+
+        progress.tick(started.elapsed());
+    }
+    progress.finish();
+    if let Some(ref region) = config.exclude_region_blocked {
+        statusln!(
+            status,
+            "Excluded {} videos blocked in region '{}'.",
+            region_blocked,
+            region
+        );
+    }
+    if config.strict_public {
+        statusln!(status, "Excluded {} videos not public.", not_public);
+    }
+    if config.title_filter.is_some() {
+        statusln!(
+            status,
+            "Excluded {} videos not matching the title filter.",
+            title_excluded
+        );
+    }
+
+    if let Some(key) = config.sort {
+        sort_videos(&mut videos, key, config.sort_desc);
+    }
+
+    if let Some(w) = writer {
+        w.finish(config.footer.then(Utc::now))?.close()?;
+    } else if let Some(ref mut out) = output {
+        crate::output::write_videos(
+            out.rewrite()?,
+            &videos,
+            &layout,
+            config.footer.then(Utc::now),
+        )?
+        .close()?;
+    }
+
+    if config.verify_output {
+        match (&mut output, config.format) {
+            (Some(out), crate::output::Format::Csv) => match verify_output(out, &videos) {
+                Ok(()) => statusln!(status, "Output verified."),
+                Err(e) => {
+                    statusln!(status, "Warning: Output verification failed: {}", e);
+                    warnings += 1;
+                }
+            },
+            (Some(_), _) => statusln!(status, "Note: Only CSV output can be verified."),
+            (None, _) => (),
+        }
+    }
+
+    if let Some(pending) = config.pending.take() {
+        if let Some(ref out) = output {
+            out.sync()?;
+        }
+        match interrupted {
+            //A partial result never replaces the output of an earlier run
+            true => config.destination = crate::output::Destination::Path(pending.partial),
+            false => pending.commit()?,
+        }
+    }
+
+    let extra_outputs = std::mem::take(&mut config.extra_outputs)
+        .into_iter()
+        .map(|extra| {
+            let path = extra.path.clone();
+            let result =
+                crate::output::write_extra(extra, &videos, &layout, config.footer.then(Utc::now));
+            (path, result.map_err(|e| e.to_string()))
+        })
+        .collect();
+
+    if let Some((format, mut f)) = config.skipped_output.take() {
+        f.write_all(
+            match format {
+                crate::output::Format::Json => crate::report::skipped_json(&skipped),
+                _ => crate::report::skipped_csv(&skipped),
+            }
+            .as_bytes(),
+        )?;
+    }
+    if let Some(mut f) = config.buckets_output.take() {
+        let buckets = crate::report::duration_buckets(&videos);
+        f.write_all(crate::report::buckets_csv(&buckets).as_bytes())?;
+    }
+    if let Some(mut f) = config.months_output.take() {
+        let timezone = match config.date_format {
+            Some(ref f) => f.timezone(),
+            None => chrono_tz::Tz::UTC,
+        };
+        let months = crate::report::month_buckets(&videos, timezone, config.fill_gaps);
+        f.write_all(crate::report::months_csv(&months).as_bytes())?;
+    }
+
+    //A skipped video would be missed by the next run otherwise
+    if let Some(ref last_run) = config.state {
+        match video_ids.iter().map(|(_, published)| *published).max() {
+            Some(newest) if warnings == 0 => match crate::state::record(last_run, newest) {
+                Ok(()) => statusln!(status, "Newest video recorded for the next run."),
+                Err(e) => {
+                    statusln!(
+                        status,
+                        "Warning: Could not update the state file '{}': {}",
+                        last_run.path.display(),
+                        e
+                    );
+                    warnings += 1;
+                }
+            },
+            Some(_) => statusln!(
+                status,
+                "Note: The state file is not updated, as the run had warnings."
+            ),
+            None => (),
+        }
+    }
+
+    Ok(Summary {
+        channel,
+        videos,
+        count,
+        total,
+        views,
+        output: match output {
+            Some(out) => Some(OutputInfo {
+                destination: config.destination.clone(),
+                size: out.size()?,
+                compressed: config.compress,
+            }),
+            None => None,
+        },
+        extra_outputs,
+        retries: config.retries.used.get(),
+        warnings,
+        uploads,
+        published: published_span,
+        skipped,
+        interrupted,
+    })
+}
+
+fn cancelled(config: &Config) -> bool {
+    config
+        .cancel
+        .is_some_and(|c| c.load(std::sync::atomic::Ordering::SeqCst))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelInfo {
+    pub id: String,
+    pub title: String,
+    pub uploads_playlist_id: String,
+    pub video_count: u64,
+}
+
+//Headline numbers of a channel, as of the query
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelStats {
+    pub handle: String,
+    pub title: String,
+    //None if the channel hides it
+    pub subscriber_count: Option<u64>,
+    pub video_count: u64,
+    pub view_count: u64,
+}
+
+//Filtering to public only (ie. excluding shorts, live, private and unlisted) by replacing default "UU" prefix
+pub fn public_playlist_id(uploads_playlist_id: &str) -> String {
+    let mut playlist_id_pub = String::new();
+    playlist_id_pub.push_str("UULF");
+    playlist_id_pub.push_str(uploads_playlist_id.get(2..).unwrap_or_default());
+    playlist_id_pub
+}
+
+//Only the channel query, costing a single quota unit (unless cached), for the playlist that would be summed
+pub fn playlist_id(config: &Config) -> Result<String, Box<dyn Error>> {
+    let cached = match config.cache {
+        Some(ref c) if !config.verify_handle => c.get(&config.channel_name, Utc::now()),
+        _ => None,
+    };
+    let channel = match cached {
+        Some(c) => c,
+        None => {
+            let c = query_channel(config, &config.channel_name)?;
+            if let Some(ref cache) = config.cache {
+                if let Err(e) = cache.put(&config.channel_name, &c, Utc::now()) {
+                    statusln!(
+                        config.status,
+                        "Warning: Could not update the channel cache: {}",
+                        e
+                    );
+                }
+            }
+            c
+        }
+    };
+    Ok(public_playlist_id(&channel.uploads_playlist_id))
+}
+
+//Only the channel query, costing a single quota unit, and never taken from the cache as the numbers change
+pub fn channel_stats(config: &Config) -> Result<ChannelStats, Box<dyn Error>> {
+    let addr = api_url(
+        "channels",
+        &[
+            ("part", "id%2Csnippet%2Cstatistics%2CcontentDetails"),
+            ("forHandle", &config.channel_name),
+        ],
+    );
+    let json = fetch(config, &addr)?;
+    let info = parse_channel(&json, &config.channel_name, config)?;
+    parse_channel_stats(&json, &config.channel_name, info.title)
+}
+
+//Of the first video of the site, so it is found with any key allowed to read the API
+const KEY_CHECK_VIDEO: &str = "jNQXAC9IVRw";
+
+//A single video query costing 1 quota unit, without any channel, the error telling why the key cannot be used
+pub fn check_key(config: &Config) -> Result<(), Box<dyn Error>> {
+    let addr = api_url("videos", &[("part", "id"), ("id", KEY_CHECK_VIDEO)]);
+    fetch(config, &addr).map(|_| ())
+}
+
+fn parse_channel_stats(
+    json: &serde_json::Value,
+    handle: &str,
+    title: String,
+) -> Result<ChannelStats, Box<dyn Error>> {
+    let context = format!("channels response for '{}'", handle);
+    let count = |name: &str| -> Result<Option<u64>, Box<dyn Error>> {
+        let pointer = format!("/items/0/statistics/{}", name);
+        match json.pointer(&pointer) {
+            Some(v) => Ok(Some(
+                v.as_str()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(json, &pointer, &context))?,
+            )),
+            None => Ok(None),
+        }
+    };
+    let required = |name: &str| -> Result<u64, Box<dyn Error>> {
+        let pointer = format!("/items/0/statistics/{}", name);
+        get_str(json, &pointer, &context)?
+            .parse()
+            .map_err(|_| invalid(json, &pointer, &context))
+    };
+
+    Ok(ChannelStats {
+        handle: String::from(handle),
+        title,
+        subscriber_count: count("subscriberCount")?,
+        video_count: required("videoCount")?,
+        view_count: required("viewCount")?,
+    })
+}
+
+pub fn print_channel_stats(stats: &ChannelStats, status: Status) {
+    //The result itself, not a diagnostic
+    let direct = status.direct();
+    statusln!(direct, "Channel: {}", stats.title);
+    match stats.subscriber_count {
+        Some(n) => statusln!(direct, "Subscribers: {}", n),
+        None => statusln!(direct, "Subscribers: hidden"),
+    }
+    statusln!(direct, "Videos: {}", stats.video_count);
+    statusln!(direct, "Views: {}", stats.view_count);
+}
+
+//One of the channels a handle matched
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub id: String,
+    pub title: String,
+    pub custom_url: Option<String>,
+}
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.custom_url {
+            Some(ref url) => write!(f, "'{}' ({}, {})", self.title, url, self.id),
+            None => write!(f, "'{}' ({})", self.title, self.id),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AmbiguousChannel {
+    total: u64,
+    candidates: Vec<Candidate>,
+}
+impl Display for AmbiguousChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "More than one result ({}), pick one interactively or use '--first-match' for the first one:",
+            self.total
+        )?;
+        for (i, c) in self.candidates.iter().enumerate() {
+            write!(f, "\n  {}. {}", i + 1, c)?;
+        }
+        Ok(())
+    }
+}
+impl Error for AmbiguousChannel {}
+
+#[derive(Debug)]
+struct NoChannel(String);
+impl Display for NoChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "No channel found with the handle '@{}', search for the channel on YouTube and take the handle from its page",
+            self.0
+        )
+    }
+}
+impl Error for NoChannel {}
+
+//Cost of fetching the videos, known exactly once the playlists are walked
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub videos: u64,
+    pub units: u64,
+    //Quota units already spent on the channel and the playlist pages
+    pub spent: u64,
+    pub duration: std::time::Duration,
+}
+
+//One request per video, each costing 1 quota unit and taking about as long as a playlist page did
+pub fn estimate(videos: u64, spent: u64, latency: std::time::Duration) -> Estimate {
+    Estimate {
+        videos,
+        units: videos,
+        spent,
+        duration: latency * videos.try_into().unwrap_or(u32::MAX),
+    }
+}
+
+//Walks the playlists as a run would, then stops before fetching any video, for the 'estimate' command
+pub fn run_estimate(mut config: Config) -> Result<Estimate, Box<dyn Error>> {
+    let asked = std::rc::Rc::new(std::cell::Cell::new(None));
+    let a = asked.clone();
+    config.confirm = Some(Box::new(move |e| {
+        a.set(Some(*e));
+        false
+    }));
+    //Nothing is written, and the estimate is wanted even above the limit
+    config.output = None;
+    config.extra_outputs = Vec::new();
+    config.state = None;
+    config.max_quota = None;
+
+    let result = run_collect(config);
+    match (asked.get(), result) {
+        (Some(e), _) => Ok(e),
+        (None, Err(e)) => Err(e),
+        (None, Ok(_)) => Err("The run ended without an estimate".into()),
+    }
+}
+
+//Resolves the handle to the channel ID and the uploads playlist ID, costing 1 quota unit
+pub fn resolve_channel(config: &Config, handle: &str) -> Result<ChannelInfo, Box<dyn Error>> {
+    query_channel(config, handle)
+}
+
+//Video ID with the publish date from the playlist item
+type PlaylistItem = (String, DateTime<Utc>);
+
+//Earliest and latest publish date seen
+type Span = Option<(DateTime<Utc>, DateTime<Utc>)>;
+
+fn widen(span: Span, date: DateTime<Utc>) -> Span {
+    match span {
+        Some((first, last)) => Some((first.min(date), last.max(date))),
+        None => Some((date, date)),
+    }
+}
+
+struct PlaylistWalk {
+    //Matching the dates, each listed once
+    items: Vec<PlaylistItem>,
+    //Requested
+    pages: u64,
+    //Of all the uploads, regardless of the dates
+    uploads: Span,
+    //Items with a warning, e.g. an unparseable date
+    skipped: Vec<Skipped>,
+}
+
+//A response failing to parse is kept next to the output if there is one
+fn query_playlist(
+    config: &Config,
+    playlist_id: &str,
+    keep_failed_response: bool,
+) -> Result<PlaylistWalk, Box<dyn Error>> {
+    let mut video_ids = Vec::<PlaylistItem>::new();
+    let mut seen = std::collections::HashSet::<String>::new();
+    let mut next_page_token: Option<String> = None;
+    let mut pages = 0;
+    let mut uploads = None;
+    let mut skipped = Vec::new();
+    //Of the first page, as later ones may count slightly differently with videos added or removed meanwhile
+    let mut total_results = None;
+    loop {
+        if cancelled(config) {
+            return Err("Interrupted before fetching the videos".into());
+        }
+        pages += 1;
+        let addr = api_url(
+            "playlistItems",
+            &[
+                ("part", "id%2Csnippet"),
+                ("playlistId", playlist_id),
+                ("maxResults", "50"),
+                ("pageToken", next_page_token.as_deref().unwrap_or_default()),
+            ],
+        );
+
+        let json = fetch(config, &addr)?;
+
+        let context = format!(
+            "playlistItems response, page {} of playlist '{}'",
+            pages, playlist_id
+        );
+        let page = parse_page(
+            config,
+            &json,
+            &mut video_ids,
+            &mut seen,
+            &mut uploads,
+            &mut skipped,
+            &context,
+        )
+        .map_err(|e| keep_failed(config, keep_failed_response, &addr, &json, e))?;
+        //Requesting the same page again would never end
+        let repeated = page.next_page_token.is_some() && page.next_page_token == next_page_token;
+        next_page_token = page.next_page_token.filter(|_| !repeated);
+        let total = *total_results.get_or_insert(page.total_results);
+        if page.total_results != total {
+            config.verbose.log(
+                1,
+                format_args!(
+                    "  Page {} reports {} results in total instead of {} of the first page, keeping the latter",
+                    pages, page.total_results, total
+                ),
+            );
+        }
+        config.verbose.log(
+            1,
+            format_args!(
+                "  {} items, {} matched so far of {} in total, next page token '{}'",
+                page.items,
+                video_ids.len(),
+                total,
+                next_page_token.as_deref().unwrap_or_default()
+            ),
+        );
+        if repeated {
+            statusln!(
+                config.status,
+                "Warning: Page {} of the playlist returned the same next page token as the one requested, stopping there.",
+                pages
+            );
+        }
+
+        if page.items == 0 || next_page_token.is_none() || video_ids.len() >= total {
+            break;
+        };
+    }
+    Ok(PlaylistWalk {
+        items: video_ids,
+        pages,
+        uploads,
+        skipped,
+    })
+}
+
+//Of an excerpt of the JSON in an error, in characters
+const EXCERPT_LEN: usize = 120;
+
+//The value at the pointer, or an error naming the field, the context (the request and the item being processed), and
+//an excerpt of the deepest part of the JSON that is there
+fn get<'a>(
+    json: &'a serde_json::Value,
+    pointer: &str,
+    context: &str,
+) -> Result<&'a serde_json::Value, Box<dyn Error>> {
+    json.pointer(pointer).ok_or_else(|| {
+        let mut parent = pointer;
+        let found = loop {
+            parent = parent.rsplit_once('/').map_or("", |(p, _)| p);
+            if let Some(v) = json.pointer(parent) {
+                break v;
+            }
+        };
+        format!(
+            "Could not find '{}' field ({}), in {}",
+            field_name(pointer),
+            context,
+            excerpt(found)
+        )
+        .into()
+    })
+}
+
+fn get_str<'a>(
+    json: &'a serde_json::Value,
+    pointer: &str,
+    context: &str,
+) -> Result<&'a str, Box<dyn Error>> {
+    get(json, pointer, context)?
+        .as_str()
+        .ok_or_else(|| invalid(json, pointer, context))
+}
+
+fn get_u64(json: &serde_json::Value, pointer: &str, context: &str) -> Result<u64, Box<dyn Error>> {
+    get(json, pointer, context)?
+        .as_u64()
+        .ok_or_else(|| invalid(json, pointer, context))
+}
+
+fn get_array<'a>(
+    json: &'a serde_json::Value,
+    pointer: &str,
+    context: &str,
+) -> Result<&'a Vec<serde_json::Value>, Box<dyn Error>> {
+    get(json, pointer, context)?
+        .as_array()
+        .ok_or_else(|| invalid(json, pointer, context))
+}
+
+//Of a field that is there, but not as expected
+fn invalid(json: &serde_json::Value, pointer: &str, context: &str) -> Box<dyn Error> {
+    format!(
+        "Invalid '{}' format ({}), found {}",
+        field_name(pointer),
+        context,
+        json.pointer(pointer).map(excerpt).unwrap_or_default()
+    )
+    .into()
+}
+
+fn field_name(pointer: &str) -> &str {
+    pointer.rsplit('/').next().unwrap_or(pointer)
+}
+
+fn excerpt(json: &serde_json::Value) -> String {
+    let s = json.to_string();
+    match s.char_indices().nth(EXCERPT_LEN) {
+        Some((i, _)) => format!("{}...", &s[..i]),
+        None => s,
+    }
+}
+
+struct Page {
+    items: usize,
+    next_page_token: Option<String>,
+    total_results: usize,
+}
+
+//Adds the matching items of a playlist page to the ones found so far, the IDs of which are in 'seen'
+fn parse_page(
+    config: &Config,
+    json: &serde_json::Value,
+    video_ids: &mut Vec<PlaylistItem>,
+    seen: &mut std::collections::HashSet<String>,
+    uploads: &mut Span,
+    skipped: &mut Vec<Skipped>,
+    context: &str,
+) -> Result<Page, Box<dyn Error>> {
+    let array = get_array(json, "/items", context)?;
+
+    for (i, e) in array.iter().enumerate() {
+        let context = match e
+            .pointer("/snippet/resourceId/videoId")
+            .and_then(|v| v.as_str())
+        {
+            Some(id) => format!("{}, video '{}'", context, id),
+            None => format!("{}, item {}", context, i),
+        };
+        let date = match crate::dates::parse_published(get_str(
+            e,
+            "/snippet/publishedAt",
+            &context,
+        )?) {
+            Ok(d) => d,
+            Err(err) => {
+                let id = e
+                    .pointer("/snippet/resourceId/videoId")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("?");
+                let detail = format!("Could not parse 'publishedAt' timestamp: {}", err);
+                statusln!(
+                    config.status,
+                    "Warning: Skipping playlist item of video '{}', could not parse 'publishedAt' timestamp: {}",
+                    id,
+                    err
+                );
+                skipped.push(Skipped {
+                    id: String::from(id),
+                    reason: SkipReason::UnparseableDate,
+                    detail,
+                });
+                continue;
+            }
+        };
+        *uploads = widen(*uploads, date);
+
+        if !in_range(date, config.start_date, config.end_date) {
+            continue;
+        }
+
+        let id = get_str(e, "/snippet/resourceId/videoId", &context)?;
+        if seen.insert(id.to_string()) {
+            video_ids.push((id.to_string(), date));
+        }
+    }
+
+    let next_page_token = next_page_token(json, context)?;
+
+    let total_results = get_u64(json, "/pageInfo/totalResults", context)?;
+    Ok(Page {
+        items: array.len(),
+        next_page_token,
+        total_results: result_count(total_results, usize::MAX)?,
+    })
+}
+
+//Up to the given maximum (that of the platform, or lower for testing), larger being surely not a real count
+fn result_count(total_results: u64, max: usize) -> Result<usize, Box<dyn Error>> {
+    usize::try_from(total_results)
+        .ok()
+        .filter(|&n| n <= max)
+        .ok_or_else(|| {
+            format!(
+                "Implausible 'totalResults' value {}, more than the {} that can be counted",
+                total_results, max
+            )
+            .into()
+        })
+}
+
+//An empty token (e.g. from a proxy) is the same as none, as requesting it would return the first page again
+fn next_page_token(
+    json: &serde_json::Value,
+    context: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    match json.get("nextPageToken") {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(v) => {
+            let token = v
+                .as_str()
+                .ok_or_else(|| invalid(json, "/nextPageToken", context))?;
+            Ok((!token.trim().is_empty()).then(|| String::from(token)))
+        }
+    }
+}
+
+//The response a parse failed on, kept next to the output as only the videos are written to it
+fn keep_failed(
+    config: &Config,
+    enabled: bool,
+    address: &str,
+    json: &serde_json::Value,
+    e: Box<dyn Error>,
+) -> Box<dyn Error> {
+    let crate::output::Destination::Path(ref path) = config.destination else {
+        return e;
+    };
+    if !enabled {
+        return e;
+    }
+    let path = format!("{}.error.json", path);
+    let kept = serde_json::json!({
+        "request": crate::verbose::redact_key(address),
+        "error": e.to_string(),
+        "response": json,
+    });
+    match serde_json::to_string_pretty(&kept)
+        .map_err(std::io::Error::from)
+        .and_then(|s| std::fs::write(&path, s + "\n"))
+    {
+        Ok(()) => format!("{} (response kept in '{}')", e, path).into(),
+        Err(w) => format!("{} (could not keep the response in '{}': {})", e, path, w).into(),
+    }
+}
+
+fn query_channel(config: &Config, handle: &str) -> Result<ChannelInfo, Box<dyn Error>> {
+    let addr = api_url(
+        "channels",
+        &[
+            ("part", "id%2Csnippet%2Cstatistics%2CcontentDetails"),
+            ("forHandle", handle),
+        ],
+    );
+
+    let json = fetch(config, &addr)?;
+    parse_channel(&json, handle, config)
+}
+
+//Of the returned items, the one picked if there are more
+fn parse_channel(
+    json: &serde_json::Value,
+    handle: &str,
+    config: &Config,
+) -> Result<ChannelInfo, Box<dyn Error>> {
+    let context = format!("channels response for '{}'", handle);
+    let index = match get_u64(json, "/pageInfo/totalResults", &context)? {
+        0 => return Err(NoChannel(String::from(handle)))?,
+        1 => 0,
+        n if config.first_match => {
+            statusln!(
+                config.status,
+                "Warning: More than one result ({}), using the first one.",
+                n
+            );
+            0
+        }
+        n => {
+            let candidates = channel_candidates(json);
+            match config.pick {
+                Some(ref pick) if !candidates.is_empty() => match pick(&candidates) {
+                    Some(i) if i < candidates.len() => i,
+                    _ => return Err("Aborted, no channel was picked".into()),
+                },
+                _ => {
+                    return Err(AmbiguousChannel {
+                        total: n,
+                        candidates,
+                    })?
+                }
+            }
+        }
+    };
+
+    let field = |pointer: &str| -> Result<String, Box<dyn Error>> {
+        Ok(get_str(json, &format!("/items/{}/{}", index, pointer), &context)?.to_string())
+    };
+
+    if config.verify_handle {
+        let found = json
+            .pointer(&format!("/items/{}/snippet/customUrl", index))
+            .and_then(|v| v.as_str());
+        let requested = handle.trim_start_matches('@');
+        match found {
+            Some(f) if f.trim_start_matches('@').eq_ignore_ascii_case(requested) => (),
+            Some(f) => {
+                return Err(format!(
+                    "The channel found for '@{}' has the handle '{}', it may have been changed (see '--verify-handle')",
+                    requested, f
+                ))?
+            }
+            None => {
+                return Err(format!(
+                    "The channel found for '@{}' has no handle to verify (see '--verify-handle')",
+                    requested
+                ))?
+            }
+        }
+    }
+
+    let uploads_playlist_id = field("contentDetails/relatedPlaylists/uploads")?;
+    if !uploads_playlist_id.starts_with("UU") {
+        return Err("Invalid 'uploads' id format".into());
+    }
+
+    Ok(ChannelInfo {
+        id: field("id")?,
+        title: field("snippet/title")?,
+        uploads_playlist_id,
+        video_count: field("statistics/videoCount")?.parse().map_err(|_| {
+            invalid(
+                json,
+                &format!("/items/{}/statistics/videoCount", index),
+                &context,
+            )
+        })?,
+    })
+}
+
+//As much of each item as there is, to tell them apart
+fn channel_candidates(json: &serde_json::Value) -> Vec<Candidate> {
+    let text = |item: &serde_json::Value, pointer: &str| {
+        item.pointer(pointer)
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+    json.get("items")
+        .and_then(|items| items.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| Candidate {
+                    id: text(item, "/id").unwrap_or_default(),
+                    title: text(item, "/snippet/title").unwrap_or_default(),
+                    custom_url: text(item, "/snippet/customUrl"),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+//Stable sort, so videos with equal keys keep their playlist order (in both directions)
+fn sort_videos(videos: &mut [Video], key: SortKey, desc: bool) {
+    videos.sort_by(|a, b| {
+        let (a, b) = if desc { (b, a) } else { (a, b) };
+        match key {
+            SortKey::Date => a.date.cmp(&b.date),
+            SortKey::Duration => a.delta.cmp(&b.delta),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        }
+    });
+}
+
+//Re-reads the written rows, which must add up to the same count and total as the fetched videos
+fn verify_output(out: &mut crate::output::Sink, videos: &[Video]) -> Result<(), String> {
+    let data = out
+        .read_back()
+        .map_err(|e| format!("Could not read back the output: {}", e))?;
+    let rows = crate::csv::read_csv(data.as_slice())
+        .map_err(|e| format!("Could not parse the output: {}", e))?;
+
+    let seconds = |v: &[Video]| v.iter().map(|v| v.delta.num_seconds()).sum::<i64>();
+    let (found, expected) = (
+        (rows.len(), seconds(&rows)),
+        (videos.len(), seconds(videos)),
+    );
+    match found == expected {
+        true => Ok(()),
+        false => Err(format!(
+            "The output has {} rows totaling {} seconds, instead of {} videos totaling {} seconds",
+            found.0, found.1, expected.0, expected.1
+        )),
+    }
+}
+
+//With both lists given, which the API does not do, the allowed one is the stricter
+fn parse_region_restriction(r: &serde_json::Value) -> Result<RegionRestriction, Box<dyn Error>> {
+    let list = |name: &str| match r.get(name) {
+        Some(v) => v
+            .as_array()
+            .and_then(|a| {
+                a.iter()
+                    .map(|c| c.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .map(Some)
+            .ok_or("Invalid 'regionRestriction' format"),
+        None => Ok(None),
+    };
+    match (list("allowed")?, list("blocked")?) {
+        (Some(allowed), _) => Ok(RegionRestriction::Allowed(allowed)),
+        (None, Some(blocked)) => Ok(RegionRestriction::Blocked(blocked)),
+        (None, None) => Err("Invalid 'regionRestriction' format".into()),
+    }
+}
+
+//Only what is actually used, to keep the responses small
+fn video_parts(config: &Config) -> String {
+    let mut parts = vec!["contentDetails"];
+    if !config.count_only {
+        parts.insert(0, "snippet");
+    }
+    if config.with_stats {
+        parts.push("statistics");
+    }
+    if config.strict_public {
+        parts.push("status");
+    }
+    parts.join("%2C")
+}
+
+//Suggests the dates to use if the filter missed all of the uploads
+fn no_match_message(
+    matched: &[PlaylistItem],
+    uploads: Span,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Option<String> {
+    let day = |d: Option<DateTime<Utc>>| d.map(|d| d.format("%Y-%m-%d").to_string());
+    match (matched.is_empty(), uploads) {
+        (true, Some((first, last))) if start.is_some() || end.is_some() => Some(format!(
+            "The channel has uploads between {} and {}, the filter {}..{} matched none.",
+            first.format("%Y-%m-%d"),
+            last.format("%Y-%m-%d"),
+            day(start).unwrap_or_default(),
+            day(end).unwrap_or_default()
+        )),
+        _ => None,
+    }
+}
+
+//Always on the UTC instants, regardless of the date format and timezone of the output
+//Both bounds are inclusive, to the second, so an end at 23:59:59 still includes a video published at 23:59:59.5
+fn in_range(date: DateTime<Utc>, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> bool {
+    let date = date.trunc_subsecs(0);
+    start.is_none_or(|s| date >= s) && end.is_none_or(|e| date <= e)
+}
+
+//A video by its ID, or why it is skipped with the details
+type ParsedItem = (String, Result<Video, (SkipReason, String)>);
+
+//Each of the requested IDs to its parsed item, or to why it is not in the response (e.g. a video deleted since listed)
+fn map_items(
+    requested: &[&str],
+    items: Vec<ParsedItem>,
+) -> std::collections::HashMap<String, Result<Video, (SkipReason, String)>> {
+    let mut returned: std::collections::HashMap<_, _> = items.into_iter().collect();
+    //A single other one in place of a single requested one is likely a mixup, e.g. by a proxy
+    let other = match (requested, returned.len()) {
+        ([id], 1) if !returned.contains_key(*id) => returned.keys().next().cloned(),
+        _ => None,
+    };
+    requested
+        .iter()
+        .map(|id| {
+            let item = returned.remove(*id).unwrap_or_else(|| {
+                let message = match other {
+                    Some(ref o) => format!("The response is of video '{}' instead", o),
+                    None => String::from(
+                        "Not in the response, it may have been deleted or made private",
+                    ),
+                };
+                Err((SkipReason::Missing, message))
+            });
+            (String::from(*id), item)
+        })
+        .collect()
+}
+
+//The API does not guarantee the order of 'items', nor that an ID is not repeated, so keep the first occurrence only
+//An item with an unparseable duration is returned as an error by its ID, so the caller may skip it
+//Without the 'snippet' part, the date falls back to the given one (from the playlist item), and the title is empty
+fn parse_video_items(
+    json: &serde_json::Value,
+    published: Option<DateTime<Utc>>,
+    thumbnail: Option<ThumbnailQuality>,
+) -> Result<Vec<ParsedItem>, Box<dyn Error>> {
+    let array = get_array(json, "/items", "videos response")?;
+
+    let mut videos = Vec::<ParsedItem>::new();
+    let mut seen = std::collections::HashSet::<String>::new();
+    for (i, e) in array.iter().enumerate() {
+        let id = get_str(e, "/id", &format!("videos response, item {}", i))?.to_string();
+        let context = format!("videos response, video '{}'", id);
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+
+        let (date, title) = match (e.get("snippet"), published) {
+            (None, Some(published)) => (published, String::new()),
+            _ => {
+                let date = match crate::dates::parse_published(get_str(
+                    e,
+                    "/snippet/publishedAt",
+                    &context,
+                )?) {
+                    Ok(d) => d,
+                    //Only this video is skipped
+                    Err(err) => {
+                        let message = format!("Could not parse 'publishedAt' timestamp: {}", err);
+                        videos.push((id, Err((SkipReason::UnparseableDate, message))));
+                        continue;
+                    }
+                };
+
+                let title = get_str(e, "/snippet/title", &context)?.to_string();
+                (date, title)
+            }
+        };
+
+        let duration = get_str(e, "/contentDetails/duration", &context)?.to_string();
+
+        let region_restriction = match e.pointer("/contentDetails/regionRestriction") {
+            Some(r) => Some(
+                parse_region_restriction(r)
+                    .map_err(|_| invalid(e, "/contentDetails/regionRestriction", &context))?,
+            ),
+            None => None,
+        };
+
+        let has_captions = match e.pointer("/contentDetails/caption") {
+            Some(c) => Some(match c.as_str() {
+                Some("true") => true,
+                Some("false") => false,
+                _ => return Err(invalid(e, "/contentDetails/caption", &context)),
+            }),
+            None => None,
+        };
+
+        let definition = match e.pointer("/contentDetails/definition") {
+            Some(d) => Some(
+                d.as_str()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(e, "/contentDetails/definition", &context))?,
+            ),
+            None => None,
+        };
+
+        let dimension = match e.pointer("/contentDetails/dimension") {
+            Some(d) => Some(
+                d.as_str()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(e, "/contentDetails/dimension", &context))?,
+            ),
+            None => None,
+        };
+
+        let projection = match e.pointer("/contentDetails/projection") {
+            Some(p) => Some(
+                p.as_str()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(e, "/contentDetails/projection", &context))?,
+            ),
+            None => None,
+        };
+
+        let views = match e.pointer("/statistics/viewCount") {
+            Some(v) => Some(
+                v.as_str()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| invalid(e, "/statistics/viewCount", &context))?,
+            ),
+            None => None,
+        };
+
+        let privacy_status = match e.pointer("/status/privacyStatus") {
+            Some(_) => Some(String::from(get_str(e, "/status/privacyStatus", &context)?)),
+            None => None,
+        };
+
+        let thumbnail_url = match (thumbnail, e.pointer("/snippet/thumbnails")) {
+            (Some(q), Some(t)) => {
+                thumbnail_url(t, q).map_err(|_| invalid(e, "/snippet/thumbnails", &context))?
+            }
+            _ => None,
+        };
+
+        //Tolerate upstream glitches, but keep the strict format so the output can be re-loaded
+        let duration = crate::period::repair_period(&duration).unwrap_or(duration);
+        let video = Video::new(date, title, id.clone(), duration.clone())
+            .map(|v| Video {
+                views,
+                region_restriction,
+                has_captions,
+                definition,
+                dimension,
+                projection,
+                privacy_status,
+                thumbnail_url,
+                ..v
+            })
+            .map_err(|e| {
+                (
+                    SkipReason::UnparseableDuration,
+                    format!("{} '{}'", e, duration),
+                )
+            });
+        videos.push((id, video));
+    }
+
+    Ok(videos)
+}
+
+//Every request goes through here, so verbose mode can list them
+fn fetch(config: &Config, address: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+    let verbose = &config.verbose;
+    let retries = &config.retries;
+    let mut attempt = 0;
+    loop {
+        //The client attaches the key as the last parameter
+        verbose.log(1, format_args!("GET {}&key=***", address));
+        let start = std::time::Instant::now();
+        let result = config.client.get_json(address, &config.key);
+        let elapsed = start.elapsed().as_millis();
+        let e = match result {
+            Ok(json) => {
+                match verbose.enabled(2) {
+                    true => verbose.log(
+                        2,
+                        format_args!("  OK in {} ms, {} bytes", elapsed, json.to_string().len()),
+                    ),
+                    false => verbose.log(1, format_args!("  OK in {} ms", elapsed)),
+                }
+                return Ok(json);
+            }
+            Err(e) => e,
+        };
+        let transient = e.is::<TransientError>();
+        let msg = config.key.redact(&e.to_string());
+        verbose.log(1, format_args!("  Failed in {} ms: {}", elapsed, msg));
+
+        if !transient || attempt >= retries.per_request {
+            return Err(msg.into());
+        }
+        if let Some(max) = retries.total {
+            if retries.used.get() >= max {
+                return Err(format!("{} (all {} retries of the run used up)", msg, max).into());
+            }
+        }
+        retries.used.set(retries.used.get() + 1);
+        let delay = retries.backoff * 2u32.pow(attempt);
+        attempt += 1;
+        verbose.log(
+            1,
+            format_args!(
+                "  Retry {}/{} in {} ms",
+                attempt,
+                retries.per_request,
+                delay.as_millis()
+            ),
+        );
+        std::thread::sleep(delay);
+    }
+}
+
+//Failure worth retrying, e.g. a network error or the server being temporarily unavailable
+#[derive(Debug)]
+pub struct TransientError(pub String);
+impl Display for TransientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl Error for TransientError {}
+
+//Retrying a failed request, with the delay doubled each time
+#[derive(Clone)]
+pub struct Retries {
+    pub per_request: u32,
+    //Limit of all the retries of the run, so a persistently failing network cannot prolong it indefinitely
+    pub total: Option<u32>,
+    pub backoff: std::time::Duration,
+    //Shared by the clones, e.g. for a run over several channels
+    pub used: std::rc::Rc<std::cell::Cell<u32>>,
+}
+impl Default for Retries {
+    fn default() -> Self {
+        Self {
+            per_request: 3,
+            total: None,
+            backoff: std::time::Duration::from_secs(1),
+            used: std::rc::Rc::new(std::cell::Cell::new(0)),
+        }
+    }
+}
+
+//Address of an API resource, without the key
+fn api_url(resource: &str, params: &[(&str, &str)]) -> String {
+    let query: Vec<String> = params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    format!(
+        "https://youtube.googleapis.com/youtube/v3/{}?{}",
+        resource,
+        query.join("&")
+    )
+}
+
+//Never part of the addresses, only attached by the HTTP client, and deliberately not Display
+#[derive(Clone)]
+pub struct ApiKey(String);
+impl ApiKey {
+    pub fn new(key: String) -> Self {
+        Self(key)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    //Replaces the key anywhere in the text, e.g. in an error quoting the full request address
+    pub fn redact(&self, s: &str) -> String {
+        match self.0.is_empty() {
+            true => String::from(s),
+            false => s.replace(&self.0, "***"),
+        }
+    }
+}
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ApiKey(***)")
+    }
+}
+
+pub trait HttpClient {
+    fn get_json(&self, address: &str, key: &ApiKey) -> Result<serde_json::Value, Box<dyn Error>>;
+}
+
+//Identifies the tool in the logs of the API and of any proxies, unless another one is given
+pub const USER_AGENT: &str = concat!("yt-api-videosum/", env!("CARGO_PKG_VERSION"));
+
+pub struct UreqClient {
+    pub user_agent: String,
+}
+impl Default for UreqClient {
+    fn default() -> Self {
+        Self {
+            user_agent: String::from(USER_AGENT),
+        }
+    }
+}
+impl HttpClient for UreqClient {
+    fn get_json(&self, address: &str, key: &ApiKey) -> Result<serde_json::Value, Box<dyn Error>> {
+        request(address, key, &self.user_agent)
+    }
+}
+
+fn request(
+    address: &str,
+    key: &ApiKey,
+    user_agent: &str,
+) -> Result<serde_json::Value, Box<dyn Error>> {
+    let req: ureq::Request = ureq::get(address)
+        .query("key", key.expose())
+        .set("Accept", "application/json")
+        .set("User-Agent", user_agent);
+
+    match req.call() {
+        Ok(res) => match res.into_json() {
+            Ok(json) => Ok(json),
+            Err(e) => return Err(format!("Failed to read JSON: {}", e))?,
+        },
+        Err(e) => {
+            if let ureq::Error::Status(status, r) = e {
+                let mut msg = format!(
+                    "Received HTTP status code: {}",
+                    http::StatusCode::from_u16(status).unwrap(),
+                );
+                if status == 400 {
+                    if r.into_json().ok().as_ref().is_some_and(is_invalid_key) {
+                        msg.push_str(" (API key not valid)");
+                    }
+                } else if status == 403 {
+                    match r.into_json().ok().as_ref().and_then(classify_forbidden) {
+                        Some(Forbidden::Quota) => msg.push_str(" (API quota exceeded)"),
+                        Some(Forbidden::Restricted) => msg.push_str(
+                            " (API key lacks access to YouTube Data API v3 or has referrer restrictions)",
+                        ),
+                        None => (),
+                    }
+                }
+                //Too many requests, or a server side error
+                match status == 429 || status >= 500 {
+                    true => return Err(TransientError(msg))?,
+                    false => return Err(msg)?,
+                }
+            } else {
+                return Err(TransientError(format!(
+                    "HTTP transfer failure: {}",
+                    crate::verbose::redact_key(&e.to_string())
+                )))?;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Forbidden {
+    Quota,
+    Restricted,
+}
+
+//Based on the 'reason' fields of the error body, see https://developers.google.com/youtube/v3/docs/errors
+fn classify_forbidden(body: &serde_json::Value) -> Option<Forbidden> {
+    let error = body.get("error")?;
+    let reasons = error_reasons(error);
+
+    if reasons.iter().any(|r| {
+        matches!(
+            *r,
+            "quotaExceeded" | "dailyLimitExceeded" | "rateLimitExceeded"
+        )
+    }) {
+        Some(Forbidden::Quota)
+    } else if reasons.iter().any(|r| {
+        r.starts_with("API_KEY_")
+            || matches!(*r, "forbidden" | "accessNotConfigured" | "SERVICE_DISABLED")
+    }) || error.get("status").and_then(|s| s.as_str()) == Some("PERMISSION_DENIED")
+    {
+        Some(Forbidden::Restricted)
+    } else {
+        None
+    }
+}
+
+//The 400 response of a key that does not exist, or was deleted
+fn is_invalid_key(body: &serde_json::Value) -> bool {
+    body.get("error").is_some_and(|error| {
+        error_reasons(error)
+            .iter()
+            .any(|r| matches!(*r, "keyInvalid" | "API_KEY_INVALID"))
+    })
+}
+
+//Of both the legacy 'errors' and the newer 'details' lists
+fn error_reasons(error: &serde_json::Value) -> Vec<&str> {
+    error
+        .get("errors")
+        .and_then(|e| e.as_array())
+        .into_iter()
+        .chain(error.get("details").and_then(|d| d.as_array()))
+        .flatten()
+        .filter_map(|e| e.get("reason")?.as_str())
+        .collect()
+}
+
+//Largest unit of a human-readable duration, e.g. 'Hours' for "50 hours" instead of "2 days 2 hours"
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum TimeBase {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+pub(crate) fn dissect_delta(
+    mut delta: TimeDelta,
+    base: TimeBase,
+    lang: crate::lang::Lang,
+) -> String {
+    use crate::lang::Unit;
+
+    let mut out = String::new();
+
+    if delta >= TimeDelta::days(1) && base >= TimeBase::Days {
+        let d = delta.num_days();
+        out.push_str(format!("{} {}", d, lang.unit(Unit::Day, d)).as_str());
+        delta -= TimeDelta::days(d);
+    }
+    if delta >= TimeDelta::hours(1) && base >= TimeBase::Hours {
+        let h = delta.num_hours();
+        if h > 0 && !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(format!("{} {}", h, lang.unit(Unit::Hour, h)).as_str());
+        delta -= TimeDelta::hours(h);
+    }
+    if delta >= TimeDelta::minutes(1) && base >= TimeBase::Minutes {
+        let m = delta.num_minutes();
+        if m > 0 && !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(format!("{} {}", m, lang.unit(Unit::Minute, m)).as_str());
+        delta -= TimeDelta::minutes(m);
+    }
+
+    let s = delta.num_seconds();
+    if s > 0 || out.is_empty() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(format!("{} {}", s, lang.unit(Unit::Second, s)).as_str());
+    }
+    delta -= TimeDelta::seconds(s);
+    debug_assert!(delta < TimeDelta::seconds(1));
+
+    out
+}
 
 #[cfg(test)]
 mod lib_test {
     use super::*;
+    use crate::lang::Lang;
+
+    #[test]
+    fn rounding_test() {
+        let tests = [
+            (TimeDelta::milliseconds(1499), Rounding::Second, 1),
+            (TimeDelta::milliseconds(1500), Rounding::Second, 2),
+            (TimeDelta::seconds(89), Rounding::Minute, 60),
+            (TimeDelta::seconds(90), Rounding::Minute, 120),
+            (TimeDelta::seconds(5399), Rounding::Hour, 3600),
+            (TimeDelta::seconds(5400), Rounding::Hour, 7200),
+            (TimeDelta::zero(), Rounding::Hour, 0),
+        ];
+        for (delta, round, seconds) in tests {
+            assert_eq!(
+                round.apply(delta).num_seconds(),
+                seconds,
+                "{:?} {}",
+                delta,
+                round
+            );
+        }
+        assert_eq!("minute".parse(), Ok(Rounding::Minute));
+        assert!("minutes".parse::<Rounding>().is_err());
+    }
+
+    #[test]
+    fn summary_total_test() {
+        let summary = |total| Summary {
+            channel: ChannelInfo {
+                id: String::new(),
+                title: String::new(),
+                uploads_playlist_id: String::new(),
+                video_count: 0,
+            },
+            videos: Vec::new(),
+            count: 0,
+            total,
+            views: None,
+            output: None,
+            extra_outputs: Vec::new(),
+            retries: 0,
+            warnings: 0,
+            uploads: None,
+            published: None,
+            skipped: Vec::new(),
+            interrupted: false,
+        };
+        let tests = [
+            (0, TimeBase::Hours, "0 seconds"),
+            (5400, TimeBase::Hours, "1 hour 30 minutes"),
+            (5400, TimeBase::Seconds, "5400 seconds"),
+            (180005, TimeBase::Hours, "50 hours 5 seconds"),
+            (180005, TimeBase::Days, "2 days 2 hours 5 seconds"),
+        ];
+        for (seconds, base, human) in tests {
+            let s = summary(TimeDelta::seconds(seconds));
+            assert_eq!(s.total_seconds(), seconds);
+            assert_eq!(s.total_human(base), human, "{} {:?}", seconds, base);
+            //The same delta, for further date math
+            assert_eq!(
+                DateTime::<Utc>::UNIX_EPOCH + s.total,
+                DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds)
+            );
+        }
+    }
+
+    #[test]
+    fn dissect_test() {
+        let sec = TimeBase::Seconds;
+        let min = TimeBase::Minutes;
+        let hrs = TimeBase::Hours;
+        let days = TimeBase::Days;
+
+        let tests = [
+            (0, sec, "0 seconds"),
+            (1, sec, "1 second"),
+            (59, sec, "59 seconds"),
+            (60, sec, "60 seconds"),
+            (61, sec, "61 seconds"),
+            (119, sec, "119 seconds"),
+            (120, sec, "120 seconds"),
+            (121, sec, "121 seconds"),
+            (599, sec, "599 seconds"),
+            (600, sec, "600 seconds"),
+            (601, sec, "601 seconds"),
+            (659, sec, "659 seconds"),
+            (660, sec, "660 seconds"),
+            (661, sec, "661 seconds"),
+            (3599, sec, "3599 seconds"),
+            (3600, sec, "3600 seconds"),
+            (3601, sec, "3601 seconds"),
+            (3659, sec, "3659 seconds"),
+            (3660, sec, "3660 seconds"),
+            (3661, sec, "3661 seconds"),
+            (4199, sec, "4199 seconds"),
+            (4200, sec, "4200 seconds"),
+            (4201, sec, "4201 seconds"),
+            (7199, sec, "7199 seconds"),
+            (7200, sec, "7200 seconds"),
+            (7201, sec, "7201 seconds"),
+            (7259, sec, "7259 seconds"),
+            (7260, sec, "7260 seconds"),
+            (7261, sec, "7261 seconds"),
+            (86399, sec, "86399 seconds"),
+            (86400, sec, "86400 seconds"),
+            (86401, sec, "86401 seconds"),
+            (86459, sec, "86459 seconds"),
+            (86460, sec, "86460 seconds"),
+            (86461, sec, "86461 seconds"),
+            (89999, sec, "89999 seconds"),
+            (90000, sec, "90000 seconds"),
+            (90001, sec, "90001 seconds"),
+            (90059, sec, "90059 seconds"),
+            (90060, sec, "90060 seconds"),
+            (90061, sec, "90061 seconds"),
+            (604799, sec, "604799 seconds"),
+            (604800, sec, "604800 seconds"),
+            (604801, sec, "604801 seconds"),
+
+            (0, min, "0 seconds"),
+            (1, min, "1 second"),
+            (59, min, "59 seconds"),
+            (60, min, "1 minute"),
+            (61, min, "1 minute 1 second"),
+            (119, min, "1 minute 59 seconds"),
+            (120, min, "2 minutes"),
+            (121, min, "2 minutes 1 second"),
+            (599, min, "9 minutes 59 seconds"),
+            (600, min, "10 minutes"),
+            (601, min, "10 minutes 1 second"),
+            (659, min, "10 minutes 59 seconds"),
+            (660, min, "11 minutes"),
+            (661, min, "11 minutes 1 second"),
+            (3599, min, "59 minutes 59 seconds"),
+            (3600, min, "60 minutes"),
+            (3601, min, "60 minutes 1 second"),
+            (3659, min, "60 minutes 59 seconds"),
+            (3660, min, "61 minutes"),
+            (3661, min, "61 minutes 1 second"),
+            (4199, min, "69 minutes 59 seconds"),
+            (4200, min, "70 minutes"),
+            (4201, min, "70 minutes 1 second"),
+            (7199, min, "119 minutes 59 seconds"),
+            (7200, min, "120 minutes"),
+            (7201, min, "120 minutes 1 second"),
+            (7259, min, "120 minutes 59 seconds"),
+            (7260, min, "121 minutes"),
+            (7261, min, "121 minutes 1 second"),
+            (86399, min, "1439 minutes 59 seconds"),
+            (86400, min, "1440 minutes"),
+            (86401, min, "1440 minutes 1 second"),
+            (86459, min, "1440 minutes 59 seconds"),
+            (86460, min, "1441 minutes"),
+            (86461, min, "1441 minutes 1 second"),
+            (89999, min, "1499 minutes 59 seconds"),
+            (90000, min, "1500 minutes"),
+            (90001, min, "1500 minutes 1 second"),
+            (90059, min, "1500 minutes 59 seconds"),
+            (90060, min, "1501 minutes"),
+            (90061, min, "1501 minutes 1 second"),
+            (604799, min, "10079 minutes 59 seconds"),
+            (604800, min, "10080 minutes"),
+            (604801, min, "10080 minutes 1 second"),
+
+            (0, hrs, "0 seconds"),
+            (1, hrs, "1 second"),
+            (59, hrs, "59 seconds"),
+            (60, hrs, "1 minute"),
+            (61, hrs, "1 minute 1 second"),
+            (119, hrs, "1 minute 59 seconds"),
+            (120, hrs, "2 minutes"),
+            (121, hrs, "2 minutes 1 second"),
+            (599, hrs, "9 minutes 59 seconds"),
+            (600, hrs, "10 minutes"),
+            (601, hrs, "10 minutes 1 second"),
+            (659, hrs, "10 minutes 59 seconds"),
+            (660, hrs, "11 minutes"),
+            (661, hrs, "11 minutes 1 second"),
+            (3599, hrs, "59 minutes 59 seconds"),
+            (3600, hrs, "1 hour"),
+            (3601, hrs, "1 hour 1 second"),
+            (3659, hrs, "1 hour 59 seconds"),
+            (3660, hrs, "1 hour 1 minute"),
+            (3661, hrs, "1 hour 1 minute 1 second"),
+            (4199, hrs, "1 hour 9 minutes 59 seconds"),
+            (4200, hrs, "1 hour 10 minutes"),
+            (4201, hrs, "1 hour 10 minutes 1 second"),
+            (7199, hrs, "1 hour 59 minutes 59 seconds"),
+            (7200, hrs, "2 hours"),
+            (7201, hrs, "2 hours 1 second"),
+            (7259, hrs, "2 hours 59 seconds"),
+            (7260, hrs, "2 hours 1 minute"),
+            (7261, hrs, "2 hours 1 minute 1 second"),
+            (86399, hrs, "23 hours 59 minutes 59 seconds"),
+            (86400, hrs, "24 hours"),
+            (86401, hrs, "24 hours 1 second"),
+            (86459, hrs, "24 hours 59 seconds"),
+            (86460, hrs, "24 hours 1 minute"),
+            (86461, hrs, "24 hours 1 minute 1 second"),
+            (89999, hrs, "24 hours 59 minutes 59 seconds"),
+            (90000, hrs, "25 hours"),
+            (90001, hrs, "25 hours 1 second"),
+            (90059, hrs, "25 hours 59 seconds"),
+            (90060, hrs, "25 hours 1 minute"),
+            (90061, hrs, "25 hours 1 minute 1 second"),
+            (604799, hrs, "167 hours 59 minutes 59 seconds"),
+            (604800, hrs, "168 hours"),
+            (604801, hrs, "168 hours 1 second"),
+
+            (0, days, "0 seconds"),
+            (1, days, "1 second"),
+            (59, days, "59 seconds"),
+            (60, days, "1 minute"),
+            (61, days, "1 minute 1 second"),
+            (119, days, "1 minute 59 seconds"),
+            (120, days, "2 minutes"),
+            (121, days, "2 minutes 1 second"),
+            (599, days, "9 minutes 59 seconds"),
+            (600, days, "10 minutes"),
+            (601, days, "10 minutes 1 second"),
+            (659, days, "10 minutes 59 seconds"),
+            (660, days, "11 minutes"),
+            (661, days, "11 minutes 1 second"),
+            (3599, days, "59 minutes 59 seconds"),
+            (3600, days, "1 hour"),
+            (3601, days, "1 hour 1 second"),
+            (3659, days, "1 hour 59 seconds"),
+            (3660, days, "1 hour 1 minute"),
+            (3661, days, "1 hour 1 minute 1 second"),
+            (4199, days, "1 hour 9 minutes 59 seconds"),
+            (4200, days, "1 hour 10 minutes"),
+            (4201, days, "1 hour 10 minutes 1 second"),
+            (7199, days, "1 hour 59 minutes 59 seconds"),
+            (7200, days, "2 hours"),
+            (7201, days, "2 hours 1 second"),
+            (7259, days, "2 hours 59 seconds"),
+            (7260, days, "2 hours 1 minute"),
+            (7261, days, "2 hours 1 minute 1 second"),
+            (86399, days, "23 hours 59 minutes 59 seconds"),
+            (86400, days, "1 day"),
+            (86401, days, "1 day 1 second"),
+            (86459, days, "1 day 59 seconds"),
+            (86460, days, "1 day 1 minute"),
+            (86461, days, "1 day 1 minute 1 second"),
+            (89999, days, "1 day 59 minutes 59 seconds"),
+            (90000, days, "1 day 1 hour"),
+            (90001, days, "1 day 1 hour 1 second"),
+            (90059, days, "1 day 1 hour 59 seconds"),
+            (90060, days, "1 day 1 hour 1 minute"),
+            (90061, days, "1 day 1 hour 1 minute 1 second"),
+            (604799, days, "6 days 23 hours 59 minutes 59 seconds"),
+            (604800, days, "7 days"),
+            (604801, days, "7 days 1 second"),
+        ];
+
+        for (t, b, s) in tests {
+            assert_eq!(dissect_delta(TimeDelta::seconds(t), b, Lang::En), s);
+        }
+    }
+
+    #[test]
+    fn dissect_lang_test() {
+        let hrs = TimeBase::Hours;
+        let days = TimeBase::Days;
+
+        let tests = [
+            (Lang::De, 0, hrs, "0 Sekunden"),
+            (Lang::De, 3661, hrs, "1 Stunde 1 Minute 1 Sekunde"),
+            (
+                Lang::De,
+                97322,
+                days,
+                "1 Tag 3 Stunden 2 Minuten 2 Sekunden",
+            ),
+            (Lang::Es, 1, hrs, "1 segundo"),
+            (Lang::Es, 176522, days, "2 días 1 hora 2 minutos 2 segundos"),
+            (Lang::Fr, 0, hrs, "0 seconde"),
+            (Lang::Fr, 7322, hrs, "2 heures 2 minutes 2 secondes"),
+            (Lang::Fr, 90061, days, "1 jour 1 heure 1 minute 1 seconde"),
+            (Lang::Hu, 97322, days, "1 nap 3 óra 2 perc 2 másodperc"),
+            (Lang::Hu, 90061, hrs, "25 óra 1 perc 1 másodperc"),
+        ];
+
+        for (l, t, b, s) in tests {
+            assert_eq!(
+                dissect_delta(TimeDelta::seconds(t), b, l),
+                s,
+                "lang={:?}",
+                l
+            );
+        }
+    }
+
+    #[test]
+    fn in_range_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        //Already 2025-01-01 in Budapest, but not in UTC
+        let video = date("2024-12-31T23:30:00Z");
+        let format =
+            crate::output::DateFormat::new(Some("%Y-%m-%d"), Some("Europe/Budapest")).unwrap();
+        assert_eq!(format.render(video), "2025-01-01");
+
+        let tests = [
+            (None, None, true),
+            (Some("2025-01-01T00:00:00Z"), None, false),
+            (Some("2025-01-01T00:00:00+01:00"), None, true),
+            (None, Some("2024-12-31T23:59:59Z"), true),
+            (None, Some("2024-12-31T23:30:00Z"), true),
+            (None, Some("2024-12-31T23:29:59Z"), false),
+            (
+                Some("2024-12-31T00:00:00Z"),
+                Some("2024-12-31T23:59:59Z"),
+                true,
+            ),
+            //Exactly at either bound, also given with an offset
+            (Some("2024-12-31T23:30:00Z"), None, true),
+            (Some("2024-12-31T23:30:01Z"), None, false),
+            (Some("2025-01-01T00:30:00+01:00"), None, true),
+            (Some("2025-01-01T00:30:01+01:00"), None, false),
+            (None, Some("2025-01-01T00:30:00+01:00"), true),
+            (None, Some("2024-12-31T19:29:59-04:00"), false),
+            (
+                Some("2024-12-31T23:30:00Z"),
+                Some("2024-12-31T23:30:00Z"),
+                true,
+            ),
+        ];
+
+        for (s, e, r) in tests {
+            assert_eq!(
+                in_range(video, s.map(date), e.map(date)),
+                r,
+                "start={:?} end={:?}",
+                s,
+                e
+            );
+        }
+
+        //The whole day in the timezone, up to the last instant of its last second
+        let tests = [
+            (chrono_tz::Tz::UTC, "2024-03-30T23:59:59.999Z", false),
+            (chrono_tz::Tz::UTC, "2024-03-31T00:00:00Z", true),
+            (chrono_tz::Tz::UTC, "2024-03-31T23:59:59Z", true),
+            (chrono_tz::Tz::UTC, "2024-03-31T23:59:59.5Z", true),
+            (chrono_tz::Tz::UTC, "2024-04-01T00:00:00Z", false),
+            (chrono_tz::Europe::Berlin, "2024-03-30T22:59:59.999Z", false),
+            (chrono_tz::Europe::Berlin, "2024-03-30T23:00:00Z", true),
+            (chrono_tz::Europe::Berlin, "2024-03-31T21:59:59Z", true),
+            (chrono_tz::Europe::Berlin, "2024-03-31T21:59:59.5Z", true),
+            (chrono_tz::Europe::Berlin, "2024-03-31T22:00:00Z", false),
+        ];
+        for (zone, published, r) in tests {
+            let bound = |bound| {
+                let parsed = crate::dates::parse_date("2024-03-31", bound, video, &zone);
+                Some(parsed.unwrap().date)
+            };
+            let (start, end) = (
+                bound(crate::dates::Bound::Start),
+                bound(crate::dates::Bound::End),
+            );
+            assert_eq!(
+                in_range(date(published), start, end),
+                r,
+                "{} {}",
+                zone,
+                published
+            );
+        }
+    }
+
+    #[test]
+    fn sort_test() {
+        let video = |id: &str, date: &str, title: &str, duration: &str| {
+            let date = DateTime::<Utc>::from(DateTime::parse_from_rfc3339(date).unwrap());
+            Video::new(
+                date,
+                title.to_string(),
+                id.to_string(),
+                duration.to_string(),
+            )
+            .unwrap()
+        };
+        let fixture = || {
+            vec![
+                video("a", "2024-03-01T00:00:00Z", "beta", "PT2M"),
+                video("b", "2024-01-01T00:00:00Z", "Alpha", "PT1M"),
+                video("c", "2024-02-01T00:00:00Z", "ÉCLAIR", "PT3M"),
+                video("d", "2024-01-01T00:00:00Z", "alpha", "PT2M"),
+                video("e", "2024-04-01T00:00:00Z", "éclair", "PT10S"),
+            ]
+        };
+
+        let tests = [
+            (SortKey::Date, false, "bdcae"),
+            (SortKey::Date, true, "eacbd"),
+            (SortKey::Duration, false, "ebadc"),
+            (SortKey::Duration, true, "cadbe"),
+            (SortKey::Title, false, "bdace"),
+            (SortKey::Title, true, "ceabd"),
+        ];
+
+        for (key, desc, order) in tests {
+            let mut videos = fixture();
+            sort_videos(&mut videos, key, desc);
+            let ids: String = videos.iter().map(|v| v.id.as_str()).collect();
+            assert_eq!(ids, order, "key={:?} desc={}", key, desc);
+        }
+    }
+
+    #[test]
+    fn parse_channel_test() {
+        let item = |id: &str| {
+            serde_json::json!({
+                "id": id,
+                "snippet": { "title": format!("Title {}", &id[2..]), "customUrl": format!("@{}", &id[2..]) },
+                "statistics": { "videoCount": "42" },
+                "contentDetails": { "relatedPlaylists": { "uploads": format!("UU{}", &id[2..]) } },
+            })
+        };
+        let config = |first_match: bool, pick: Option<Pick>| Config {
+            status: Status::Stderr,
+            first_match,
+            pick,
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+
+        let json =
+            serde_json::json!({ "pageInfo": { "totalResults": 1 }, "items": [item("UCabc")] });
+        assert_eq!(
+            parse_channel(&json, "abc", &config(false, None)).unwrap(),
+            ChannelInfo {
+                id: "UCabc".into(),
+                title: "Title abc".into(),
+                uploads_playlist_id: "UUabc".into(),
+                video_count: 42,
+            }
+        );
+
+        let json = serde_json::json!({
+            "pageInfo": { "totalResults": 3 },
+            "items": [item("UCabc"), item("UCdef"), { "id": "UCghi", "snippet": { "title": "Ghi" } }],
+        });
+        let e = parse_channel(&json, "abc", &config(false, None)).unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<AmbiguousChannel>()
+                .map(|a| a.candidates.clone()),
+            Some(vec![
+                Candidate {
+                    id: "UCabc".into(),
+                    title: "Title abc".into(),
+                    custom_url: Some("@abc".into()),
+                },
+                Candidate {
+                    id: "UCdef".into(),
+                    title: "Title def".into(),
+                    custom_url: Some("@def".into()),
+                },
+                Candidate {
+                    id: "UCghi".into(),
+                    title: "Ghi".into(),
+                    custom_url: None,
+                },
+            ])
+        );
+        assert!(e
+            .to_string()
+            .ends_with("\n  2. 'Title def' (@def, UCdef)\n  3. 'Ghi' (UCghi)"));
+        assert_eq!(
+            parse_channel(&json, "abc", &config(true, None)).unwrap().id,
+            "UCabc"
+        );
+        let picked = Some(Box::new(|c: &[Candidate]| -> Option<usize> {
+            assert_eq!(c.len(), 3);
+            Some(1)
+        }) as Pick);
+        assert_eq!(
+            parse_channel(&json, "abc", &config(false, picked))
+                .unwrap()
+                .id,
+            "UCdef"
+        );
+        for pick in [None, Some(3)] {
+            let e = parse_channel(&json, "abc", &config(false, Some(Box::new(move |_| pick))))
+                .unwrap_err();
+            assert_eq!(e.to_string(), "Aborted, no channel was picked");
+        }
+
+        let json = serde_json::json!({ "pageInfo": { "totalResults": 0 }, "items": [] });
+        let e =
+            parse_channel(&json, "abc", &config(true, Some(Box::new(|_| Some(0))))).unwrap_err();
+        assert!(e.is::<NoChannel>());
+        assert!(e
+            .to_string()
+            .starts_with("No channel found with the handle '@abc'"));
+
+        let json =
+            serde_json::json!({ "pageInfo": { "totalResults": 1 }, "items": [{ "id": "UCabc" }] });
+        assert!(!parse_channel(&json, "abc", &config(false, None))
+            .unwrap_err()
+            .is::<AmbiguousChannel>());
+
+        let verify = Config {
+            verify_handle: true,
+            ..config(false, None)
+        };
+        let mut other = item("UCabc");
+        other["snippet"]["customUrl"] = serde_json::json!("@xyz");
+        let mut legacy = item("UCabc");
+        legacy["snippet"]
+            .as_object_mut()
+            .unwrap()
+            .remove("customUrl");
+        let tests = [
+            (item("UCabc"), "abc", Ok(())),
+            (item("UCabc"), "ABC", Ok(())),
+            (item("UCAbc"), "@abc", Ok(())),
+            (other, "abc", Err("has the handle '@xyz'")),
+            (legacy, "abc", Err("has no handle")),
+        ];
+        for (item, handle, result) in tests {
+            let json = serde_json::json!({ "pageInfo": { "totalResults": 1 }, "items": [item] });
+            match (parse_channel(&json, handle, &verify), result) {
+                (Ok(_), Ok(())) => (),
+                (Err(e), Err(msg)) => assert!(e.to_string().contains(msg), "{}: {}", handle, e),
+                (r, _) => panic!("{}: unexpected {:?}", handle, r.map(|c| c.id)),
+            }
+            assert!(parse_channel(&json, handle, &config(false, None)).is_ok());
+        }
+    }
+
+    #[test]
+    fn no_match_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let config = |start, end| Config {
+            status: Status::Stderr,
+            client: Box::new(fixture()),
+            start_date: start,
+            end_date: end,
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let uploads = Some((date("2023-12-31T23:59:59Z"), date("2025-01-01T00:00:00Z")));
+
+        let summary = run_collect(config(
+            Some(date("2010-01-01T00:00:00Z")),
+            Some(date("2011-12-31T23:59:59Z")),
+        ))
+        .unwrap();
+        assert!(summary.videos.is_empty());
+        assert_eq!(summary.uploads, uploads);
+        assert_eq!(summary.warnings, 1);
+
+        //The span covers the filtered out videos too
+        let summary = run_collect(config(Some(date("2024-06-01T00:00:00Z")), None)).unwrap();
+        assert_eq!(summary.videos.len(), 1);
+        assert_eq!((summary.uploads, summary.warnings), (uploads, 0));
+
+        let tests = [
+            (vec![], Some("2010-01-01T00:00:00Z"), Some("2011-12-31T23:59:59Z"), 
+                Some("The channel has uploads between 2023-12-31 and 2025-01-01, the filter 2010-01-01..2011-12-31 matched none.")),
+            (vec![], None, Some("2011-12-31T23:59:59Z"),
+                Some("The channel has uploads between 2023-12-31 and 2025-01-01, the filter ..2011-12-31 matched none.")),
+            (vec![], Some("2030-01-01T00:00:00Z"), None,
+                Some("The channel has uploads between 2023-12-31 and 2025-01-01, the filter 2030-01-01.. matched none.")),
+            (vec![], None, None, None),
+            (vec![(String::from("a"), date("2024-01-01T00:00:00Z"))], Some("2024-01-01T00:00:00Z"), None, None),
+        ];
+        for (matched, start, end, message) in tests {
+            assert_eq!(
+                no_match_message(&matched, uploads, start.map(date), end.map(date)).as_deref(),
+                message
+            );
+        }
+        assert_eq!(
+            no_match_message(&[], None, Some(date("2024-01-01T00:00:00Z")), None),
+            None
+        );
+    }
+
+    #[test]
+    fn small_run_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-small-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.csv");
+
+        let tests = [
+            (
+                "2030-01-01T00:00:00Z",
+                "2030-12-31T23:59:59Z",
+                "",
+                "Sum total: 0 seconds",
+            ),
+            (
+                "2024-01-01T00:00:00Z",
+                "2024-01-01T00:00:10Z",
+                "d",
+                "Sum total: 30 seconds",
+            ),
+            (
+                "2024-01-01T00:00:00Z",
+                "2024-12-31T23:59:59Z",
+                "acd",
+                "Sum total: 4231 seconds, or 1 hour 10 minutes 31 seconds",
+            ),
+        ];
+        for (start, end, ids, total) in tests {
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(fixture()),
+                start_date: Some(date(start)),
+                end_date: Some(date(end)),
+                output: Some(File::create(&path).unwrap()),
+                destination: crate::output::Destination::Path(String::from(path.to_str().unwrap())),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            assert_eq!(summary.count, ids.len());
+            assert_eq!(total_line(summary.total, crate::lang::Lang::En), total);
+
+            let written = std::fs::read_to_string(&path).unwrap();
+            let rows: Vec<_> = written.lines().filter(|l| !l.starts_with('#')).collect();
+            assert_eq!(rows.len(), ids.len(), "{}", written);
+            assert!(written.starts_with(crate::csv::HEADER));
+            let read: String = crate::csv::read_csv(written.as_bytes())
+                .unwrap()
+                .iter()
+                .map(|v| v.id.as_str())
+                .collect();
+            assert_eq!(read, ids);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn summary_shown_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let tests = [
+            (None, false, true),
+            (None, true, true),
+            (Some("2030-01-01T00:00:00Z"), false, true),
+            (Some("2030-01-01T00:00:00Z"), true, false),
+        ];
+        for (start, only_if_nonempty, shown) in tests {
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(fixture()),
+                start_date: start.map(date),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            assert_eq!(
+                summary.shown(only_if_nonempty),
+                shown,
+                "start={:?} only_if_nonempty={}",
+                start,
+                only_if_nonempty
+            );
+        }
+    }
+
+    #[test]
+    fn failed_run_output_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-failed-run-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.csv");
+
+        //Address of the missing response, the maximum quota and the sort key, to the rows written before the failure
+        let tests = [
+            (None, Some(1), None, None),
+            (Some("pageToken=page2&"), None, None, None),
+            (Some("id=d&"), None, Some(SortKey::Title), None),
+            (Some("id=d&"), None, None, Some("abc")),
+        ];
+        for (missing, max_quota, sort, rows) in tests {
+            let mut client = fixture();
+            client
+                .responses
+                .retain(|(p, _)| missing.is_none_or(|m| !p.contains(m)));
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                max_quota,
+                sort,
+                output: Some(File::create(&path).unwrap()),
+                destination: crate::output::Destination::Path(String::from(path.to_str().unwrap())),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            assert!(run_collect(config).is_err(), "{:?}", missing);
+
+            //Never a response of the API
+            let written = std::fs::read_to_string(&path).unwrap();
+            match rows {
+                None => assert_eq!(written, "", "{:?}", missing),
+                Some(ids) => {
+                    assert!(written.starts_with(crate::csv::HEADER), "{}", written);
+                    let read: String = crate::csv::read_csv(written.as_bytes())
+                        .unwrap()
+                        .iter()
+                        .map(|v| v.id.as_str())
+                        .collect();
+                    assert_eq!(read, ids);
+                }
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn channel_stats_test() {
+        let mut client = fixture();
+        let log = client.log.clone();
+        client.responses[0].1["items"][0]["statistics"] = serde_json::json!({
+            "videoCount": "5",
+            "viewCount": "123456",
+            "subscriberCount": "789",
+        });
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let stats = channel_stats(&config).unwrap();
+        assert_eq!(
+            (stats.subscriber_count, stats.video_count, stats.view_count),
+            (Some(789), 5, 123456)
+        );
+        assert_eq!(stats.handle, "somechannel");
+        assert_eq!(log.borrow().len(), 1);
+
+        let json = |statistics| serde_json::json!({ "items": [{ "statistics": statistics }] });
+        let tests = [
+            (
+                json(
+                    serde_json::json!({ "videoCount": "1", "viewCount": "2", "hiddenSubscriberCount": true }),
+                ),
+                Ok(None),
+            ),
+            (
+                json(
+                    serde_json::json!({ "videoCount": "1", "viewCount": "2", "subscriberCount": "3" }),
+                ),
+                Ok(Some(3)),
+            ),
+            (json(serde_json::json!({ "videoCount": "1" })), Err(())),
+            (
+                json(serde_json::json!({ "videoCount": "1", "viewCount": 2 })),
+                Err(()),
+            ),
+        ];
+        for (json, result) in tests {
+            let stats = parse_channel_stats(&json, "x", String::new());
+            assert_eq!(
+                stats.map(|s| s.subscriber_count).map_err(|_| ()),
+                result,
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn playlist_id_test() {
+        let tests = [("UUxyz", "UULFxyz"), ("UU", "UULF"), ("", "UULF")];
+        for (uploads, public) in tests {
+            assert_eq!(public_playlist_id(uploads), public);
+        }
+
+        let client = fixture();
+        let log = client.log.clone();
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        assert_eq!(playlist_id(&config).unwrap(), "UULFxyz");
+        assert_eq!(log.borrow().len(), 1);
+    }
+
+    #[test]
+    fn check_key_test() {
+        let mut client = fixture();
+        let log = client.log.clone();
+        let config = |client| Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::new())
+        };
+        assert!(check_key(&config(fixture())).is_err());
+
+        client.responses.push((
+            String::from("videos?part=id&id=jNQXAC9IVRw&"),
+            serde_json::json!({ "items": [{ "id": "jNQXAC9IVRw" }] }),
+        ));
+        check_key(&config(client)).unwrap();
+        assert_eq!(log.borrow().len(), 1);
+        assert!(!log.borrow()[0].contains("forHandle"));
+    }
+
+    #[test]
+    fn forbidden_test() {
+        let quota = serde_json::json!({ "error": {
+            "code": 403,
+            "errors": [{ "domain": "youtube.quota", "reason": "quotaExceeded" }],
+        }});
+        let referrer = serde_json::json!({ "error": {
+            "code": 403,
+            "errors": [{ "domain": "global", "reason": "forbidden" }],
+            "status": "PERMISSION_DENIED",
+            "details": [{ "reason": "API_KEY_HTTP_REFERRER_BLOCKED" }],
+        }});
+        let service = serde_json::json!({ "error": {
+            "code": 403,
+            "status": "PERMISSION_DENIED",
+            "details": [{ "reason": "API_KEY_SERVICE_BLOCKED" }],
+        }});
+        let other = serde_json::json!({ "error": {
+            "code": 403,
+            "errors": [{ "reason": "videoNotFound" }],
+        }});
+
+        let invalid = serde_json::json!({ "error": {
+            "code": 400,
+            "errors": [{ "domain": "usageLimits", "reason": "keyInvalid" }],
+            "details": [{ "reason": "API_KEY_INVALID" }],
+        }});
+
+        assert!(is_invalid_key(&invalid));
+        assert!(!is_invalid_key(&quota));
+        assert_eq!(classify_forbidden(&quota), Some(Forbidden::Quota));
+        assert_eq!(classify_forbidden(&referrer), Some(Forbidden::Restricted));
+        assert_eq!(classify_forbidden(&service), Some(Forbidden::Restricted));
+        assert_eq!(classify_forbidden(&other), None);
+        assert_eq!(classify_forbidden(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn parse_items_test() {
+        let item = |id: &str, title: &str, duration: &str| {
+            serde_json::json!({
+                "id": id,
+                "snippet": { "publishedAt": "2024-01-02T03:04:05Z", "title": title },
+                "contentDetails": { "duration": duration },
+            })
+        };
+
+        let json = serde_json::json!({ "items": [
+            item("b", "second", "PT2M"),
+            item("a", "first", "PT1M"),
+            item("b", "duplicate", "PT3M"),
+            item("c", "third", "PT3S"),
+            item("a", "duplicate", "PT4M"),
+        ]});
+        let videos: Vec<_> = parse_video_items(&json, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|(_, v)| v.unwrap())
+            .collect();
+        let ids: Vec<_> = videos
+            .iter()
+            .map(|v| (v.id.as_str(), v.title.as_str()))
+            .collect();
+        assert_eq!(ids, [("b", "second"), ("a", "first"), ("c", "third")]);
+        let total = videos
+            .iter()
+            .fold(TimeDelta::zero(), |acc, v| acc + v.delta);
+        assert_eq!(total, TimeDelta::seconds(183));
+
+        assert!(
+            parse_video_items(&serde_json::json!({ "items": [] }), None, None)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse_video_items(&serde_json::json!({}), None, None).is_err());
+
+        //Malformed durations are reported per item, recoverable ones are accepted
+        let json = serde_json::json!({ "items": [
+            item("a", "recovered", "15M33S"),
+            item("b", "malformed", "PT15X"),
+            item("c", "fine", "PT3S"),
+        ]});
+        let parsed: Vec<_> = parse_video_items(&json, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|(i, v)| (i, v.map(|v| v.duration).map_err(|_| ())))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                (String::from("a"), Ok(String::from("PT15M33S"))),
+                (String::from("b"), Err(())),
+                (String::from("c"), Ok(String::from("PT3S"))),
+            ]
+        );
+        assert!(
+            parse_video_items(&serde_json::json!({ "items": [{ "id": "x" }] }), None, None)
+                .is_err()
+        );
+
+        //Dates in the variants of RFC3339 are accepted, an unparseable one only skips its item
+        let dated = |id: &str, date: &str| {
+            serde_json::json!({
+                "id": id,
+                "snippet": { "publishedAt": date, "title": id },
+                "contentDetails": { "duration": "PT1M" },
+            })
+        };
+        let json = serde_json::json!({ "items": [
+            dated("a", "2024-01-02T03:04:05Z"),
+            dated("b", "2024-01-02T03:04:05+00:00"),
+            dated("c", "2024-01-02T03:04:05.678Z"),
+            dated("d", "sometime"),
+            dated("e", "2024-01-02 03:04:05"),
+        ]});
+        let parsed: Vec<_> = parse_video_items(&json, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|(i, v)| (i, v.map(|v| v.date.to_rfc3339())))
+            .collect();
+        assert_eq!(parsed[0].1, Ok(String::from("2024-01-02T03:04:05+00:00")));
+        assert_eq!(parsed[1].1, Ok(String::from("2024-01-02T03:04:05+00:00")));
+        assert_eq!(
+            parsed[2].1,
+            Ok(String::from("2024-01-02T03:04:05.678+00:00"))
+        );
+        assert_eq!(
+            parsed[3].1.as_ref().unwrap_err().0,
+            SkipReason::UnparseableDate
+        );
+        assert!(parsed[3]
+            .1
+            .as_ref()
+            .unwrap_err()
+            .1
+            .contains("'publishedAt'"));
+        assert_eq!(parsed[4].1, Ok(String::from("2024-01-02T03:04:05+00:00")));
+
+        //Only 'contentDetails' and 'statistics' requested
+        let published =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-05-06T07:08:09Z").unwrap());
+        let json = serde_json::json!({ "items": [{
+            "id": "x",
+            "contentDetails": { "duration": "PT1M" },
+            "statistics": { "viewCount": "1234" },
+        }]});
+        let (_, video) = parse_video_items(&json, Some(published), None)
+            .unwrap()
+            .remove(0);
+        let video = video.unwrap();
+        assert_eq!((video.date, video.title.as_str()), (published, ""));
+        assert_eq!(video.views, Some(1234));
+        assert_eq!(video.has_captions, None);
+        assert_eq!(video.definition, None);
+        assert_eq!((video.dimension, video.projection), (None, None));
+        assert!(parse_video_items(&json, None, None).is_err());
+
+        let tests = [
+            (serde_json::json!("true"), Ok(Some(true))),
+            (serde_json::json!("false"), Ok(Some(false))),
+            (serde_json::json!(true), Err(())),
+            (serde_json::json!("yes"), Err(())),
+        ];
+        for (caption, result) in tests {
+            let json = serde_json::json!({ "items": [{
+                "id": "x",
+                "contentDetails": { "duration": "PT1M", "caption": caption },
+            }]});
+            let parsed = parse_video_items(&json, Some(published), None)
+                .map(|mut v| v.remove(0).1.unwrap().has_captions)
+                .map_err(|_| ());
+            assert_eq!(parsed, result, "caption={}", caption);
+        }
+
+        let tests = [
+            (serde_json::json!("hd"), Ok(Some(Definition::Hd))),
+            (serde_json::json!("sd"), Ok(Some(Definition::Sd))),
+            (serde_json::json!("HD"), Err(())),
+            (serde_json::json!(1080), Err(())),
+        ];
+        for (definition, result) in tests {
+            let json = serde_json::json!({ "items": [{
+                "id": "x",
+                "contentDetails": { "duration": "PT1M", "definition": definition },
+            }]});
+            let parsed = parse_video_items(&json, Some(published), None)
+                .map(|mut v| v.remove(0).1.unwrap().definition)
+                .map_err(|_| ());
+            assert_eq!(parsed, result, "definition={}", definition);
+        }
+
+        let tests = [
+            (
+                serde_json::json!({ "dimension": "3d", "projection": "360" }),
+                Ok((Some(Dimension::ThreeD), Some(Projection::Spherical))),
+            ),
+            (
+                serde_json::json!({ "dimension": "2d", "projection": "rectangular" }),
+                Ok((Some(Dimension::TwoD), Some(Projection::Rectangular))),
+            ),
+            (
+                serde_json::json!({ "projection": "360" }),
+                Ok((None, Some(Projection::Spherical))),
+            ),
+            (serde_json::json!({ "dimension": "3D" }), Err(())),
+            (serde_json::json!({ "projection": 360 }), Err(())),
+        ];
+        for (fields, result) in tests {
+            let mut details = serde_json::json!({ "duration": "PT1M" });
+            for (k, v) in fields.as_object().unwrap() {
+                details[k] = v.clone();
+            }
+            let json = serde_json::json!({ "items": [{ "id": "x", "contentDetails": details }] });
+            let parsed = parse_video_items(&json, Some(published), None)
+                .map(|mut v| {
+                    let video = v.remove(0).1.unwrap();
+                    (video.dimension, video.projection)
+                })
+                .map_err(|_| ());
+            assert_eq!(parsed, result, "{}", fields);
+        }
+    }
+
+    #[test]
+    fn parts_test() {
+        let config = |count_only, with_stats| Config {
+            count_only,
+            with_stats,
+            ..Config::new(String::new(), String::new())
+        };
+
+        let tests = [
+            (false, false, "snippet%2CcontentDetails"),
+            (true, false, "contentDetails"),
+            (false, true, "snippet%2CcontentDetails%2Cstatistics"),
+            (true, true, "contentDetails%2Cstatistics"),
+        ];
+
+        for (c, w, r) in tests {
+            assert_eq!(video_parts(&config(c, w)), r);
+        }
+        let strict = Config {
+            strict_public: true,
+            ..config(false, false)
+        };
+        assert_eq!(video_parts(&strict), "snippet%2CcontentDetails%2Cstatus");
+    }
+
+    //Canned responses by a substring of the address, in order of precedence
+    struct Fixture {
+        responses: Vec<(String, serde_json::Value)>,
+        log: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    }
+    impl HttpClient for Fixture {
+        //Like the real client, with the key as the last parameter
+        fn get_json(
+            &self,
+            address: &str,
+            key: &ApiKey,
+        ) -> Result<serde_json::Value, Box<dyn Error>> {
+            let address = format!("{}&key={}", address, key.expose());
+            self.log.borrow_mut().push(address.clone());
+            match self
+                .responses
+                .iter()
+                .find(|(p, _)| address.contains(p.as_str()))
+            {
+                Some((_, json)) => Ok(json.clone()),
+                None => Err(format!("No fixture for '{}'", address).into()),
+            }
+        }
+    }
+
+    fn fixture() -> Fixture {
+        let playlist_item = |id: &str, date: &str| {
+            serde_json::json!({
+                "snippet": { "publishedAt": date, "resourceId": { "videoId": id } },
+            })
+        };
+        let video = |id: &str, date: &str, title: &str, duration: &str| {
+            (
+                format!("videos?part=snippet%2CcontentDetails&id={}&", id),
+                serde_json::json!({ "items": [{
+                    "id": id,
+                    "snippet": { "publishedAt": date, "title": title },
+                    "contentDetails": { "duration": duration },
+                }]}),
+            )
+        };
+
+        Fixture {
+            responses: vec![
+                (
+                    String::from("channels?"),
+                    serde_json::json!({
+                        "pageInfo": { "totalResults": 1 },
+                        "items": [{
+                            "id": "UCxyz",
+                            "snippet": { "title": "Some Channel" },
+                            "statistics": { "videoCount": "5" },
+                            "contentDetails": { "relatedPlaylists": { "uploads": "UUxyz" } },
+                        }],
+                    }),
+                ),
+                (
+                    String::from("playlistId=UULFxyz&maxResults=50&pageToken=&"),
+                    serde_json::json!({
+                        "pageInfo": { "totalResults": 5 },
+                        "nextPageToken": "page2",
+                        "items": [
+                            playlist_item("a", "2024-03-01T00:00:00Z"),
+                            playlist_item("b", "2023-12-31T23:59:59Z"),
+                            playlist_item("c", "2024-02-01T00:00:00Z"),
+                        ],
+                    }),
+                ),
+                (
+                    String::from("playlistId=UULFxyz&maxResults=50&pageToken=page2&"),
+                    serde_json::json!({
+                        "pageInfo": { "totalResults": 5 },
+                        "items": [
+                            playlist_item("c", "2024-02-01T00:00:00Z"),
+                            playlist_item("d", "2024-01-01T00:00:00Z"),
+                            playlist_item("e", "2025-01-01T00:00:00Z"),
+                        ],
+                    }),
+                ),
+                (
+                    String::from("playlistId=PLone&"),
+                    serde_json::json!({
+                        "pageInfo": { "totalResults": 2 },
+                        "items": [
+                            playlist_item("a", "2024-03-01T00:00:00Z"),
+                            playlist_item("c", "2024-02-01T00:00:00Z"),
+                        ],
+                    }),
+                ),
+                (
+                    String::from("playlistId=PLtwo&"),
+                    serde_json::json!({
+                        "pageInfo": { "totalResults": 3 },
+                        "items": [
+                            playlist_item("c", "2024-02-01T00:00:00Z"),
+                            playlist_item("d", "2024-01-01T00:00:00Z"),
+                            playlist_item("e", "2025-01-01T00:00:00Z"),
+                        ],
+                    }),
+                ),
+                video("a", "2024-03-01T00:00:00Z", "First", "PT10M"),
+                video("b", "2023-12-31T23:59:59Z", "Too early", "PT1H"),
+                video("c", "2024-02-01T00:00:00Z", "Second", "PT1H1S"),
+                video("d", "2024-01-01T00:00:00Z", "Third", "PT30S"),
+                video("e", "2025-01-01T00:00:00Z", "Too late", "PT1H"),
+            ],
+            log: Default::default(),
+        }
+    }
+
+    #[test]
+    fn run_collect_test() {
+        let date = |s| {
+            Some(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let path =
+            std::env::temp_dir().join(format!("yt_api_videosum_run_{}.csv", std::process::id()));
+
+        let tests = [
+            (None, false, "acd"),
+            (Some(SortKey::Duration), true, "cad"),
+            (Some(SortKey::Date), false, "dca"),
+        ];
+
+        for (sort, sort_desc, ids) in tests {
+            let client = fixture();
+            let log = client.log.clone();
+            let config = Config {
+                start_date: date("2024-01-01T00:00:00Z"),
+                end_date: date("2024-12-31T23:59:59Z"),
+                output: Some(File::create(&path).unwrap()),
+                footer: false,
+                sort,
+                sort_desc,
+                status: Status::Stderr,
+                client: Box::new(client),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+
+            assert_eq!(summary.channel.title, "Some Channel");
+            let order: String = summary.videos.iter().map(|v| v.id.as_str()).collect();
+            assert_eq!(order, ids);
+            assert_eq!(summary.total, TimeDelta::seconds(600 + 3601 + 30));
+
+            //Both pages, then only the videos within the range, each once
+            let log = log.borrow();
+            assert!(log[0].contains("forHandle=somechannel&key=KEY"));
+            assert!(log[1].contains("pageToken=&"));
+            assert!(log[2].contains("pageToken=page2&"));
+            assert_eq!(log.len(), 6);
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            assert!(content.contains("\n#start=2024-01-01T00:00:00Z\n"));
+            assert!(content.contains("\n#matched_video_count=3\n"));
+            assert!(!content.contains("KEY"));
+            let reloaded = read_csv(content.as_bytes()).unwrap();
+            let order: String = reloaded.iter().map(|v| v.id.as_str()).collect();
+            assert_eq!(order, ids);
+            assert_eq!(
+                summary.output.unwrap().size,
+                std::fs::metadata(&path).unwrap().len()
+            );
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stream_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-stream-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.csv");
+        let config = |stream: bool, sort: Option<SortKey>| Config {
+            status: Status::Stderr,
+            client: Box::new(fixture()),
+            output: Some(File::create(&path).unwrap()),
+            stream,
+            sort,
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let ids = |path: &std::path::Path| -> Vec<String> {
+            let written = std::fs::read_to_string(path).unwrap();
+            read_csv(written.as_bytes())
+                .unwrap()
+                .into_iter()
+                .map(|v| v.id)
+                .collect()
+        };
+
+        let kept = run_collect(config(false, None)).unwrap();
+        let kept_ids = ids(&path);
+        let streamed = run_collect(config(true, None)).unwrap();
+        assert!(streamed.videos.is_empty());
+        assert_eq!(streamed.count, 5);
+        assert_eq!(
+            (streamed.count, streamed.total),
+            (kept.videos.len(), kept.total)
+        );
+        assert_eq!(ids(&path), kept_ids);
+        assert!(run_collect(config(true, Some(SortKey::Date))).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn map_items_test() {
+        let item = |id: &str| {
+            (
+                String::from(id),
+                Video::new(
+                    Utc::now(),
+                    String::new(),
+                    String::from(id),
+                    String::from("PT1M"),
+                )
+                .map_err(|e| (SkipReason::UnparseableDuration, e)),
+            )
+        };
+        let ids = |map: &std::collections::HashMap<String, Result<Video, (SkipReason, String)>>| {
+            let mut ids: Vec<_> = map
+                .iter()
+                .map(|(k, v)| {
+                    (
+                        k.clone(),
+                        v.as_ref()
+                            .map(|v| v.id.clone())
+                            .map_err(|(r, e)| format!("{}: {}", r, e)),
+                    )
+                })
+                .collect();
+            ids.sort();
+            ids
+        };
+        let ok = |k: &str, v: &str| (String::from(k), Ok(String::from(v)));
+        let err = |k: &str, e: &str| (String::from(k), Err(String::from(e)));
+        let missing = "missing: Not in the response, it may have been deleted or made private";
+
+        //Reordered, and one not requested
+        assert_eq!(
+            ids(&map_items(
+                &["a", "b"],
+                vec![item("c"), item("b"), item("a")]
+            )),
+            [ok("a", "a"), ok("b", "b")]
+        );
+        assert_eq!(
+            ids(&map_items(&["a", "b"], vec![item("b")])),
+            [err("a", missing), ok("b", "b")]
+        );
+        assert_eq!(ids(&map_items(&["a"], vec![])), [err("a", missing)]);
+        assert_eq!(
+            ids(&map_items(&["a"], vec![item("x")])),
+            [err("a", "missing: The response is of video 'x' instead")]
+        );
+        assert_eq!(
+            ids(&map_items(&["a"], vec![item("x"), item("y")])),
+            [err("a", missing)]
+        );
+
+        //Skipped with a warning, instead of stopping the run
+        for mismatched in [false, true] {
+            let mut client = fixture();
+            let json = &mut client
+                .responses
+                .iter_mut()
+                .find(|(p, _)| p.contains("id=d&"))
+                .unwrap()
+                .1;
+            let mut other = json["items"][0].clone();
+            other["id"] = serde_json::json!("x");
+            json["items"] = match mismatched {
+                true => serde_json::json!([other]),
+                false => serde_json::json!([]),
+            };
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            let ids: String = summary.videos.iter().map(|v| v.id.as_str()).collect();
+            assert_eq!((ids.as_str(), summary.warnings), ("abce", 1));
+        }
+    }
+
+    #[test]
+    fn page_token_test() {
+        let tests = [
+            (
+                serde_json::json!({ "nextPageToken": "page2" }),
+                Ok(Some("page2")),
+            ),
+            (serde_json::json!({ "nextPageToken": "" }), Ok(None)),
+            (serde_json::json!({ "nextPageToken": " \t" }), Ok(None)),
+            (serde_json::json!({ "nextPageToken": null }), Ok(None)),
+            (serde_json::json!({}), Ok(None)),
+            (serde_json::json!({ "nextPageToken": 2 }), Err(())),
+        ];
+        for (json, result) in tests {
+            assert_eq!(
+                next_page_token(&json, "playlistItems response")
+                    .map(|t| t.map(|t| t.to_string()))
+                    .map_err(|_| ()),
+                result.map(|t| t.map(String::from)),
+                "{}",
+                json
+            );
+        }
+
+        //Neither an empty token nor a repeated one is requested, even if more results are claimed
+        for (page, token, requests) in [(1, "", 1), (2, "page2", 2)] {
+            let mut client = fixture();
+            let log = client.log.clone();
+            let json = &mut client
+                .responses
+                .iter_mut()
+                .filter(|(p, _)| p.contains("playlistId=UULFxyz"))
+                .nth(page - 1)
+                .unwrap()
+                .1;
+            json["nextPageToken"] = serde_json::json!(token);
+            json["pageInfo"]["totalResults"] = serde_json::json!(50);
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            assert!(run_collect(config).is_ok());
+            let pages = log
+                .borrow()
+                .iter()
+                .filter(|a| a.contains("/playlistItems?"))
+                .count();
+            assert_eq!(pages, requests, "token={:?}", token);
+        }
+    }
+
+    #[test]
+    fn result_count_test() {
+        let max = u32::MAX as usize;
+        let tests = [
+            (0, Some(0)),
+            (5, Some(5)),
+            (u32::MAX as u64, Some(max)),
+            (u32::MAX as u64 + 1, None),
+            (u64::MAX, None),
+        ];
+        for (total, count) in tests {
+            match (result_count(total, max), count) {
+                (Ok(n), Some(c)) => assert_eq!(n, c),
+                (Err(e), None) => assert!(e.to_string().contains("Implausible"), "{}", e),
+                (r, _) => panic!("{}: unexpected {:?}", total, r.map_err(|e| e.to_string())),
+            }
+        }
+
+        //The count of the first page is kept, whether a later one claims more or fewer
+        for (first, second, requests) in [(5, 50, 2), (50, 4, 3)] {
+            let mut client = fixture();
+            let log = client.log.clone();
+            let mut pages = client
+                .responses
+                .iter_mut()
+                .filter(|(p, _)| p.contains("playlistId=UULFxyz"));
+            pages.next().unwrap().1["pageInfo"]["totalResults"] = serde_json::json!(first);
+            let json = &mut pages.next().unwrap().1;
+            json["pageInfo"]["totalResults"] = serde_json::json!(second);
+            json["nextPageToken"] = serde_json::json!("page3");
+            client.responses.push((
+                String::from("playlistId=UULFxyz&maxResults=50&pageToken=page3&"),
+                serde_json::json!({ "pageInfo": { "totalResults": second }, "items": [] }),
+            ));
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            assert_eq!(run_collect(config).unwrap().videos.len(), 5);
+            let pages = log
+                .borrow()
+                .iter()
+                .filter(|a| a.contains("/playlistItems?"))
+                .count();
+            assert_eq!(pages, requests, "{} then {}", first, second);
+        }
+    }
+
+    #[test]
+    fn response_context_test() {
+        let mut client = fixture();
+        let (_, json) = client
+            .responses
+            .iter_mut()
+            .find(|(p, _)| p.contains("id=c&"))
+            .unwrap();
+        json["items"][0]["snippet"]
+            .as_object_mut()
+            .unwrap()
+            .remove("title");
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let e = run_collect(config).err().unwrap().to_string();
+        assert!(
+            e.starts_with(
+                "Could not find 'title' field (videos response, video 'c'), in {\"publishedAt\""
+            ),
+            "{}",
+            e
+        );
+
+        let mut client = fixture();
+        client.responses[2].1["items"][1]["snippet"]["resourceId"]["videoId"] =
+            serde_json::json!(7);
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let e = run_collect(config).err().unwrap().to_string();
+        assert!(
+            e.starts_with("Invalid 'videoId' format (playlistItems response, page 2 of playlist 'UULFxyz', item 1), found 7"),
+            "{}",
+            e
+        );
+
+        let json = serde_json::json!({ "a": { "b": "x".repeat(200) }, "n": "1" });
+        let tests = [
+            ("/a/b", Ok("x".repeat(200))),
+            ("/n", Ok(String::from("1"))),
+            (
+                "/a/c",
+                Err(format!(
+                    "Could not find 'c' field (ctx), in {{\"b\":\"{}...",
+                    "x".repeat(114)
+                )),
+            ),
+            (
+                "/a/c/d",
+                Err(String::from("Could not find 'd' field (ctx), in {\"b\"")),
+            ),
+            (
+                "/a",
+                Err(String::from("Invalid 'a' format (ctx), found {\"b\"")),
+            ),
+            (
+                "/z",
+                Err(String::from("Could not find 'z' field (ctx), in {\"a\"")),
+            ),
+        ];
+        for (pointer, result) in tests {
+            match (get_str(&json, pointer, "ctx"), result) {
+                (Ok(s), Ok(expected)) => assert_eq!(s, expected),
+                (Err(e), Err(expected)) => {
+                    assert!(e.to_string().starts_with(&expected), "{}: {}", pointer, e);
+                    assert!(e.to_string().chars().count() < 200, "{}", e);
+                }
+                (r, _) => panic!("{}: unexpected {:?}", pointer, r.map_err(|e| e.to_string())),
+            }
+        }
+        assert!(get_u64(&json, "/n", "ctx").is_err());
+    }
+
+    #[test]
+    fn keep_failed_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-failed-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.csv");
+        let path = String::from(path.to_str().unwrap());
+        let kept = format!("{}.error.json", path);
+
+        let broken = [
+            (
+                "id=d&",
+                serde_json::json!({ "items": [{ "id": "d", "snippet": 42 }] }),
+            ),
+            ("pageToken=page2&", serde_json::json!({ "items": "none" })),
+        ];
+        for (address, body) in broken {
+            let mut client = fixture();
+            client
+                .responses
+                .iter_mut()
+                .find(|(p, _)| p.contains(address))
+                .unwrap()
+                .1 = body.clone();
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                output: Some(File::create(&path).unwrap()),
+                destination: crate::output::Destination::Path(path.clone()),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let e = run_collect(config).err().unwrap();
+            assert!(
+                e.to_string()
+                    .ends_with(&format!("(response kept in '{}')", kept)),
+                "{}",
+                e
+            );
+            let json: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&kept).unwrap()).unwrap();
+            assert_eq!(json["response"], body);
+            assert!(json["request"]
+                .as_str()
+                .unwrap()
+                .contains(address.trim_end_matches('&')));
+            assert!(!json["request"].as_str().unwrap().contains("KEY"));
+            std::fs::remove_file(&kept).unwrap();
+        }
+
+        //Nothing is kept without an output
+        let mut client = fixture();
+        client
+            .responses
+            .iter_mut()
+            .find(|(p, _)| p.contains("id=d&"))
+            .unwrap()
+            .1 = serde_json::json!({});
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            destination: crate::output::Destination::Path(path.clone()),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        assert!(run_collect(config).is_err());
+        assert!(!std::path::Path::new(&kept).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn output_line_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-message-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = String::from(dir.join("out.csv").to_str().unwrap());
+
+        let config = |output: Option<File>, destination| Config {
+            status: Status::Stderr,
+            client: Box::new(fixture()),
+            output,
+            destination,
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        use crate::output::Destination;
+        let tests = [
+            (
+                true,
+                Destination::Path(path.clone()),
+                format!("Success, output written to '{}'.", path),
+            ),
+            (
+                true,
+                Destination::Stdout,
+                String::from("Success, output written to stdout."),
+            ),
+            //Not claiming the default path for a file given without one
+            (
+                true,
+                Destination::default(),
+                String::from("Success, output written."),
+            ),
+            (
+                false,
+                Destination::Path(path.clone()),
+                String::from("Success."),
+            ),
+        ];
+        for (given, destination, expected) in tests {
+            let output = given.then(|| File::create(&path).unwrap());
+            let summary = run_collect(config(output, destination)).unwrap();
+            assert_eq!(summary.output_line(), expected);
+        }
+
+        let mut summary = run_collect(config(
+            Some(File::create(&path).unwrap()),
+            Destination::Fd(3),
+        ))
+        .unwrap();
+        summary.interrupted = true;
+        summary.output.as_mut().unwrap().compressed = true;
+        let size = summary.output.as_ref().unwrap().size;
+        assert_eq!(
+            summary.output_line(),
+            format!(
+                "Interrupted, output written to file descriptor 3 ({} bytes compressed).",
+                size
+            )
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn channel_pick_test() {
+        let channels = |n: usize| {
+            let mut client = fixture();
+            let item = client.responses[0].1["items"][0].clone();
+            let items: Vec<_> = (0..n)
+                .map(|i| {
+                    let mut item = item.clone();
+                    item["id"] = serde_json::json!(format!("UC{}", i));
+                    item
+                })
+                .collect();
+            client.responses[0].1 =
+                serde_json::json!({ "pageInfo": { "totalResults": n }, "items": items });
+            client
+        };
+        let config = |client, pick: Option<Pick>| Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            pick,
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+
+        let summary = run_collect(config(channels(3), Some(Box::new(|_| Some(2))))).unwrap();
+        assert_eq!(summary.channel.id, "UC2");
+        assert_eq!(summary.videos.len(), 5);
+        let e = run_collect(config(channels(3), None)).err().unwrap();
+        assert_eq!(
+            e.downcast_ref::<AmbiguousChannel>()
+                .unwrap()
+                .candidates
+                .len(),
+            3
+        );
+        //Not asked for a single result
+        let summary = run_collect(config(channels(1), Some(Box::new(|_| None)))).unwrap();
+        assert_eq!(summary.channel.id, "UC0");
+        let e = run_collect(config(channels(0), Some(Box::new(|_| Some(0)))))
+            .err()
+            .unwrap();
+        assert!(e.is::<NoChannel>());
+    }
+
+    #[test]
+    fn run_collect_error_test() {
+        let mut client = fixture();
+        client.responses.retain(|(p, _)| !p.contains("id=c&"));
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let e = run_collect(config).err().unwrap();
+        assert!(e.to_string().contains("No fixture"), "{}", e);
+
+        let mut client = fixture();
+        client.responses[0].1["pageInfo"]["totalResults"] = serde_json::json!(2);
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        assert!(run_collect(config).err().unwrap().is::<AmbiguousChannel>());
+
+        //An inverted range fails before any request
+        let client = fixture();
+        let log = client.log.clone();
+        let date = |s| {
+            Some(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            start_date: date("2024-06-01T00:00:00Z"),
+            end_date: date("2024-01-01T00:00:00Z"),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let e = run_collect(config).err().unwrap();
+        assert!(e.to_string().contains("is after the end date"), "{}", e);
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn unparseable_date_test() {
+        //In the playlist, the item is skipped with a warning, and the rest summed
+        let mut client = fixture();
+        client.responses[1].1["items"][0]["snippet"]["publishedAt"] =
+            serde_json::json!("2024-03-01");
+        client.responses[1].1["items"][2]["snippet"]["publishedAt"] =
+            serde_json::json!("2024-02-01T00:00:00.000+0000");
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+        let ids: String = summary.videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, "bcde");
+        assert_eq!(summary.warnings, 1);
+    }
+
+    #[test]
+    fn since_last_run_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let last_run = crate::state::LastRun {
+            path: std::env::temp_dir()
+                .join(format!("yt_api_videosum_since_{}.json", std::process::id())),
+            channel: String::from("somechannel"),
+        };
+        let _ = std::fs::remove_file(&last_run.path);
+        let run = |client: Fixture, start_date, end_date| {
+            let config = Config {
+                start_date,
+                end_date,
+                status: Status::Stderr,
+                client: Box::new(client),
+                state: Some(last_run.clone()),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            run_collect(config).unwrap()
+        };
+        let newest = || {
+            crate::state::State::load(&last_run.path)
+                .unwrap()
+                .newest("somechannel")
+        };
+
+        //A skipped video leaves the state as it was
+        let mut client = fixture();
+        client.responses.retain(|(p, _)| !p.contains("id=d&"));
+        client.responses.push((
+            String::from("id=d&"),
+            serde_json::json!({ "items": [{
+                "id": "d",
+                "contentDetails": { "duration": "later" },
+            }]}),
+        ));
+        let summary = run(client, None, Some(date("2024-02-15T00:00:00Z")));
+        assert_eq!(summary.warnings, 1);
+        assert_eq!(newest(), None);
+
+        let summary = run(fixture(), None, Some(date("2024-02-15T00:00:00Z")));
+        let ids: String = summary.videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, "bcd");
+        assert_eq!(newest(), Some(date("2024-02-01T00:00:00Z")));
+
+        //Only the videos published since are fetched the second time
+        let client = fixture();
+        let log = client.log.clone();
+        let start = crate::state::start_after(newest().unwrap());
+        let summary = run(client, Some(start), None);
+        let ids: String = summary.videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, "ae");
+        let fetched: Vec<_> = log
+            .borrow()
+            .iter()
+            .filter(|a| a.contains("/videos?"))
+            .cloned()
+            .collect();
+        assert_eq!(fetched.len(), 2, "{:?}", fetched);
+        assert_eq!(newest(), Some(date("2025-01-01T00:00:00Z")));
+
+        std::fs::remove_file(&last_run.path).unwrap();
+    }
+
+    #[test]
+    fn extra_outputs_test() {
+        use crate::output::{ExtraOutput, Format};
+
+        let dir =
+            std::env::temp_dir().join(format!("yt_api_videosum_extra_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let extra = |name: &str, format| {
+            let path = dir.join(name);
+            ExtraOutput {
+                path: path.display().to_string(),
+                format,
+                file: File::create(&path).unwrap(),
+            }
+        };
+        let readonly = dir.join("readonly.md");
+        std::fs::write(&readonly, "").unwrap();
+
+        let config = Config {
+            output: Some(File::create(dir.join("report.csv")).unwrap()),
+            extra_outputs: vec![
+                extra("report.json", Format::Json),
+                ExtraOutput {
+                    path: readonly.display().to_string(),
+                    format: Format::Markdown,
+                    file: File::open(&readonly).unwrap(),
+                },
+                extra("report.md", Format::Markdown),
+            ],
+            status: Status::Stderr,
+            client: Box::new(fixture()),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+        assert_eq!(summary.videos.len(), 5);
+
+        let results: Vec<_> = summary
+            .extra_outputs
+            .iter()
+            .map(|(_, r)| r.is_ok())
+            .collect();
+        assert_eq!(results, [true, false, true]);
+        assert_eq!(summary.extra_outputs[1].0, readonly.display().to_string());
+
+        let csv = read_csv(
+            std::fs::read_to_string(dir.join("report.csv"))
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(csv.len(), 5);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["videos"].as_array().unwrap().len(), 5);
+        assert_eq!(json["videos"][0]["videoId"], "a");
+        assert_eq!(json["metadata"]["channel"], "@somechannel");
+        assert_eq!(
+            json["summary"]["total_seconds"],
+            600 + 3600 + 3601 + 30 + 3600
+        );
+        assert!(!json.to_string().contains("KEY"));
+
+        let md = std::fs::read_to_string(dir.join("report.md")).unwrap();
+        let rows: Vec<_> = md.lines().filter(|l| l.starts_with("| ")).collect();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows[1],
+            "| 2024-03-01 | First | 0:10:00 | [a](https://www.youtube.com/watch?v=a) |"
+        );
+        assert!(md.contains("channel_id=UCxyz"));
+
+        assert_eq!(std::fs::read_to_string(&readonly).unwrap(), "");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cache_test() {
+        let path =
+            std::env::temp_dir().join(format!("yt_api_videosum_run_cache_{}", std::process::id()));
+        let cache = crate::cache::ChannelCache::new(&path, TimeDelta::days(30));
+
+        for channel_queries in [1, 0] {
+            let client = fixture();
+            let log = client.log.clone();
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                cache: Some(cache.clone()),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            assert_eq!(summary.channel.uploads_playlist_id, "UUxyz");
+            assert_eq!(summary.videos.len(), 5);
+            let count = log
+                .borrow()
+                .iter()
+                .filter(|a| a.contains("/channels?"))
+                .count();
+            assert_eq!(count, channel_queries);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn playlists_test() {
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+        let client = fixture();
+        let log = client.log.clone();
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            end_date: Some(date("2024-12-31T00:00:00Z")),
+            playlists: vec![String::from("PLone"), String::from("PLtwo")],
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+
+        let ids: Vec<&str> = summary.videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c", "d"]);
+        assert_eq!(summary.total, TimeDelta::seconds(10 * 60 + 3601 + 30));
+        assert!(!log.borrow().iter().any(|a| a.contains("playlistId=UULF")));
+    }
+
+    #[test]
+    fn verbose_test() {
+        struct Capture(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+        impl Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut logs = Vec::new();
+        for level in [0, 1, 2] {
+            let captured = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(fixture()),
+                verbose: crate::verbose::Verbose::with_writer(
+                    level,
+                    Box::new(Capture(captured.clone())),
+                ),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            run_collect(config).unwrap();
+            let log = String::from_utf8(captured.take()).unwrap();
+            assert!(!log.contains("KEY"), "{}", log);
+            logs.push(log);
+        }
+
+        assert_eq!(logs[0], "");
+        assert!(logs[1].contains(
+            "GET https://youtube.googleapis.com/youtube/v3/playlistItems?part=id%2Csnippet&playlistId=UULFxyz&maxResults=50&pageToken=page2&key=***\n"
+        ));
+        assert!(logs[1].contains("next page token 'page2'"));
+        assert!(!logs[1].contains("bytes"));
+        assert!(logs[2].contains(" bytes\n") && logs[2].contains("Parsed in "));
+    }
+
+    #[test]
+    fn status_test() {
+        let tests = [
+            (Status::Stdout, Status::Stdout),
+            (Status::Stderr, Status::Stderr),
+            (Status::Log { stderr: false }, Status::Stdout),
+            (Status::Log { stderr: true }, Status::Stderr),
+        ];
+        for (status, direct) in tests {
+            assert_eq!(status.direct(), direct, "{:?}", status);
+        }
+        assert_eq!(Status::Stdout.progress(), Status::Stderr);
+        assert_eq!(
+            Status::Log { stderr: false }.progress(),
+            Status::Log { stderr: false }
+        );
+
+        //Complete lines are taken as records, the rest is kept for the next message
+        let log = Status::Log { stderr: true };
+        status!(log, "Querying channel info...");
+        LOG_LINE.with_borrow(|l| assert_eq!(l, "Querying channel info..."));
+        statusln!(log, " done\nWarning: x\n");
+        status!(log, "Querying");
+        LOG_LINE.with_borrow(|l| assert_eq!(l, "Querying"));
+        assert!(!log.is_terminal());
+    }
+
+    #[test]
+    fn api_key_test() {
+        assert_eq!(
+            api_url("channels", &[("part", "id"), ("forHandle", "@x")]),
+            "https://youtube.googleapis.com/youtube/v3/channels?part=id&forHandle=@x"
+        );
+
+        let config = Config::new(String::from("SECRET"), String::from("somechannel"));
+        assert_eq!(format!("{:?}", config.key), "ApiKey(***)");
+        assert_eq!(config.key.redact("a?key=SECRET SECRET"), "a?key=*** ***");
+        assert_eq!(ApiKey::new(String::new()).redact("abc"), "abc");
+
+        struct Failing;
+        impl HttpClient for Failing {
+            fn get_json(
+                &self,
+                address: &str,
+                key: &ApiKey,
+            ) -> Result<serde_json::Value, Box<dyn Error>> {
+                assert!(!address.contains("SECRET"));
+                Err(format!("{}: failed with {}", address, key.expose()).into())
+            }
+        }
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(Failing),
+            ..config
+        };
+        let e = fetch(&config, "https://a/b?part=id").unwrap_err();
+        assert_eq!(e.to_string(), "https://a/b?part=id: failed with ***");
+    }
+
+    #[test]
+    fn estimate_test() {
+        let latency = std::time::Duration::from_millis(120);
+        let tests = [(0, 0), (1, 120), (50, 6000), (1862, 223440)];
+        for (count, millis) in tests {
+            let e = estimate(count, 3, latency);
+            assert_eq!((e.units, e.spent), (count, 3), "count={}", count);
+            assert_eq!(e.duration.as_millis(), millis, "count={}", count);
+        }
+
+        for (answer, result) in [(true, 5), (false, 0)] {
+            let client = fixture();
+            let log = client.log.clone();
+            let asked = std::rc::Rc::new(std::cell::Cell::new(None));
+            let a = asked.clone();
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                confirm: Some(Box::new(move |e| {
+                    a.set(Some(*e));
+                    answer
+                })),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config);
+
+            assert_eq!(asked.get().map(|e| (e.videos, e.spent)), Some((5, 3)));
+            assert_eq!(summary.map(|s| s.videos.len()).unwrap_or_default(), result);
+            let fetched = log
+                .borrow()
+                .iter()
+                .filter(|a| a.contains("/videos?"))
+                .count();
+            assert_eq!(fetched > 0, answer);
+        }
+
+        let client = fixture();
+        let log = client.log.clone();
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            max_quota: Some(1),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let e = run_estimate(config).unwrap();
+        assert_eq!((e.videos, e.spent), (5, 3));
+        assert!(!log.borrow().iter().any(|a| a.contains("/videos?")));
+    }
+
+    #[test]
+    fn max_quota_test() {
+        //1 for the channel, 2 playlist pages and 5 videos
+        for (max, ok) in [(None, true), (Some(8), true), (Some(7), false)] {
+            let client = fixture();
+            let log = client.log.clone();
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                max_quota: max,
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let result = run_collect(config);
+
+            assert_eq!(result.is_ok(), ok, "max={:?}", max);
+            let fetched = log
+                .borrow()
+                .iter()
+                .filter(|a| a.contains("/videos?"))
+                .count();
+            assert_eq!(fetched, if ok { 5 } else { 0 }, "max={:?}", max);
+        }
+    }
+
+    #[test]
+    fn region_test() {
+        let restricted = [
+            ("id=a&", serde_json::json!({ "blocked": ["DE", "AT"] })),
+            ("id=c&", serde_json::json!({ "allowed": ["US"] })),
+        ];
+        for (region, count) in [(None, 5), (Some("DE"), 3), (Some("us"), 5), (Some("FR"), 4)] {
+            let mut client = fixture();
+            for (pattern, r) in &restricted {
+                let (_, json) = client
+                    .responses
+                    .iter_mut()
+                    .find(|(p, _)| p.contains(pattern))
+                    .unwrap();
+                json["items"][0]["contentDetails"]["regionRestriction"] = r.clone();
+            }
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                exclude_region_blocked: region.map(String::from),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            assert_eq!(summary.videos.len(), count, "region={:?}", region);
+        }
+
+        let tests = [
+            (serde_json::json!({ "blocked": [] }), Some(false)),
+            (serde_json::json!({ "allowed": [] }), Some(true)),
+            (
+                serde_json::json!({ "allowed": ["de"], "blocked": ["DE"] }),
+                Some(false),
+            ),
+            (serde_json::json!({}), None),
+            (serde_json::json!({ "blocked": "DE" }), None),
+            (serde_json::json!({ "blocked": [1] }), None),
+        ];
+        for (json, blocked) in tests {
+            let r = parse_region_restriction(&json).ok();
+            assert_eq!(r.map(|r| r.blocks("DE")), blocked, "{}", json);
+        }
+    }
+
+    #[test]
+    fn strict_public_test() {
+        let statuses = [
+            ("id=a&", Some("public")),
+            ("id=b&", Some("unlisted")),
+            ("id=c&", Some("public")),
+            ("id=d&", Some("private")),
+            ("id=e&", None),
+        ];
+        for (strict, ids) in [(false, "abcde"), (true, "ac")] {
+            let mut client = fixture();
+            for (pattern, s) in &statuses {
+                let (p, json) = client
+                    .responses
+                    .iter_mut()
+                    .find(|(p, _)| p.contains(pattern))
+                    .unwrap();
+                if strict {
+                    *p = p.replace("contentDetails&", "contentDetails%2Cstatus&");
+                }
+                if let Some(s) = s {
+                    json["items"][0]["status"] = serde_json::json!({ "privacyStatus": s });
+                }
+            }
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                strict_public: strict,
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            let mut found: Vec<&str> = summary.videos.iter().map(|v| v.id.as_str()).collect();
+            found.sort();
+            assert_eq!(found.concat(), ids, "strict={}", strict);
+        }
+
+        let json = serde_json::json!({ "items": [{
+            "id": "x",
+            "snippet": { "publishedAt": "2024-01-02T03:04:05Z", "title": "x" },
+            "contentDetails": { "duration": "PT1M" },
+            "status": { "privacyStatus": 1 },
+        }]});
+        assert!(parse_video_items(&json, None, None).is_err());
+    }
+
+    #[test]
+    fn skipped_report_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-skipped-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("skipped.csv");
+
+        let mut client = fixture();
+        let (_, json) = client
+            .responses
+            .iter_mut()
+            .find(|(p, _)| p.contains("id=c&"))
+            .unwrap();
+        json["items"] = serde_json::json!([]);
+        let (_, json) = client
+            .responses
+            .iter_mut()
+            .find(|(p, _)| p.contains("id=e&"))
+            .unwrap();
+        json["items"][0]["contentDetails"]["duration"] = serde_json::json!("PT1X");
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            title_filter: Some(TitleFilter {
+                any: vec![String::from("i")],
+                all: Vec::new(),
+            }),
+            skipped_output: Some((crate::output::Format::Csv, File::create(&path).unwrap())),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+        let mut skipped: Vec<_> = summary
+            .skipped
+            .iter()
+            .map(|s| (s.id.as_str(), s.reason))
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            [
+                ("b", SkipReason::TitleFilter),
+                ("c", SkipReason::Missing),
+                ("e", SkipReason::UnparseableDuration),
+            ]
+        );
+        assert_eq!(summary.warnings, 2);
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            written.starts_with("#video_id,reason,detail\n"),
+            "{}",
+            written
+        );
+        assert!(written.contains("\nc,missing,"), "{}", written);
+        assert_eq!(written.lines().count(), 4);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn thumbnail_test() {
+        let url = |q: &str| format!("https://i.ytimg.com/vi/x/{}.jpg", q);
+        let all = serde_json::json!({
+            "default": { "url": url("default") },
+            "medium": { "url": url("mqdefault") },
+            "high": { "url": url("hqdefault") },
+            "standard": { "url": url("sddefault") },
+            "maxres": { "url": url("maxresdefault") },
+        });
+        let sd = serde_json::json!({
+            "default": { "url": url("default") },
+            "high": { "url": url("hqdefault") },
+        });
+        let tests = [
+            (&all, ThumbnailQuality::Maxres, Some("maxresdefault")),
+            (&all, ThumbnailQuality::High, Some("hqdefault")),
+            (&all, ThumbnailQuality::Default, Some("default")),
+            (&sd, ThumbnailQuality::Maxres, Some("hqdefault")),
+            (&sd, ThumbnailQuality::Medium, Some("default")),
+            (&serde_json::json!({}), ThumbnailQuality::High, None),
+        ];
+        for (thumbnails, quality, expected) in tests {
+            assert_eq!(
+                thumbnail_url(thumbnails, quality).unwrap(),
+                expected.map(url),
+                "{} of {}",
+                quality,
+                thumbnails
+            );
+        }
+        assert!(thumbnail_url(&serde_json::json!({ "high": {} }), ThumbnailQuality::High).is_err());
+        assert_eq!(
+            "maxres".parse::<ThumbnailQuality>(),
+            Ok(ThumbnailQuality::Maxres)
+        );
+        assert!("standard".parse::<ThumbnailQuality>().is_err());
+
+        //Only kept if asked for
+        let json = serde_json::json!({ "items": [{
+            "id": "x",
+            "snippet": {
+                "publishedAt": "2024-01-02T03:04:05Z",
+                "title": "x",
+                "thumbnails": sd,
+            },
+            "contentDetails": { "duration": "PT1M" },
+        }]});
+        let thumbnail = |quality| {
+            parse_video_items(&json, None, quality).unwrap()[0]
+                .1
+                .as_ref()
+                .unwrap()
+                .thumbnail_url
+                .clone()
+        };
+        assert_eq!(thumbnail(None), None);
+        assert_eq!(
+            thumbnail(Some(ThumbnailQuality::Maxres)),
+            Some(url("hqdefault"))
+        );
+    }
+
+    #[test]
+    fn title_filter_test() {
+        let terms = |s: &str| match s {
+            "" => Vec::new(),
+            s => s.split(',').map(String::from).collect(),
+        };
+        let start =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap());
+        let tests = [
+            ("FIRST,third", "", None, vec!["a", "d"]),
+            ("", "too,late", None, vec!["e"]),
+            ("t", "o", None, vec!["b", "e"]),
+            ("too", "", Some(start), vec!["e"]),
+            ("none", "", None, vec![]),
+        ];
+        for (any, all, start_date, ids) in tests {
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(fixture()),
+                start_date,
+                title_filter: Some(TitleFilter {
+                    any: terms(any),
+                    all: terms(all),
+                }),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+            let mut found: Vec<&str> = summary.videos.iter().map(|v| v.id.as_str()).collect();
+            found.sort();
+            assert_eq!(found, ids, "any={:?} all={:?}", any, all);
+        }
+    }
+
+    #[test]
+    fn raw_title_test() {
+        use crate::output::{ExtraOutput, Format};
+
+        let dir =
+            std::env::temp_dir().join(format!("yt_api_videosum_raw_title_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut client = fixture();
+        let (_, response) = client
+            .responses
+            .iter_mut()
+            .find(|(k, _)| k.contains("&id=a&"))
+            .unwrap();
+        response["items"][0]["snippet"]["title"] = serde_json::json!("First \u{202E}4pm.exe");
+
+        let config = Config {
+            output: Some(File::create(dir.join("report.csv")).unwrap()),
+            extra_outputs: vec![ExtraOutput {
+                path: dir.join("report.json").display().to_string(),
+                format: Format::Json,
+                file: File::create(dir.join("report.json")).unwrap(),
+            }],
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+        assert_eq!(summary.videos[0].title, "First 4pm.exe");
+        assert_eq!(summary.videos[0].raw_title, "First \u{202E}4pm.exe");
+
+        let csv = std::fs::read_to_string(dir.join("report.csv")).unwrap();
+        assert!(csv.contains(",First 4pm.exe,a,"));
+        assert!(!csv.contains('\u{202E}'));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap())
+                .unwrap();
+        assert_eq!(json["videos"][0]["title"], "First 4pm.exe");
+        assert_eq!(json["videos"][0]["title_raw"], "First \u{202E}4pm.exe");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
     #[test]
-    fn dissect_test() {
-        let sec = TimeBase::_Seconds;
-        let min = TimeBase::Minutes;
-        let hrs = TimeBase::Hours;
-        let days = TimeBase::Days;
+    fn cancel_test() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        //Set as the given video is requested, as if Ctrl-C was pressed meanwhile
+        struct Interrupting {
+            inner: Fixture,
+            at: &'static str,
+            flag: &'static AtomicBool,
+        }
+        impl HttpClient for Interrupting {
+            fn get_json(
+                &self,
+                address: &str,
+                key: &ApiKey,
+            ) -> Result<serde_json::Value, Box<dyn Error>> {
+                if address.contains(self.at) {
+                    self.flag.store(true, Ordering::SeqCst);
+                }
+                self.inner.get_json(address, key)
+            }
+        }
+
+        static FLAGS: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+        let dir =
+            std::env::temp_dir().join(format!("yt-api-videosum-cancel-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.csv");
+        let path = path.to_str().unwrap();
+        let pending = crate::output::Pending::new(path);
+        let client = fixture();
+        let log = client.log.clone();
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(Interrupting {
+                inner: client,
+                at: "videos?part=snippet%2CcontentDetails&id=c",
+                flag: &FLAGS[0],
+            }),
+            output: Some(File::create(&pending.partial).unwrap()),
+            pending: Some(pending.clone()),
+            cancel: Some(&FLAGS[0]),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+
+        assert!(summary.interrupted);
+        assert_eq!(summary.warnings, 1);
+        let ids: Vec<&str> = summary.videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(
+            log.borrow()
+                .iter()
+                .filter(|a| a.contains("/videos?"))
+                .count(),
+            3
+        );
+        //Kept under the temporary name, with the rows so far in the selected format
+        assert!(!std::path::Path::new(path).exists());
+        let written = std::fs::read_to_string(&pending.partial).unwrap();
+        assert_eq!(read_csv(written.as_bytes()).unwrap().len(), 3);
+        assert!(written
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("#video_count=3,"));
+        assert_eq!(
+            summary.output.map(|o| o.destination),
+            Some(crate::output::Destination::Path(pending.partial.clone()))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let client = fixture();
+        let log = client.log.clone();
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(Interrupting {
+                inner: client,
+                at: "playlistId=UULFxyz&maxResults=50&pageToken=",
+                flag: &FLAGS[1],
+            }),
+            cancel: Some(&FLAGS[1]),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        assert!(run_collect(config).is_err());
+        assert!(!log.borrow().iter().any(|a| a.contains("/videos?")));
+    }
+
+    #[test]
+    fn user_agent_test() {
+        assert_eq!(
+            UreqClient::default().user_agent,
+            format!("yt-api-videosum/{}", env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[test]
+    fn resume_test() {
+        let resumed = |id: &str, duration: &str| {
+            Video::new(
+                DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+                    .unwrap()
+                    .into(),
+                format!("Resumed {}", id),
+                String::from(id),
+                String::from(duration),
+            )
+            .unwrap()
+        };
+        let client = fixture();
+        let log = client.log.clone();
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            //'z' is no longer on the channel
+            resume: vec![
+                resumed("a", "PT2M"),
+                resumed("c", "PT3M"),
+                resumed("z", "PT4M"),
+            ],
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
+
+        let fetched: Vec<String> = log
+            .borrow()
+            .iter()
+            .filter_map(|a| a.split("/videos?part=snippet%2CcontentDetails&id=").nth(1))
+            .map(|id| String::from(id.trim_end_matches("&key=KEY")))
+            .collect();
+        assert_eq!(fetched, ["b", "d", "e"]);
+        let durations: Vec<(&str, &str)> = summary
+            .videos
+            .iter()
+            .map(|v| (v.id.as_str(), v.duration.as_str()))
+            .collect();
+        assert_eq!(
+            durations,
+            [
+                ("a", "PT2M"),
+                ("b", "PT1H"),
+                ("c", "PT3M"),
+                ("d", "PT30S"),
+                ("e", "PT1H")
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_test() {
+        let config = |client| Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        assert!(run(config(fixture())).is_ok());
+
+        let mut client = fixture();
+        let (_, json) = client
+            .responses
+            .iter_mut()
+            .find(|(p, _)| p.contains("id=a&"))
+            .unwrap();
+        json["items"][0]["contentDetails"]["duration"] = serde_json::json!("bogus");
+        let e = run(config(client)).unwrap_err();
+        assert!(e.is::<Partial>());
+        assert_eq!(e.to_string(), "Completed with 1 warning");
+
+        let mut summary = run_collect(config(fixture())).unwrap();
+        summary
+            .extra_outputs
+            .push((String::from("x.json"), Err(String::from("x"))));
+        summary.warnings = 2;
+        assert_eq!(summary.warning_count(), 3);
+        assert_eq!(
+            check_warnings(&summary).unwrap_err().to_string(),
+            "Completed with 3 warnings"
+        );
+    }
+
+    #[test]
+    fn verify_output_test() {
+        let path =
+            std::env::temp_dir().join(format!("yt_api_videosum_verify_{}", std::process::id()));
+        let date =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-01-02T10:00:00Z").unwrap());
+        let videos = vec![
+            Video::new(
+                date,
+                "A, \"quoted\"\none".into(),
+                "id0".into(),
+                "PT1M".into(),
+            )
+            .unwrap(),
+            Video::new(date, "Plain".into(), "id1".into(), "PT1H2M3S".into()).unwrap(),
+        ];
+        let open = || {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)
+                .unwrap()
+        };
+
+        for compress in [false, true] {
+            let mut sink = crate::output::Sink::new(open(), compress).unwrap();
+            crate::output::write_videos(
+                sink.rewrite().unwrap(),
+                &videos,
+                &Default::default(),
+                None,
+            )
+            .unwrap()
+            .close()
+            .unwrap();
+            assert_eq!(
+                verify_output(&mut sink, &videos),
+                Ok(()),
+                "compress={}",
+                compress
+            );
+            assert!(verify_output(&mut sink, &videos[..1])
+                .unwrap_err()
+                .contains(
+                    "has 2 rows totaling 3783 seconds, instead of 1 videos totaling 60 seconds"
+                ));
+        }
+
+        let mut sink = crate::output::Sink::new(open(), false).unwrap();
+        let mut stream = sink.rewrite().unwrap();
+        stream
+            .write_all(b"2024-01-02T10:00:00Z,A, \"quoted\",id0,PT1M,60\n")
+            .unwrap();
+        stream.close().unwrap();
+        assert!(verify_output(&mut sink, &videos)
+            .unwrap_err()
+            .starts_with("Could not parse the output"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_output_test() {
+        use std::io::Read;
+
+        for sort in [None, Some(SortKey::Title)] {
+            let (mut reader, writer) = std::io::pipe().unwrap();
+            let config = Config {
+                output: Some(File::from(std::os::fd::OwnedFd::from(writer))),
+                footer: false,
+                sort,
+                status: Status::Stderr,
+                client: Box::new(fixture()),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            let summary = run_collect(config).unwrap();
+
+            let mut content = String::new();
+            reader.read_to_string(&mut content).unwrap();
+            //Only the final output, without the intermediate responses
+            assert!(content.starts_with(crate::csv::HEADER), "{}", content);
+            assert_eq!(
+                read_csv(content.as_bytes()).unwrap().len(),
+                summary.videos.len()
+            );
+        }
+    }
+
+    #[test]
+    fn retry_test() {
+        //Fails the given number of times on each address before passing it on
+        struct Flaky {
+            inner: Fixture,
+            failures: u32,
+            seen: std::cell::RefCell<Vec<(String, u32)>>,
+        }
+        impl HttpClient for Flaky {
+            fn get_json(
+                &self,
+                address: &str,
+                key: &ApiKey,
+            ) -> Result<serde_json::Value, Box<dyn Error>> {
+                let mut seen = self.seen.borrow_mut();
+                let i = match seen.iter().position(|(a, _)| a == address) {
+                    Some(i) => i,
+                    None => {
+                        seen.push((String::from(address), 0));
+                        seen.len() - 1
+                    }
+                };
+                seen[i].1 += 1;
+                match seen[i].1 <= self.failures {
+                    true => Err(TransientError(format!(
+                        "HTTP transfer failure: {}&key={}",
+                        address,
+                        key.expose()
+                    )))?,
+                    false => self.inner.get_json(address, key),
+                }
+            }
+        }
 
+        //8 requests: channel, 2 playlist pages and 5 videos
         let tests = [
-            (0, sec, "0 seconds"),
-            (1, sec, "1 second"),
-            (59, sec, "59 seconds"),
-            (60, sec, "60 seconds"),
-            (61, sec, "61 seconds"),
-            (119, sec, "119 seconds"),
-            (120, sec, "120 seconds"),
-            (121, sec, "121 seconds"),
-            (599, sec, "599 seconds"),
-            (600, sec, "600 seconds"),
-            (601, sec, "601 seconds"),
-            (659, sec, "659 seconds"),
-            (660, sec, "660 seconds"),
-            (661, sec, "661 seconds"),
-            (3599, sec, "3599 seconds"),
-            (3600, sec, "3600 seconds"),
-            (3601, sec, "3601 seconds"),
-            (3659, sec, "3659 seconds"),
-            (3660, sec, "3660 seconds"),
-            (3661, sec, "3661 seconds"),
-            (4199, sec, "4199 seconds"),
-            (4200, sec, "4200 seconds"),
-            (4201, sec, "4201 seconds"),
-            (7199, sec, "7199 seconds"),
-            (7200, sec, "7200 seconds"),
-            (7201, sec, "7201 seconds"),
-            (7259, sec, "7259 seconds"),
-            (7260, sec, "7260 seconds"),
-            (7261, sec, "7261 seconds"),
-            (86399, sec, "86399 seconds"),
-            (86400, sec, "86400 seconds"),
-            (86401, sec, "86401 seconds"),
-            (86459, sec, "86459 seconds"),
-            (86460, sec, "86460 seconds"),
-            (86461, sec, "86461 seconds"),
-            (89999, sec, "89999 seconds"),
-            (90000, sec, "90000 seconds"),
-            (90001, sec, "90001 seconds"),
-            (90059, sec, "90059 seconds"),
-            (90060, sec, "90060 seconds"),
-            (90061, sec, "90061 seconds"),
-            (604799, sec, "604799 seconds"),
-            (604800, sec, "604800 seconds"),
-            (604801, sec, "604801 seconds"),
+            (0, None, Ok(0)),
+            (1, None, Ok(8)),
+            (3, None, Ok(24)),
+            (4, None, Err("")),
+            (1, Some(8), Ok(8)),
+            (1, Some(7), Err("(all 7 retries of the run used up)")),
+            (2, Some(0), Err("(all 0 retries of the run used up)")),
+        ];
+        for (failures, total, result) in tests {
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(Flaky {
+                    inner: fixture(),
+                    failures,
+                    seen: std::cell::RefCell::new(Vec::new()),
+                }),
+                retries: Retries {
+                    total,
+                    backoff: std::time::Duration::ZERO,
+                    ..Retries::default()
+                },
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            match (run_collect(config), result) {
+                (Ok(s), Ok(retries)) => assert_eq!(s.retries, retries, "failures={}", failures),
+                (Err(e), Err(msg)) => {
+                    assert!(e.to_string().ends_with(msg), "{}", e);
+                    assert!(!e.to_string().contains("KEY"), "{}", e);
+                }
+                (Ok(_), Err(_)) => panic!("failures={}: unexpected success", failures),
+                (Err(e), Ok(_)) => panic!("failures={}: unexpected error: {}", failures, e),
+            }
+        }
 
-            (0, min, "0 seconds"),
-            (1, min, "1 second"),
-            (59, min, "59 seconds"),
-            (60, min, "1 minute"),
-            (61, min, "1 minute 1 second"),
-            (119, min, "1 minute 59 seconds"),
-            (120, min, "2 minutes"),
-            (121, min, "2 minutes 1 second"),
-            (599, min, "9 minutes 59 seconds"),
-            (600, min, "10 minutes"),
-            (601, min, "10 minutes 1 second"),
-            (659, min, "10 minutes 59 seconds"),
-            (660, min, "11 minutes"),
-            (661, min, "11 minutes 1 second"),
-            (3599, min, "59 minutes 59 seconds"),
-            (3600, min, "60 minutes"),
-            (3601, min, "60 minutes 1 second"),
-            (3659, min, "60 minutes 59 seconds"),
-            (3660, min, "61 minutes"),
-            (3661, min, "61 minutes 1 second"),
-            (4199, min, "69 minutes 59 seconds"),
-            (4200, min, "70 minutes"),
-            (4201, min, "70 minutes 1 second"),
-            (7199, min, "119 minutes 59 seconds"),
-            (7200, min, "120 minutes"),
-            (7201, min, "120 minutes 1 second"),
-            (7259, min, "120 minutes 59 seconds"),
-            (7260, min, "121 minutes"),
-            (7261, min, "121 minutes 1 second"),
-            (86399, min, "1439 minutes 59 seconds"),
-            (86400, min, "1440 minutes"),
-            (86401, min, "1440 minutes 1 second"),
-            (86459, min, "1440 minutes 59 seconds"),
-            (86460, min, "1441 minutes"),
-            (86461, min, "1441 minutes 1 second"),
-            (89999, min, "1499 minutes 59 seconds"),
-            (90000, min, "1500 minutes"),
-            (90001, min, "1500 minutes 1 second"),
-            (90059, min, "1500 minutes 59 seconds"),
-            (90060, min, "1501 minutes"),
-            (90061, min, "1501 minutes 1 second"),
-            (604799, min, "10079 minutes 59 seconds"),
-            (604800, min, "10080 minutes"),
-            (604801, min, "10080 minutes 1 second"),
+        //Other errors are not retried
+        let client = fixture();
+        let log = client.log.clone();
+        let mut client = client;
+        client.responses.retain(|(p, _)| !p.contains("channels?"));
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(client),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        assert!(run_collect(config).is_err());
+        assert_eq!(log.borrow().len(), 1);
+    }
 
-            (0, hrs, "0 seconds"),
-            (1, hrs, "1 second"),
-            (59, hrs, "59 seconds"),
-            (60, hrs, "1 minute"),
-            (61, hrs, "1 minute 1 second"),
-            (119, hrs, "1 minute 59 seconds"),
-            (120, hrs, "2 minutes"),
-            (121, hrs, "2 minutes 1 second"),
-            (599, hrs, "9 minutes 59 seconds"),
-            (600, hrs, "10 minutes"),
-            (601, hrs, "10 minutes 1 second"),
-            (659, hrs, "10 minutes 59 seconds"),
-            (660, hrs, "11 minutes"),
-            (661, hrs, "11 minutes 1 second"),
-            (3599, hrs, "59 minutes 59 seconds"),
-            (3600, hrs, "1 hour"),
-            (3601, hrs, "1 hour 1 second"),
-            (3659, hrs, "1 hour 59 seconds"),
-            (3660, hrs, "1 hour 1 minute"),
-            (3661, hrs, "1 hour 1 minute 1 second"),
-            (4199, hrs, "1 hour 9 minutes 59 seconds"),
-            (4200, hrs, "1 hour 10 minutes"),
-            (4201, hrs, "1 hour 10 minutes 1 second"),
-            (7199, hrs, "1 hour 59 minutes 59 seconds"),
-            (7200, hrs, "2 hours"),
-            (7201, hrs, "2 hours 1 second"),
-            (7259, hrs, "2 hours 59 seconds"),
-            (7260, hrs, "2 hours 1 minute"),
-            (7261, hrs, "2 hours 1 minute 1 second"),
-            (86399, hrs, "23 hours 59 minutes 59 seconds"),
-            (86400, hrs, "24 hours"),
-            (86401, hrs, "24 hours 1 second"),
-            (86459, hrs, "24 hours 59 seconds"),
-            (86460, hrs, "24 hours 1 minute"),
-            (86461, hrs, "24 hours 1 minute 1 second"),
-            (89999, hrs, "24 hours 59 minutes 59 seconds"),
-            (90000, hrs, "25 hours"),
-            (90001, hrs, "25 hours 1 second"),
-            (90059, hrs, "25 hours 59 seconds"),
-            (90060, hrs, "25 hours 1 minute"),
-            (90061, hrs, "25 hours 1 minute 1 second"),
-            (604799, hrs, "167 hours 59 minutes 59 seconds"),
-            (604800, hrs, "168 hours"),
-            (604801, hrs, "168 hours 1 second"),
+    #[test]
+    fn min_videos_test() {
+        let config = Config {
+            status: Status::Stderr,
+            client: Box::new(fixture()),
+            ..Config::new(String::from("KEY"), String::from("somechannel"))
+        };
+        let summary = run_collect(config).unwrap();
 
-            (0, days, "0 seconds"),
-            (1, days, "1 second"),
-            (59, days, "59 seconds"),
-            (60, days, "1 minute"),
-            (61, days, "1 minute 1 second"),
-            (119, days, "1 minute 59 seconds"),
-            (120, days, "2 minutes"),
-            (121, days, "2 minutes 1 second"),
-            (599, days, "9 minutes 59 seconds"),
-            (600, days, "10 minutes"),
-            (601, days, "10 minutes 1 second"),
-            (659, days, "10 minutes 59 seconds"),
-            (660, days, "11 minutes"),
-            (661, days, "11 minutes 1 second"),
-            (3599, days, "59 minutes 59 seconds"),
-            (3600, days, "1 hour"),
-            (3601, days, "1 hour 1 second"),
-            (3659, days, "1 hour 59 seconds"),
-            (3660, days, "1 hour 1 minute"),
-            (3661, days, "1 hour 1 minute 1 second"),
-            (4199, days, "1 hour 9 minutes 59 seconds"),
-            (4200, days, "1 hour 10 minutes"),
-            (4201, days, "1 hour 10 minutes 1 second"),
-            (7199, days, "1 hour 59 minutes 59 seconds"),
-            (7200, days, "2 hours"),
-            (7201, days, "2 hours 1 second"),
-            (7259, days, "2 hours 59 seconds"),
-            (7260, days, "2 hours 1 minute"),
-            (7261, days, "2 hours 1 minute 1 second"),
-            (86399, days, "23 hours 59 minutes 59 seconds"),
-            (86400, days, "1 day"),
-            (86401, days, "1 day 1 second"),
-            (86459, days, "1 day 59 seconds"),
-            (86460, days, "1 day 1 minute"),
-            (86461, days, "1 day 1 minute 1 second"),
-            (89999, days, "1 day 59 minutes 59 seconds"),
-            (90000, days, "1 day 1 hour"),
-            (90001, days, "1 day 1 hour 1 second"),
-            (90059, days, "1 day 1 hour 59 seconds"),
-            (90060, days, "1 day 1 hour 1 minute"),
-            (90061, days, "1 day 1 hour 1 minute 1 second"),
-            (604799, days, "6 days 23 hours 59 minutes 59 seconds"),
-            (604800, days, "7 days"),
-            (604801, days, "7 days 1 second"),
+        let tests = [
+            (None, true),
+            (Some(0), true),
+            (Some(5), true),
+            (Some(6), false),
         ];
+        for (min, r) in tests {
+            assert_eq!(check_min_videos(&summary, min).is_ok(), r, "min={:?}", min);
+        }
+    }
 
-        for (t, b, s) in tests {
-            assert_eq!(dissect_delta(TimeDelta::seconds(t), b), s);
+    #[test]
+    fn pending_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt_api_videosum_pending_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("output.csv");
+        std::fs::write(&path, "previous run\n").unwrap();
+        let pending = crate::output::Pending::new(path.to_str().unwrap());
+
+        for fail in [true, false] {
+            let mut client = fixture();
+            if fail {
+                client.responses.retain(|(p, _)| !p.contains("id=c&"));
+            }
+            let config = Config {
+                status: Status::Stderr,
+                client: Box::new(client),
+                output: Some(File::create(&pending.partial).unwrap()),
+                pending: Some(pending.clone()),
+                ..Config::new(String::from("KEY"), String::from("somechannel"))
+            };
+            assert_eq!(run_collect(config).is_ok(), !fail);
+
+            let content = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(content == "previous run\n", fail);
+            assert_eq!(std::path::Path::new(&pending.partial).exists(), fail);
         }
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .starts_with(crate::csv::HEADER));
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,104 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to write the videos as an extended M3U playlist
+
+    The header is followed by the metadata lines as comments, which players ignore.
+    Each entry consists of an '#EXTINF:<seconds>,<title>' line followed by the watch URL. As the title runs until the
+    end of the line, line breaks are replaced by spaces, and commas by semicolons, because some players split the
+    title at the first comma.
+*/
+
+use std::error::Error;
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+
+use crate::output::{Metadata, RowWriter};
+use crate::Video;
+
+pub const HEADER: &str = "#EXTM3U";
+
+pub fn sanitize_title(title: &str) -> String {
+    title
+        .split(['\r', '\n'])
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(',', ";")
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+}
+impl<W: Write> Writer<W> {
+    pub fn new(mut out: W, metadata: Option<&Metadata>) -> Result<Self, Box<dyn Error>> {
+        writeln!(out, "{}", HEADER)?;
+        for line in metadata.map(Metadata::lines).unwrap_or_default() {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()?;
+        Ok(Self { out })
+    }
+}
+impl<W: Write> RowWriter<W> for Writer<W> {
+    fn write(&mut self, video: &Video) -> Result<(), Box<dyn Error>> {
+        self.out.write_all(
+            format!(
+                "#EXTINF:{},{}\n{}\n",
+                video.delta.num_seconds(),
+                sanitize_title(&video.title),
+                video.url(),
+            )
+            .as_bytes(),
+        )?;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, _footer: Option<DateTime<Utc>>) -> Result<W, Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod m3u_test {
+    use super::*;
+
+    #[test]
+    fn write_test() {
+        let date =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-01-02T10:00:00Z").unwrap());
+        let videos = [
+            Video::new(date, "Plain".into(), "id0".into(), "PT1M".into()).unwrap(),
+            Video::new(date, "Hello, World".into(), "id1".into(), "PT1H2M3S".into()).unwrap(),
+            Video::new(date, "Two\r\nlines\n".into(), "id2".into(), "P1DT4S".into()).unwrap(),
+        ];
+
+        let mut writer: Box<dyn RowWriter<Vec<u8>>> =
+            Box::new(Writer::new(Vec::new(), None).unwrap());
+        for v in &videos {
+            writer.write(v).unwrap();
+        }
+        let out = writer.finish(None).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+#EXTM3U
+#EXTINF:60,Plain
+https://www.youtube.com/watch?v=id0
+#EXTINF:3723,Hello; World
+https://www.youtube.com/watch?v=id1
+#EXTINF:86404,Two lines
+https://www.youtube.com/watch?v=id2
+"
+        );
+    }
+}
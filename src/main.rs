@@ -16,252 +16,4644 @@ const DESC: &str =
 YouTube API tool for calculating the video runtime sum of a channel.
 
 Usage:
-yt_api_videosum [-k api_key] [-s [start_date]] [-e [end_date]] [channel_name]
+yt_api_videosum [run] [-k api_key | -K key_file] [--key-profile name]
+                [--store-key]
+                [-s [start_date]] [-e [end_date]] [-o output]
+                [--force | --append | --no-clobber] [--output-fd fd]
+                [--compress]
+                [--verify-output] [--no-output]
+                [--format format]
+                [--template template] [--footer | --no-footer]
+                [--sort key [--desc]] [--stream]
+                [--summary-only-if-nonempty]
+                [--channels-compare channels | --channels-file path]
+                [--merge-output path]
+                [--porcelain | --raw-seconds] [--total-fd fd]
+                [--lang lang] [--date-format format]
+                [--timezone timezone | --local-time]
+                [--strip-emoji] [--ascii-titles]
+                [--count-only] [--with-stats] [--channel-stats]
+                [--print-playlist-id] [--check-key]
+                [--with-thumbnail [quality]]
+                [--group-by-duration-bucket path]
+                [--monthly-csv path [--fill-gaps]]
+                [--cache path [--cache-max-age days]]
+                [--since-last-run] [--reset-state] [--state-file path]
+                [--playlist ids]... [--yes] [--max-quota units]
+                [--max-retries-total count] [--user-agent ua]
+                [--min-videos count]
+                [--by-hour] [--first-match] [--verify-handle]
+                [--exclude-region-blocked region] [--strict-public]
+                [--title-any terms]... [--title-all terms]...
+                [--resume-from path] [--diff-file path] [--skipped-report path]
+                [--captions-report] [--captions-column]
+                [--quality-report] [--definition-column]
+                [--immersive-report] [--immersive-columns] [--rate]
+                [--round unit]
+                [--equivalent | --equivalent-units units]
+                [--interactive | --non-interactive] [-i] [--color when]
+                [--explain | --explain-only] [-v | -vv] [-V] [channel_name]
+yt_api_videosum resolve [options] channel_name
+yt_api_videosum stats [options] csv_file
+yt_api_videosum estimate [options] channel_name
+
+Commands:
+run  Sum the runtime of the videos of the channel, with the options below.
+       This is the default if no command is given, so it is only needed for
+       a channel named like a command, e.g. 'yt_api_videosum run stats' (or
+       'yt_api_videosum @stats').
+resolve  Only display the IDs of the channel and its uploads playlists.
+stats  Display statistics of the videos of an earlier CSV output, without
+         any request.
+estimate  Only display the quota cost and the expected time of a run.
+Run 'yt_api_videosum <command> -h' for the options of each command.
 
 Options:
--k  YT API key supplied in plain text.
-      If empty, the program will look for it in the 'config/key.txt' file.
--s
--e  Filter the videos by publish date, giving a start- and/or end date for
+-k, --key  YT API key supplied in plain text.
+      If empty (e.g. '-k \"\"'), the program will look for it in the keyring
+      of the OS (if built with the 'keyring' feature, see '--store-key'),
+      then in a key file, see '-K'.
+-K, --key-file path  File containing the API key. By default, the first
+      existing one of '$XDG_CONFIG_HOME/yt-api-videosum/key.txt'
+      ('~/.config/yt-api-videosum/key.txt' if the variable is not set) and
+      'config/key.txt' (relative to the working directory) is used.
+      The file contains either the key alone on its first line, or one
+      'name = KEY' line per profile, e.g. to use separate keys for separate
+      quotas. Empty lines and lines starting with '#' are ignored. A warning
+      is displayed if the file is readable by all users, or if the key does
+      not look like an API key.
+--key-profile name  Profile to use from the key file. May be omitted if
+      there is only one, or one called 'default'.
+--store-key  Store the API key in the keyring of the OS (as the given
+               '--key-profile', or 'default'), then exit. The key is taken
+               from '-k', or asked without echoing it. It is then used
+               instead of the key file, unless '-K' is given. If the keyring
+               cannot be reached, the key file is used with a warning. Only
+               available if built with the 'keyring' feature, i.e.
+               'cargo build --features keyring'.
+-s, --start
+-e, --end  Filter the videos by publish date, giving a start- and/or end date for
       the active interval. Date is expected in RFC3339 format,
-      i.e. 'yyyy-mm-ddTHH:MM:SSZ' (note the UTC timezone).
-      If the timestamp is empty, it will be asked interactively.
--h  Display this help and exit.
+      i.e. 'yyyy-mm-ddTHH:MM:SSZ' or with an offset like '+01:00', as a
+      local time without an offset, i.e. 'yyyy-mm-ddTHH:MM[:SS]' (or with a
+      space instead of 'T'), or as Unix epoch seconds prefixed with '@',
+      e.g. '@1704067200'.
+      A calendar date ('2024-01-31'), month ('2024-01') or year ('2024')
+      is also accepted, as well as 'today' and 'yesterday', meaning their
+      start (00:00:00) for '-s', and their end (23:59:59) for '-e', so e.g.
+      '-s 2024-01 -e 2024-03' includes all of March.
+      Local times and dates are in UTC, unless given by '--timezone' or
+      '--local-time'. Around daylight saving time transitions, a local time
+      occurring twice is taken as the earlier one, and a skipped one is
+      moved forward by the length of the gap (e.g. 02:30 to 03:30), both
+      with a warning.
+      Both bounds are inclusive, and compared to the publish dates as
+      instants, i.e. in UTC whatever offset or timezone they were given in:
+      a video published exactly at the start or the end is included, and so
+      is one within the last second of the end, e.g. at 23:59:59.5 for
+      '-e 2024-03-31'. To include the whole day in another timezone, give
+      the date alone with '--timezone', e.g. '-e 2024-03-31 --timezone
+      Europe/Berlin', rather than an offset timestamp in the evening.
+      Relative dates count back from the current time, in days, weeks,
+      months or years, e.g. '30d', '2w', '6m' or '1y'.
+      If the timestamp is empty, it will be asked interactively (an error
+      in non-interactive mode), where an empty answer or 'none' means no
+      limit.
+      A start date after the end date is an error (asked again if it was
+      given interactively), while equal ones are allowed with a note.
+-o, --output  Path of the output file, 'output.txt' by default. May contain the
+      placeholders '{channel}', '{date}' (run date as 'yyyymmdd'), '{start}'
+      and '{end}' (filter dates as 'yyyymmdd', or 'all' if not given),
+      e.g. 'reports/{channel}-{date}.csv'.
+      An existing non-empty output file is not overwritten: the program
+      aborts, or asks for confirmation in interactive mode.
+      May be given more than once to write additional outputs at the end
+      from the same results. Their format is inferred from the extension
+      ('.csv', '.m3u', '.m3u8', '.json', '.jsonl', '.md', optionally
+      followed by '.gz'), or given as a prefix, e.g. 'json:dashboard.txt'.
+      A failure with one of them is reported without affecting the others.
+      If a response of the API cannot be read, it is kept with the request
+      in a file named after the output with '.error.json' appended, e.g.
+      'output.txt.error.json', for debugging.
+--output-fd fd  Write the output to the given open file descriptor instead
+                 of a file (Unix only), e.g. '--output-fd 3 3>&1 >/dev/null'
+                 to pipe the data while the messages stay on the terminal.
+                 Cannot be used with '-o'. An output that is not seekable
+                 (e.g. a pipe) is only written once at the end.
+--force  Overwrite the output file if it already exists.
+--append  Append to the output file if it already exists.
+--no-clobber  Never overwrite an existing output file (even an empty one, or
+                when asked in interactive mode), fail instead. This applies
+                to the additional outputs too. Cannot be used with
+                '--force', which is then needed to overwrite it.
+--compress  Compress the output file with gzip. This is implied if the
+              output path ends with '.gz'. Cannot be used with '--append'.
+--verify-output  Once the (CSV) output file is written, read it back and
+                   display a warning if its rows do not add up to the same
+                   video count and total, as a check of the output itself.
+--no-output  Do not write any output file, only display the summary. No file
+               is created or truncated, except by '--group-by-duration-bucket'.
+               Cannot be used with '-o', '--output-fd' or '--verify-output'.
+--format  Format of the (first) output file, either 'csv' (default), 'm3u'
+            for an extended M3U playlist of the video URLs, e.g. for mpv or
+            VLC, 'json', 'md' for a Markdown table, or 'template' for a
+            custom line format given by '--template'. If not given, it is
+            inferred from the extension of '-o' ('.csv', '.m3u', '.m3u8',
+            '.json', '.jsonl', '.md', optionally followed by '.gz'), with a
+            warning and CSV for any other.
+--template  Line written for each video with the 'template' format, where
+              the placeholders {date}, {title}, {id}, {url}, {duration},
+              {duration_seconds}, {duration_hms} and {views} are replaced,
+              and '{{' and '}}' are literal braces, e.g.
+              '{date} | {duration_hms} | {title}'.
+--footer  Add the '#'-prefixed summary line (video count, total and run
+            timestamp) to the end of the output. Default for CSV, ignored
+            for M3U.
+--no-footer  Omit the summary line from the end of the CSV output.
+--sort  Sort the output rows by 'date', 'duration' or 'title' (the latter
+          case-insensitive) instead of the playlist order. Videos with equal
+          keys keep their playlist order.
+--desc  Sort in descending order instead of ascending.
+--stream  Only write the videos to the output as they are fetched, without
+            keeping them for the end of the run, so even channels with tens
+            of thousands of videos take little memory. The summary is
+            displayed as usual, but the options needing all the videos at
+            the end (e.g. '--sort', '--by-hour' or additional '-o' outputs)
+            cannot be used with it, and neither can the 'json' format,
+            which is built in memory.
+--summary-only-if-nonempty  Do not display the summary (success message and
+                              sum total) if no videos matched the filters.
+--channels-compare  Instead of a single channel, process each channel in the
+                      given comma-separated list, and display a table
+                      comparing the video count, total and average runtime,
+                      and date span of them. The filters and limits of a
+                      run apply to each channel, but no output file is
+                      written, and the other reports are not displayed.
+--channels-file path  Same as '--channels-compare', with the channels read
+                        from the given file ('-' for stdin), one per line.
+                        Empty lines and anything after a '#' are ignored.
+                        Invalid lines are reported with their line number
+                        and skipped. Nothing is asked interactively when
+                        reading the list from stdin.
+--merge-output path  With '--channels-compare' or '--channels-file', also
+                       write the videos of all channels to a single CSV
+                       file, with an additional leading 'channel' column
+                       giving the handle of the channel of each video.
+--porcelain  Display a single machine-readable line on stdout at the end
+               (one per channel with '--channels-compare'), while all other
+               messages go to stderr. The format is stable across versions:
+               'channel=@name videos=N total_seconds=N start=DATE end=DATE
+               status=STATUS', where dates are 'yyyy-mm-dd' if at midnight
+               (UTC), RFC3339 otherwise, or '-' if not given, and status is
+               'ok', 'empty' (no videos matched), 'partial' (completed with
+               warnings), 'interrupted' (stopped by Ctrl-C) or 'error'
+               (videos and total_seconds are '-' then). Values are quoted
+               for the shell when needed. An error before querying the
+               channel (e.g. an invalid option or a missing key file) gives
+               no line, only the exit status and a message on stderr.
+--raw-seconds  Display only the total runtime in seconds on stdout at the end
+                 (nothing if the run failed), while all other messages go to
+                 stderr, even the usage errors, e.g.
+                 'total=$(yt_api_videosum --raw-seconds ...)'.
+--total-fd fd  With '--raw-seconds', write the total to the given open file
+                 descriptor instead of stdout (Unix only), e.g. to keep stdout
+                 for the output with '--output-fd 1'.
+--date-format format  Add a leading 'date' column to the CSV output, with
+                       the publish date in the given strftime format, e.g.
+                       '%d.%m.%Y'. The 'publishedAt' column is kept as RFC3339
+                       UTC, and the '-s'/'-e' filters still apply to it.
+--timezone timezone  Render the 'date' column in the given IANA timezone,
+                     e.g. 'Europe/Budapest', instead of UTC. Implies the
+                     'date' column (in RFC3339 if no format is given).
+                     Local times and dates of '-s'/'-e' are in this
+                     timezone as well.
+--local-time  Take the local times and dates of '-s'/'-e' in the timezone
+                of the system instead of UTC.
+--cache  Keep the resolved channel IDs in the given file, saving the
+           channel query (and its quota cost) on later runs.
+--cache-max-age  Days after which a cached channel is resolved again, in
+                   case the handle was moved to another channel, 30 by
+                   default.
+--since-last-run  Only sum the videos published after the newest one seen
+                    by the last successful run for the channel, e.g. for a
+                    periodic job. The newest date is recorded at the end of
+                    each run with this option that completed without
+                    warnings. If there is no previous run (or the state file
+                    cannot be read), all videos are fetched, with a notice.
+                    Cannot be combined with '-s'.
+--reset-state  Forget the last run of the channel before running, so
+                 '--since-last-run' fetches all videos again.
+--state-file path  File keeping the last runs, by default
+                     '$XDG_CONFIG_HOME/yt-api-videosum/state.json'
+                     ('~/.config/yt-api-videosum/state.json' if the variable
+                     is not set).
+--playlist ids  Sum the videos of the given playlists (comma-separated or
+                 by repeating the option) instead of the public uploads of
+                 the channel, e.g. a series. Videos in more than one of them
+                 are counted once, and the video count of each playlist is
+                 displayed.
+--yes  Do not ask for confirmation before fetching the details of more
+         than 500 videos, which is otherwise asked in interactive mode once
+         the playlist is read, showing the quota cost and the expected
+         time.
+--max-quota units  Abort before fetching the details of the videos if the
+                    run would cost more quota units in total (1 for the
+                    channel, 1 per playlist page of 50 videos, 1 per
+                    video). This applies with '--yes' and in
+                    non-interactive mode, too.
+--max-retries-total count  Requests failing due to a network error or a
+                             temporary server error (HTTP 429 or 5xx) are
+                             retried up to 3 times each, after 1, 2 and 4
+                             seconds. This limits the retries of the whole
+                             run (over all channels), so a persistently
+                             flaky network cannot prolong it indefinitely.
+                             The number of retries is displayed with the
+                             summary.
+--user-agent ua  The 'User-Agent' header of the requests, 'yt-api-videosum/'
+                   followed by the version by default, e.g. for a proxy
+                   only letting known clients through.
+--min-videos count  Fail (with a nonzero exit code) if fewer videos matched
+                     the filters, e.g. to catch a sudden drop when
+                     monitoring a channel. The outputs are still written.
+--by-hour  Also display the video count and total runtime by the hour of
+             publishing (00-23), in the timezone given by '--timezone' (UTC
+             by default), e.g. to see when a channel usually uploads.
+--captions-report  Also display how many of the videos have captions, and
+                     what share of the runtime they make up.
+--captions-column  Add a 'has_captions' column ('true' or 'false') to the CSV
+                     output.
+--quality-report  Also display how many of the videos are in HD and in SD,
+                    and what share of the runtime they make up, e.g. to see
+                    how much of the catalog of an older channel is SD.
+--definition-column  Add a 'definition' column ('hd' or 'sd') to the CSV
+                       output, after 'has_captions' if both are given.
+--rate  Also display the average number of videos and hours of content per
+          week, over the span from the first to the last video published
+          (taken as a week if shorter). At least two videos are needed.
+--round unit  Round the durations computed for the reports to the nearest
+                'second', 'minute' or 'hour', e.g. the content per week of
+                '--rate' (then displayed as a duration), or the average of
+                '--channels-compare'. The total and the videos are always
+                precise.
+--immersive-report  Also display how many of the videos are 360° and 3D, and
+                      what share of the runtime they make up, e.g. for a
+                      catalog of VR content.
+--immersive-columns  Add 'dimension' ('2d' or '3d') and 'projection'
+                       ('rectangular' or '360') columns to the CSV output,
+                       after the other optional columns.
+--equivalent  Also display the total runtime in relatable units, i.e. as
+                8-hour work days, 40-hour work weeks and semester courses at
+                3 hours per week (of 15 weeks).
+--equivalent-units units  Same as '--equivalent', but with the given units
+                            as comma-separated 'name=hours' pairs, e.g.
+                            'lectures=1.5,movies=2'.
+--first-match  If more than one channel is found for the name, use the first
+                 one (with a warning) instead of asking which one to use,
+                 or stopping with the list of them in non-interactive
+                 mode.
+--verify-handle  Stop with an error if the handle of the channel found is not
+                   the one given (ignoring case), e.g. as a handle given up by
+                   a channel can be taken by another one. The channel info is
+                   always queried then, instead of taken from the cache.
+--exclude-region-blocked region  Leave out the videos not viewable in the
+                                   given region (two-letter country code,
+                                   e.g. 'DE') as per their region
+                                   restriction, for the runtime actually
+                                   watchable there. The number of excluded
+                                   videos is displayed.
+--strict-public  Leave out the videos whose status is not 'public' (e.g. an
+                   unlisted or private one still in the playlist), for a
+                   total of the public videos only. The number of excluded
+                   videos is displayed.
+--title-any terms  Only keep the videos whose title contains any of the given
+                    comma-separated terms (or given by repeating the option),
+                    e.g. 'Ep,Episode,Part' for a series with inconsistent
+                    naming. Letter case is ignored, and the terms may match
+                    anywhere in the title, even within a word (so 'Ep' also
+                    matches 'Episode' and 'Deep').
+--title-all terms  Same as '--title-any', but only keep the videos whose title
+                     contains all of the terms. Given both, a video is kept if
+                     it matches both, i.e. any of the '--title-any' terms and
+                     all of the '--title-all' terms. The date filters apply as
+                     well, and the number of excluded videos is displayed.
+                     Cannot be used with '--count-only', which does not fetch
+                     the titles.
+--resume-from path  Reuse the videos of an earlier CSV output (e.g. the
+                      partial file of an interrupted run, or '-' for stdin)
+                      instead of fetching them again. Only the videos of the
+                      channel missing from it are fetched, and the ones no
+                      longer on the channel are left out.
+--diff-file path  Compare the videos with the ones of an earlier CSV output
+                    (e.g. of yesterday's run, or '-' for stdin), displaying
+                    the videos new and removed since by ID, and the change of
+                    the total runtime. Cannot be used with '--count-only'.
+--skipped-report path  Also write the videos listed but left out of the total,
+                         by ID and reason ('missing', 'unparseable-date',
+                         'unparseable-duration', 'region-blocked',
+                         'not-public' or 'title-filter') with the message,
+                         as JSON if the path ends with '.json' or as CSV
+                         otherwise, e.g. to check why a total is lower than
+                         expected. Same placeholders and overwrite rules
+                         apply as for '-o'. The count by reason is displayed.
+--count-only  Only display the video count and total duration, without
+                fetching the titles or writing an output file.
+--with-stats  Also fetch the view count of the videos, and display the
+                total in the summary.
+--with-thumbnail [quality]  Also write the URL of the thumbnail of the videos
+                              in a trailing 'thumbnail_url' column (or field
+                              for JSON), e.g. for a visual index. The quality
+                              is one of 'default', 'medium', 'high' (default)
+                              or 'maxres', falling back to the next lower one
+                              a video has (only HD uploads have 'maxres').
+                              Cannot be used with '--count-only'.
+--channel-stats  Only display the title, subscriber count, video count and
+                   view count of the channel, then exit. This is a single
+                   request (costing one quota unit), without any per-video
+                   work or output file. With '--porcelain', a single
+                   'key=value' line is printed on stdout instead.
+--print-playlist-id  Only resolve the channel, and print the ID of the
+                       playlist of its public uploads (as summed by
+                       default) on stdout, then exit, e.g. to give it to
+                       '--playlist' later. This is a single request
+                       (costing one quota unit), or none if the channel
+                       is found in the '--cache' file.
+--check-key  Only check that the API key can be used, then exit, with an
+               error telling whether it is not valid, out of quota or
+               restricted otherwise. This is a single request for a known
+               video (costing one quota unit), without any channel.
+--group-by-duration-bucket path  Also write the video count and total
+                                   duration per duration bucket (<1m, 1-5m,
+                                   5-10m, 10-20m, 20-30m, 30-60m, 1-2h, >2h)
+                                   as CSV to the given path, e.g. for
+                                   charts. Same placeholders and overwrite
+                                   rules apply as for '-o'.
+--monthly-csv path  Also write the video count and total duration per month
+                      of publishing (in the timezone of '--timezone', UTC by
+                      default) as CSV to the given path, in chronological
+                      order, e.g. for plotting. Same placeholders and
+                      overwrite rules apply as for '-o'.
+--fill-gaps  Include the months without videos between the first and the last
+               one in the '--monthly-csv' output, so the series is
+               continuous.
+--strip-emoji  Remove emoji from the titles.
+--ascii-titles  Transliterate the titles to ASCII, e.g. 'Café' to 'Cafe',
+                  while removing characters without a transliteration.
+--lang lang  Language of the human-readable total in the summary, one of
+               'en' (default), 'de', 'es', 'fr' or 'hu'. Other messages and
+               the output file are not affected.
+--explain  Display the resolved settings (with the API key redacted), and
+             where each came from (command line, file, prompt or default),
+             before running.
+--explain-only  Same as '--explain', but exit afterwards without running.
+-v, --verbose  Display each request (with the API key redacted), its result
+                 and timing, the page tokens and the running video counts on
+                 stderr. Given twice (or as '-vv'), also the response sizes
+                 and the parse timing.
+--non-interactive  Never ask for anything, fail instead, naming the missing
+                     input and the option to give it with, e.g. for CI jobs.
+                     This is the default when stdin is not a terminal.
+--interactive  Ask for the missing inputs and confirmations even if stdin is
+                 not a terminal, e.g. to answer them from a pipe.
+-i, --wizard  Ask for the channel (confirming the title found), the date
+                range, the output file and format, and the optional filters
+                one by one, with the default in brackets, then display the
+                equivalent command line (without the '-k' key) and run it,
+                e.g. to script it next time. Other options may be given
+                along, except the ones asked for. An invalid answer is asked
+                again, and Ctrl-D aborts. Resolving the channel for the
+                confirmation costs one more quota unit.
+--color when  Color the messages: warnings in yellow, errors in red, the
+                total in bold green, and the notes dimmed. One of 'auto'
+                (default, only on a terminal, and unless the 'NO_COLOR'
+                environment variable is set), 'always' or 'never'. The
+                porcelain line and the output files are never colored.
+-V, --version  Display the version and exit.
+-h, --help  Display this help and exit.
 
 Parameters:
 channel_name  Human-readable name of the channel, with or without the
-                '@' prefix. If omitted, it will be asked interactively.
+                '@' prefix. If omitted, it will be asked interactively (an
+                error in non-interactive mode). The end of the input (e.g.
+                Ctrl-D) while asked aborts the program.
+                May be given before, after or between the options, or after
+                '--' if it starts with '-', e.g. '-- -minusbrand'.
+
+Options with a value also accept the '--option=value' form. The value is
+taken from the next argument whatever it starts with, e.g. '-k -abc', except
+for the values that may be left out ('-s', '-e' and '--with-thumbnail'),
+which need the '=' form for a value starting with '-', e.g. '--end=-100'.
+Arguments after '--' are never taken as options.
+Each option may be given only once, except '-o', '-v', '--playlist',
+'--title-any' and '--title-all', which add up. The alternatives separated by
+'|' in the usage cannot be used together.
+
+Environment:
+RUST_LOG  If set, the progress and warning messages become log records on
+            stderr, at the 'info' and 'warn' levels, and the details of
+            '--verbose' are logged at the 'debug' and 'trace' levels
+            (regardless of '-v'), e.g. 'RUST_LOG=debug'. The prompts and the
+            total are still displayed as usual.
+NO_COLOR  If set to a non-empty value, no colors are used unless given
+            '--color always'.
+
+Exit status:
+0  Success.
+1  Runtime or API error, e.g. an invalid API key or an unknown channel.
+2  Usage error, e.g. an unknown option, an invalid value or a missing input
+     in non-interactive mode.
+3  Completed with warnings, e.g. skipped videos, an additional output that
+     could not be written, or channels skipped in a comparison, or
+     interrupted by Ctrl-C (see Output).
+130  Interrupted by a second Ctrl-C, exiting immediately.
 
 Output:
 Aggregated total of video duration is displayed interactively.
-Also a full list of the videos are saved to 'output.txt' in CSV format, or in
-case the process could not complete, it will contain the last intermediate
-JSON response to help figuring out what went wrong.
+Also a full list of the videos are saved to the output file in CSV format.
+The file is written under a temporary name (e.g. 'output.txt.partial'), and
+moved in place only once complete, so an existing file is never replaced by
+a half-written one (except with '--append', which writes in place).
+The rows are written as soon as each video is fetched (or at the end when
+sorting), so in case the process could not complete, the temporary file is
+kept with the videos completed so far, or removed if it failed before that.
+The output only ever holds the videos in the selected format, e.g. a CSV
+header alone when no video matched, never the responses of the API.
+On Ctrl-C, no more videos are requested, and the ones fetched so far are
+written in the selected format (under the temporary name, so an earlier
+output is kept), with the total marked as partial. Pressing it again exits
+immediately.
+The CSV and M3U files start with '#'-prefixed lines recording the parameters
+of the run (tool version, channel, date filters and video counts, but never
+the API key), which the JSON and Markdown files embed as well.
+Control characters, bidirectional formatting and zero-width characters are
+removed from the titles in the output, while the JSON responses keep the
+original ones.
 
 Created by Zoltan Kovari, 2024.
 ";
 
+const RESOLVE_DESC: &str = "Description:
+Resolve a channel to its ID and the IDs of its uploads playlists, costing a
+single quota unit.
+
+Usage:
+yt_api_videosum resolve [-k api_key | -K key_file] [--key-profile name]
+                        [--first-match] [--user-agent ua] [--color when]
+                        [-v | -vv] channel_name
+
+Options:
+-k, -K, --key-profile, --first-match, --user-agent, --color, -v  Same as for
+      a run, see 'yt_api_videosum -h'.
+
+Output:
+The title and the ID of the channel, the ID of the playlist of all its
+uploads, and of its public uploads (as summed by default, see '--playlist'),
+one per line on stdout.
+";
+
+const STATS_DESC: &str = "Description:
+Display statistics of the videos of an earlier CSV output, without any API
+request (so no key is needed).
+
+Usage:
+yt_api_videosum stats [--lang lang] [--color when] csv_file
+
+Options:
+--lang, --color  Same as for a run, see 'yt_api_videosum -h'.
+
+Parameters:
+csv_file  CSV output of an earlier run ('-' for stdin), compressed if its
+            name ends with '.gz'.
+
+Output:
+The video count, the sum total, the average, the longest and the shortest
+video, and the range of the publish dates.
+";
+
+const ESTIMATE_DESC: &str = "Description:
+Estimate the quota cost and the time of a run, by reading the playlist of the
+channel as a run would, but stopping before fetching the details of any
+video. The quota spent on the estimate itself is displayed as well.
+
+Usage:
+yt_api_videosum estimate [-k api_key | -K key_file] [--key-profile name]
+                         [-s start_date] [-e end_date] [--playlist ids]...
+                         [--first-match] [--user-agent ua] [--color when]
+                         [-v | -vv] channel_name
+
+Options:
+-k, -K, --key-profile, --playlist, --first-match, --user-agent, --color, -v
+      Same as for a run, see 'yt_api_videosum -h'.
+-s, --start
+-e, --end  Same as for a run, but local times and dates are always in UTC,
+             and the dates are never asked interactively.
+";
+
 use std::fs::File;
-use std::io::BufRead;
+use std::io::{BufRead, IsTerminal, Seek};
+use std::path::PathBuf;
 
 use chrono::prelude::*;
+use yt_api_videosum::dates::Bound;
+use yt_api_videosum::Status;
 
+#[derive(Debug, PartialEq)]
 enum OptionalDate {
     Some(String),
     Ask,
     Date(DateTime<Utc>),
     None,
 }
+impl OptionalDate {
+    fn date(&self) -> Option<DateTime<Utc>> {
+        match self {
+            OptionalDate::Date(d) => Some(*d),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputMode {
+    Protect,
+    Force,
+    Append,
+    //Never overwritten, even if empty or when interactive
+    NoClobber,
+}
+
+macro_rules! statusln {
+    ($status:expr) => {
+        $status.print(format_args!("\n"))
+    };
+    ($status:expr, $($arg:tt)*) => {
+        $status.print(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
 
 const HELP: &str = "Run with '-h' option to display help.";
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+//Exit codes, as documented in the help
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_PARTIAL: i32 = 3;
 
-    /* Start loading command line arguments */
+//Set by the first Ctrl-C, the run then finishes with the videos fetched so far
+static INTERRUPTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+//As a shell would report the process killed by SIGINT
+const EXIT_INTERRUPTED: i32 = 130;
 
+fn main() {
     let args: Vec<String> = std::env::args().skip(1).collect();
-    if args.iter().any(|e| e == "-h" || e == "--help") {
-        println!("{}", DESC);
-        return Ok(());
+    //Without a handler, Ctrl-C simply terminates the process as before
+    let _ = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        Status::Stderr.print(format_args!(
+            "\nNote: Interrupted, finishing with the videos fetched so far (press Ctrl-C again to exit immediately)...\n"
+        ));
+    });
+    let result = dispatch(&args);
+    match result {
+        Ok(()) => (),
+        Err(ref e) if e.is::<Usage>() => {
+            usage_status(&args).print(format_args!("Warning: {}\n{}\n", e, HELP))
+        }
+        //Each warning was displayed already
+        Err(ref e) if e.is::<yt_api_videosum::Partial>() => (),
+        Err(ref e) => Status::Stderr.print(format_args!("Error: {}\n", e)),
     }
+    std::process::exit(exit_code(&result));
+}
 
-    let mut key: Option<String> = None;
-    let mut start_date: OptionalDate = OptionalDate::None;
-    let mut end_date: OptionalDate = OptionalDate::None;
-    let mut channel_name: Option<String> = None;
-
-    let mut i = 0;
-    while i < args.len() {
-        let e = &args[i];
-
-        if e.starts_with('-') {
-            match e.as_str() {
-                "-k" => {
-                    match args.get(i + 1) {
-                        Some(s) if !s.starts_with('-') => {
-                            i += 1;
-                            key = Some(String::from(s));
-                        }
-                        _ => (),
-                    };
-                }
-                "-s" => {
-                    start_date = match args.get(i + 1) {
-                        Some(s) if !s.starts_with('-') => {
-                            i += 1;
-                            match s.is_empty() {
-                                false => OptionalDate::Some(String::from(s)),
-                                true => OptionalDate::Ask,
-                            }
-                        }
-                        _ => OptionalDate::Ask,
-                    };
-                }
-                "-e" => {
-                    end_date = match args.get(i + 1) {
-                        Some(s) if !s.starts_with('-') => {
-                            i += 1;
-                            match s.is_empty() {
-                                false => OptionalDate::Some(String::from(s)),
-                                true => OptionalDate::Ask,
-                            }
-                        }
-                        _ => OptionalDate::Ask,
-                    };
-                }
-                _ => {
-                    println!("Warning: Invalid argument(s)!\n{}", HELP);
-                    return Ok(());
-                }
+//Stdout is kept for the porcelain line or the total even if the arguments could not be parsed
+fn usage_status(args: &[String]) -> Status {
+    let options = args.iter().take_while(|a| *a != "--");
+    match options
+        .into_iter()
+        .any(|a| a == "--porcelain" || a == "--raw-seconds")
+    {
+        true => Status::Stderr,
+        false => Status::Stdout,
+    }
+}
+
+fn exit_code(result: &Result<(), Box<dyn std::error::Error>>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) if e.is::<Usage>() => EXIT_USAGE,
+        Err(e) if e.is::<yt_api_videosum::Partial>() => EXIT_PARTIAL,
+        Err(_) => EXIT_ERROR,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Run,
+    Resolve,
+    Stats,
+    Estimate,
+}
+impl Command {
+    fn name(self) -> &'static str {
+        match self {
+            Command::Run => "run",
+            Command::Resolve => "resolve",
+            Command::Stats => "stats",
+            Command::Estimate => "estimate",
+        }
+    }
+
+    fn help(self) -> &'static str {
+        match self {
+            Command::Run => DESC,
+            Command::Resolve => RESOLVE_DESC,
+            Command::Stats => STATS_DESC,
+            Command::Estimate => ESTIMATE_DESC,
+        }
+    }
+
+    //Options besides '-h' and '-V', all of them for a run
+    fn accepts(self, option: &str) -> bool {
+        const KEY: &[&str] = &["-k", "--key", "-K", "--key-file", "--key-profile"];
+        const COMMON: &[&str] = &[
+            "--color",
+            "-v",
+            "--verbose",
+            "-vv",
+            "--first-match",
+            "--user-agent",
+        ];
+        match self {
+            Command::Run => true,
+            Command::Resolve => KEY.contains(&option) || COMMON.contains(&option),
+            Command::Stats => ["--lang", "--color"].contains(&option),
+            Command::Estimate => {
+                KEY.contains(&option)
+                    || COMMON.contains(&option)
+                    || ["-s", "--start", "-e", "--end", "--playlist"].contains(&option)
             }
-        } else if i == args.len() - 1 {
-            channel_name = Some(e.clone());
-        } else {
-            println!("Warning: Invalid argument(s)!\n{}", HELP);
-            return Ok(());
         }
+    }
+}
+
+//The command named by the first argument, or a run with all of them (as before the commands existed)
+fn split_command(args: &[String]) -> (Command, &[String]) {
+    let command = match args.first().map(String::as_str) {
+        Some("run") => Command::Run,
+        Some("resolve") => Command::Resolve,
+        Some("stats") => Command::Stats,
+        Some("estimate") => Command::Estimate,
+        _ => return (Command::Run, args),
+    };
+    (command, &args[1..])
+}
+
+fn dispatch(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (command, args) = split_command(args);
+    if let Some(s) = early_exit(args, command.help()) {
+        println!("{}", s);
+        return Ok(());
+    }
+    if std::env::var_os("RUST_LOG").is_some() {
+        env_logger::init();
+    }
+    match command {
+        Command::Run => run(args),
+        Command::Resolve => resolve(args),
+        Command::Stats => stats(args),
+        Command::Estimate => estimate(args),
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    /* Start loading command line arguments */
 
-        i += 1;
+    let mut a = parse_args(args).map_err(|e| match e.is::<Usage>() {
+        true => e,
+        false => Usage(e.to_string()).into(),
+    })?;
+    if a.wizard {
+        return wizard(args, a);
+    }
+
+    yt_api_videosum::color::init(a.color);
+    check_conflicts(&a)?;
+    let status = message_status(a.porcelain || a.raw_seconds);
+    if let Some(ref path) = a.channels_file {
+        a.compare = Some(read_channels_file(path, status)?);
     }
 
     /* Parse dates if specified */
 
-    if let OptionalDate::Some(ref s) = start_date {
-        match DateTime::parse_from_rfc3339(s) {
-            Ok(d) => {
-                start_date = OptionalDate::Date(DateTime::<Utc>::from(d));
+    let mut r = resolve_options(&a, status)?;
+
+    /* Parse or load API key */
+
+    if a.store_key {
+        return store_key(&a, status);
+    }
+    (r.key, r.key_source) = load_key(
+        a.key.clone(),
+        a.key_file.as_deref(),
+        a.key_profile.as_deref(),
+        status,
+    )?;
+    if a.check_key {
+        yt_api_videosum::check_key(&base_config(&a, &r, String::new()))
+            .map_err(|e| format!("The API key cannot be used: {}", e))?;
+        statusln!(status, "The API key is valid.");
+        return Ok(());
+    }
+
+    /* Ask for channel name if not specified */
+
+    r.channel_name = match a.channel_name {
+        Some(ref name) => {
+            parse_channel(name).map_err(|e| format!("Invalid channel '{}': {}", name, e))?
+        }
+        None if a.compare.is_some() => String::new(),
+        None => ask_channel(r.interactive, &mut std::io::stdin().lock(), status)?,
+    };
+
+    if a.channel_stats {
+        let stats = yt_api_videosum::channel_stats(&base_config(&a, &r, r.channel_name.clone()))?;
+        match a.porcelain {
+            true => println!("{}", yt_api_videosum::report::stats_porcelain_line(&stats)),
+            false => yt_api_videosum::print_channel_stats(&stats, status),
+        }
+        return Ok(());
+    }
+    if a.print_playlist_id {
+        let config = base_config(&a, &r, r.channel_name.clone());
+        //Alone on stdout, to be captured by scripts
+        println!("{}", yt_api_videosum::playlist_id(&config)?);
+        return Ok(());
+    }
+
+    /* Load the newest video of the last run if needed */
+
+    load_state(&a, &mut r)?;
+
+    /* Ask for dates if needed */
+
+    ask_dates(&mut r)?;
+
+    if a.explain || a.explain_only {
+        status
+            .direct()
+            .print(format_args!("{}", explain_table(&explain_entries(&a, &r)?)));
+        if a.explain_only {
+            return Ok(());
+        }
+    }
+
+    match a.compare.take() {
+        Some(channels) => run_multi(a, r, channels),
+        None => run_single(a, r),
+    }
+}
+
+//Resolved from the arguments for a run, by prompting for the ones not given if needed
+struct Resolved {
+    status: Status,
+    interactive: bool,
+    key: String,
+    channel_name: String,
+    zone: Zone,
+    start_date: OptionalDate,
+    end_date: OptionalDate,
+    output_mode: OutputMode,
+    format: yt_api_videosum::output::Format,
+    footer: bool,
+    date_format: Option<yt_api_videosum::output::DateFormat>,
+    state_path: Option<PathBuf>,
+    //Where the values came from, for '--explain'
+    key_source: String,
+    channel_source: &'static str,
+    start_source: &'static str,
+    end_source: &'static str,
+}
+
+//The options given together that cannot be used so, before anything is read or asked
+fn check_conflicts(a: &Args) -> Result<(), Usage> {
+    if a.channels_file.is_some() && a.compare.is_some() {
+        return Err(Usage::conflict("--channels-compare", "--channels-file"));
+    }
+    //The channels file is read into the same list, named as given in the messages
+    let compare = a.compare.is_some() || a.channels_file.is_some();
+    let compare_option = match a.channels_file {
+        Some(_) => "--channels-file",
+        None => "--channels-compare",
+    };
+
+    if a.local_time && a.timezone.is_some() {
+        return Err(Usage::conflict("--local-time", "--timezone"));
+    }
+    if a.no_clobber && a.output_mode == OutputMode::Force {
+        return Err(Usage::conflict("--no-clobber", "--force"));
+    }
+    if a.output_fd.is_some() && a.output_given {
+        return Err(Usage::conflict("--output-fd", "-o"));
+    }
+    if a.no_output {
+        for (given, option) in [
+            (a.output_given, "-o"),
+            (a.output_fd.is_some(), "--output-fd"),
+            (a.verify_output, "--verify-output"),
+        ] {
+            if given {
+                return Err(Usage::conflict("--no-output", option));
             }
-            Err(e) => {
-                Err(format!("Could not parse start timestamp '{}': {}", &s, e))?;
+        }
+    }
+    if a.stream {
+        for (given, option) in [
+            (a.sort.is_some(), "--sort"),
+            (!a.extra_paths.is_empty(), "-o"),
+            (a.verify_output, "--verify-output"),
+            (a.by_hour, "--by-hour"),
+            (a.captions_report, "--captions-report"),
+            (a.quality_report, "--quality-report"),
+            (a.immersive_report, "--immersive-report"),
+            (a.buckets_path.is_some(), "--group-by-duration-bucket"),
+            (a.months_path.is_some(), "--monthly-csv"),
+            (compare, compare_option),
+            (a.diff_path.is_some(), "--diff-file"),
+        ] {
+            if given {
+                return Err(Usage::conflict("--stream", option));
             }
         }
     }
-    if let OptionalDate::Some(ref s) = end_date {
-        match DateTime::parse_from_rfc3339(s) {
-            Ok(d) => {
-                end_date = OptionalDate::Date(DateTime::<Utc>::from(d));
+    if a.count_only && a.thumbnail.is_some() {
+        return Err(Usage::conflict("--with-thumbnail", "--count-only"));
+    }
+    if a.count_only && a.title_filter.is_some() {
+        return Err(Usage(String::from(
+            "The '--title-any' and '--title-all' options cannot be used with '--count-only'",
+        )));
+    }
+    if a.resume_from.is_some() && compare {
+        return Err(Usage::conflict("--resume-from", compare_option));
+    }
+    //The resumed videos have no status to check
+    if a.resume_from.is_some() && a.strict_public {
+        return Err(Usage::conflict("--resume-from", "--strict-public"));
+    }
+    if a.diff_path.is_some() {
+        if compare {
+            return Err(Usage::conflict("--diff-file", compare_option));
+        }
+        if a.count_only {
+            return Err(Usage::conflict("--diff-file", "--count-only"));
+        }
+    }
+    if a.skipped_path.is_some() && compare {
+        return Err(Usage::conflict("--skipped-report", compare_option));
+    }
+    if a.channel_stats && compare {
+        return Err(Usage::conflict("--channel-stats", compare_option));
+    }
+    if a.print_playlist_id {
+        if compare {
+            return Err(Usage::conflict("--print-playlist-id", compare_option));
+        }
+        if a.channel_stats {
+            return Err(Usage::conflict("--print-playlist-id", "--channel-stats"));
+        }
+    }
+    if a.check_key {
+        for (given, option) in [
+            (a.store_key, "--store-key"),
+            (compare, compare_option),
+            (a.channel_stats, "--channel-stats"),
+            (a.print_playlist_id, "--print-playlist-id"),
+        ] {
+            if given {
+                return Err(Usage::conflict("--check-key", option));
             }
-            Err(e) => {
-                Err(format!("Could not parse end timestamp '{}': {}", &s, e))?;
+        }
+        if a.channel_name.is_some() {
+            return Err(Usage(String::from(
+                "The '--check-key' option does not take a channel",
+            )));
+        }
+    }
+    if a.since_last_run {
+        if !matches!(a.start_date, OptionalDate::None) {
+            return Err(Usage::conflict("--since-last-run", "-s"));
+        }
+        if compare {
+            return Err(Usage::conflict("--since-last-run", compare_option));
+        }
+    }
+    if a.raw_seconds {
+        for (given, option) in [
+            (a.porcelain, "--porcelain"),
+            (compare, compare_option),
+            (a.channel_stats, "--channel-stats"),
+            (a.print_playlist_id, "--print-playlist-id"),
+        ] {
+            if given {
+                return Err(Usage::conflict("--raw-seconds", option));
             }
         }
     }
-
-    /* Parse or load API key */
-
-    let key = match key {
-        Some(k) => k,
-        None => {
-            println!("Info: No API key supplied, trying 'config/key.txt' file...");
-            let file = std::fs::File::open("config/key.txt")?;
-            let meta = file.metadata()?;
-            if !meta.is_file() {
-                return Err("Target is not a regular file".into());
-            } else {
-                match meta.len() {
-                    0 => return Err("File is empty".into()),
-                    128.. => {
-                        return Err(format!(
-                            "File looks too large to only contain the key [len={}]",
-                            meta.len()
-                        )
-                        .into())
-                    }
-                    _ => {
-                        let mut s = String::new();
-                        std::io::BufReader::new(file).read_line(&mut s)?;
-                        println!("Successfully loaded API key.");
-                        match s.trim().split_once(char::is_whitespace) {
-                            Some((first, _)) => String::from(first),
-                            None => s,
-                        }
-                    }
-                }
+    if a.total_fd.is_some() && !a.raw_seconds {
+        return Err(Usage(String::from(
+            "The '--total-fd' option requires '--raw-seconds'",
+        )));
+    }
+    if a.total_fd.is_some() && a.total_fd == a.output_fd {
+        return Err(Usage(String::from(
+            "The '--output-fd' and '--total-fd' options cannot be given the same file descriptor",
+        )));
+    }
+    if a.fill_gaps && a.months_path.is_none() {
+        return Err(Usage(String::from(
+            "The '--fill-gaps' option requires '--monthly-csv'",
+        )));
+    }
+    //Only the table and the merged output are written, while the filters and limits apply to each channel
+    if compare {
+        for (given, option) in [
+            (a.output_given || !a.extra_paths.is_empty(), "-o"),
+            (a.output_fd.is_some(), "--output-fd"),
+            (a.verify_output, "--verify-output"),
+            (a.buckets_path.is_some(), "--group-by-duration-bucket"),
+            (a.months_path.is_some(), "--monthly-csv"),
+            (a.by_hour, "--by-hour"),
+            (a.captions_report, "--captions-report"),
+            (a.quality_report, "--quality-report"),
+            (a.immersive_report, "--immersive-report"),
+            (a.rate, "--rate"),
+            (a.equivalent.is_some(), "--equivalent"),
+            (a.given.iter().any(|g| g.0 == "--lang"), "--lang"),
+        ] {
+            if given {
+                return Err(Usage::conflict(option, compare_option));
             }
         }
+    }
+    if a.merge_output.is_some() && !compare {
+        return Err(Usage(String::from(
+            "The '--merge-output' option requires '--channels-compare' or '--channels-file'",
+        )));
+    }
+    Ok(())
+}
+
+//One channel per line, '-' for stdin, with the invalid lines skipped
+fn read_channels_file(
+    path: &str,
+    status: Status,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let (channels, invalid) = match path {
+        "-" => read_channels(std::io::stdin().lock())?,
+        _ => {
+            read_channels(std::io::BufReader::new(File::open(path).map_err(|e| {
+                format!("Could not open channels file '{}': {}", path, e)
+            })?))?
+        }
     };
+    for e in &invalid {
+        statusln!(status, "Warning: Skipping {}", e);
+    }
+    if channels.is_empty() {
+        return Err(format!("No valid channels in '{}'", path).into());
+    }
+    Ok(channels)
+}
 
-    /* Ask for channel name if not specified */
+//Everything not needing the key nor prompting, for the usage errors to show up first
+fn resolve_options(a: &Args, status: Status) -> Result<Resolved, Box<dyn std::error::Error>> {
+    let zone = match (a.local_time, &a.timezone) {
+        (true, _) => Zone::Local,
+        (false, Some(t)) => Zone::Named(yt_api_videosum::output::parse_timezone(t)?),
+        (false, None) => Zone::Named(chrono_tz::Tz::UTC),
+    };
+    let output_mode = match (a.no_clobber, a.output_mode) {
+        //Appending leaves the content as it was
+        (true, OutputMode::Protect) => OutputMode::NoClobber,
+        (_, mode) => mode,
+    };
 
-    let channel_name = String::from(
-        match channel_name {
-            Some(name) => name,
-            None => {
-                let mut name;
-                loop {
-                    println!("Channel name:");
-                    name = String::new();
-                    std::io::stdin().read_line(&mut name)?;
-                    if name.trim().is_empty() {
-                        println!("Warning: Empty name supplied!");
-                    } else if !name.is_ascii() || name.trim().contains(char::is_whitespace) {
-                        println!("Warning: Invalid character supplied!");
-                    } else {
-                        break;
-                    }
-                }
-                name
-            }
-        }
-        .trim()
-        .trim_matches('@'),
-    );
+    let parse = |d: &OptionalDate, bound, what| match d {
+        OptionalDate::Some(s) => match parse_date(s, bound, &zone, status) {
+            Ok(d) => Ok(OptionalDate::Date(d)),
+            Err(e) => Err(Usage(format!(
+                "Could not parse {} timestamp '{}': {}",
+                what, s, e
+            ))),
+        },
+        OptionalDate::Ask => Ok(OptionalDate::Ask),
+        OptionalDate::Date(d) => Ok(OptionalDate::Date(*d)),
+        OptionalDate::None => Ok(OptionalDate::None),
+    };
+    let start_date = parse(&a.start_date, Bound::Start, "start")?;
+    let end_date = parse(&a.end_date, Bound::End, "end")?;
 
-    /* Ask for dates if needed */
+    let format_given = a.format.is_some();
+    let (format, warning) =
+        output_format(a.format, a.output_given.then_some(a.output_path.as_str()));
+    if let Some(w) = warning {
+        statusln!(status, "Warning: {}", w);
+    }
+    if a.stream && format == yt_api_videosum::output::Format::Json {
+        return Err(Usage(match format_given {
+            true => Usage::conflict("--stream", "--format json").0,
+            false => format!(
+                "The '--stream' option cannot be used with the JSON output '{}'",
+                a.output_path
+            ),
+        })
+        .into());
+    }
 
-    if let OptionalDate::Ask = start_date {
-        loop {
-            println!("Filter to dates starting from:");
-            let mut s = String::new();
-            std::io::stdin().read_line(&mut s)?;
-            let s = s.as_str().trim();
-            match DateTime::parse_from_rfc3339(s.trim()) {
-                Ok(d) => {
-                    start_date = OptionalDate::Date(DateTime::<Utc>::from(d));
-                    break;
-                }
-                Err(e) => {
-                    println!("Warning: Could not parse timestamp '{}': {}", &s, e);
-                    println!("Note: RFC3339 format required, i.e. 'yyyy-mm-ddTHH:MM:SSZ'");
-                }
+    let is_template = format == yt_api_videosum::output::Format::Template;
+    if is_template != a.template.is_some() {
+        return Err(Usage(String::from(
+            "The '--format template' and '--template' options must be used together",
+        ))
+        .into());
+    }
+
+    let date_format = match (&a.date_format, &a.timezone) {
+        (None, None) => None,
+        (f, t) => Some(yt_api_videosum::output::DateFormat::new(
+            f.as_deref(),
+            t.as_deref(),
+        )?),
+    };
+
+    let date_source = |d: &OptionalDate| match d {
+        OptionalDate::None => "default",
+        OptionalDate::Ask => "prompt",
+        _ => "command line",
+    };
+    Ok(Resolved {
+        status,
+        //Stdin is taken by the channel list then
+        interactive: a
+            .interactive
+            .unwrap_or_else(|| std::io::stdin().is_terminal())
+            && a.channels_file.as_deref() != Some("-"),
+        key: String::new(),
+        channel_name: String::new(),
+        zone,
+        start_source: date_source(&start_date),
+        end_source: date_source(&end_date),
+        start_date,
+        end_date,
+        output_mode,
+        format,
+        footer: a.footer.unwrap_or(!is_template),
+        date_format,
+        state_path: None,
+        key_source: String::new(),
+        channel_source: match (&a.channel_name, &a.compare) {
+            (None, None) => "prompt",
+            _ => "command line",
+        },
+    })
+}
+
+fn store_key(a: &Args, status: Status) -> Result<(), Box<dyn std::error::Error>> {
+    if !yt_api_videosum::keystore::ENABLED {
+        return Err(yt_api_videosum::keystore::store(None, "")
+            .unwrap_err()
+            .into());
+    }
+    let key = match a.key {
+        Some(ref k) => k.clone(),
+        None => yt_api_videosum::keystore::read_hidden("API key:", status.direct())?,
+    };
+    yt_api_videosum::keystore::store(a.key_profile.as_deref(), &key)?;
+    statusln!(
+        status,
+        "Key stored in the keyring, as profile '{}'.",
+        a.key_profile.as_deref().unwrap_or("default")
+    );
+    Ok(())
+}
+
+//Forgets the last run if asked, or starts after it with '--since-last-run'
+fn load_state(a: &Args, r: &mut Resolved) -> Result<(), Box<dyn std::error::Error>> {
+    let status = r.status;
+    let channel_name = &r.channel_name;
+    r.state_path = match (&a.state_path, a.since_last_run || a.reset_state) {
+        (Some(p), _) => Some(PathBuf::from(p)),
+        (None, true) => Some(
+            yt_api_videosum::state::default_path(
+                std::env::var("XDG_CONFIG_HOME").ok().as_deref(),
+                std::env::var("HOME").ok().as_deref(),
+            )
+            .ok_or("Could not locate the state file, give it with '--state-file'")?,
+        ),
+        (None, false) => None,
+    };
+    if let (true, Some(ref path)) = (a.reset_state, &r.state_path) {
+        let mut state = yt_api_videosum::state::State::load(path).unwrap_or_default();
+        match state.remove(channel_name) {
+            true => {
+                state.save(path).map_err(|e| {
+                    format!(
+                        "Could not update the state file '{}': {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+                statusln!(
+                    status,
+                    "Note: The last run of '@{}' was forgotten.",
+                    channel_name
+                );
             }
+            false => statusln!(
+                status,
+                "Note: No previous run recorded for '@{}'.",
+                channel_name
+            ),
         }
     }
-    if let OptionalDate::Ask = end_date {
-        loop {
-            println!("Filter to dates ending at:");
-            let mut s = String::new();
-            std::io::stdin().read_line(&mut s)?;
-            let s = s.as_str().trim();
-            match DateTime::parse_from_rfc3339(s) {
-                Ok(d) => {
-                    end_date = OptionalDate::Date(DateTime::<Utc>::from(d));
-                    break;
-                }
-                Err(e) => {
-                    println!("Warning: Could not parse timestamp '{}': {}", &s, e);
-                    println!("Note: RFC3339 format required, i.e. 'yyyy-mm-ddTHH:MM:SSZ'");
-                }
+    if let (true, Some(ref path)) = (a.since_last_run, &r.state_path) {
+        match yt_api_videosum::state::State::load(path).map(|s| s.newest(channel_name)) {
+            Ok(Some(newest)) => {
+                let start = yt_api_videosum::state::start_after(newest);
+                statusln!(
+                    status,
+                    "Fetching the videos since the last run, newer than {}.",
+                    newest.to_rfc3339()
+                );
+                r.start_date = OptionalDate::Date(start);
+                r.start_source = "state file";
             }
+            Ok(None) => statusln!(
+                status,
+                "Note: No previous run recorded for '@{}', fetching all videos.",
+                channel_name
+            ),
+            Err(e) => statusln!(
+                status,
+                "Note: Could not read the state file '{}': {}, fetching all videos.",
+                path.display(),
+                e
+            ),
         }
     }
+    Ok(())
+}
+
+//The dates left to ask for, then the range as a whole is checked
+fn ask_dates(r: &mut Resolved) -> Result<(), Box<dyn std::error::Error>> {
+    let answered = |d: Option<DateTime<Utc>>| match d {
+        Some(d) => OptionalDate::Date(d),
+        None => OptionalDate::None,
+    };
+    if let OptionalDate::Ask = r.start_date {
+        let d = ask_date(
+            Bound::Start,
+            r.end_date.date(),
+            &r.zone,
+            r.interactive,
+            &mut std::io::stdin().lock(),
+            r.status,
+        )?;
+        r.start_date = answered(d);
+    }
+    if let OptionalDate::Ask = r.end_date {
+        let d = ask_date(
+            Bound::End,
+            r.start_date.date(),
+            &r.zone,
+            r.interactive,
+            &mut std::io::stdin().lock(),
+            r.status,
+        )?;
+        r.end_date = answered(d);
+    }
+
+    match yt_api_videosum::dates::check_range(r.start_date.date(), r.end_date.date(), Utc::now()) {
+        Ok(Some(message)) => statusln!(r.status, "{}", message),
+        Ok(None) => (),
+        Err(e) => return Err(Usage(e).into()),
+    }
+    Ok(())
+}
+
+fn explain_entries<'a>(
+    a: &'a Args,
+    r: &'a Resolved,
+) -> Result<Vec<ExplainEntry<'a>>, Box<dyn std::error::Error>> {
+    let source = |given: bool| match given {
+        true => "command line",
+        false => "default",
+    };
+    let flag = |f: &str| a.given.iter().any(|g| g.0 == f);
+    let value = |f: &str| a.given.iter().rev().find(|g| g.0 == f)?.1.clone();
+    let date = |d: Option<DateTime<Utc>>| match d {
+        Some(d) => d.to_rfc3339(),
+        None => String::from("-"),
+    };
+    let channel = match a.compare {
+        Some(ref c) => c
+            .iter()
+            .map(|c| format!("@{}", c))
+            .collect::<Vec<_>>()
+            .join(","),
+        None => format!("@{}", r.channel_name),
+    };
+    let output = yt_api_videosum::output::expand_path(
+        &a.output_path,
+        &r.channel_name,
+        Utc::now(),
+        r.start_date.date(),
+        r.end_date.date(),
+    )?;
+    let entries = [
+        ("key", redact(&r.key), r.key_source.as_str()),
+        ("channel", channel, r.channel_source),
+        ("start", date(r.start_date.date()), r.start_source),
+        ("end", date(r.end_date.date()), r.end_source),
+        ("output", output, source(a.output_given)),
+        (
+            "output fd",
+            a.output_fd.map(|fd| fd.to_string()).unwrap_or_default(),
+            source(a.output_fd.is_some()),
+        ),
+        ("no output", a.no_output.to_string(), source(a.no_output)),
+        (
+            "additional outputs",
+            a.extra_paths.join(", "),
+            source(!a.extra_paths.is_empty()),
+        ),
+        (
+            "output mode",
+            format!("{:?}", r.output_mode),
+            source(flag("--force") || flag("--append") || a.no_clobber),
+        ),
+        (
+            "format",
+            format!("{:?}", r.format),
+            match (flag("--format"), a.output_given) {
+                (false, true) => "output extension",
+                (given, _) => source(given),
+            },
+        ),
+        (
+            "template",
+            value("--template").unwrap_or_default(),
+            source(flag("--template")),
+        ),
+        (
+            "compress",
+            a.compress.to_string(),
+            source(flag("--compress")),
+        ),
+        (
+            "verify output",
+            a.verify_output.to_string(),
+            source(a.verify_output),
+        ),
+        (
+            "footer",
+            r.footer.to_string(),
+            source(flag("--footer") || flag("--no-footer")),
+        ),
+        (
+            "sort",
+            a.sort.map(|k| format!("{:?}", k)).unwrap_or_default(),
+            source(flag("--sort")),
+        ),
+        (
+            "descending",
+            a.sort_desc.to_string(),
+            source(flag("--desc")),
+        ),
+        ("stream", a.stream.to_string(), source(a.stream)),
+        (
+            "date format",
+            value("--date-format").unwrap_or_default(),
+            source(flag("--date-format")),
+        ),
+        (
+            "timezone",
+            value("--timezone").unwrap_or(String::from("UTC")),
+            source(flag("--timezone")),
+        ),
+        (
+            "titles",
+            format!("{:?}", a.titles),
+            source(flag("--strip-emoji") || flag("--ascii-titles")),
+        ),
+        ("language", format!("{:?}", a.lang), source(flag("--lang"))),
+        ("count only", a.count_only.to_string(), source(a.count_only)),
+        ("with stats", a.with_stats.to_string(), source(a.with_stats)),
+        (
+            "thumbnail",
+            a.thumbnail.map(|t| t.to_string()).unwrap_or_default(),
+            source(a.thumbnail.is_some()),
+        ),
+        (
+            "merged output",
+            a.merge_output.clone().unwrap_or_default(),
+            source(a.merge_output.is_some()),
+        ),
+        (
+            "bucket output",
+            a.buckets_path.clone().unwrap_or_default(),
+            source(a.buckets_path.is_some()),
+        ),
+        (
+            "monthly output",
+            a.months_path.clone().unwrap_or_default(),
+            source(a.months_path.is_some()),
+        ),
+        ("fill gaps", a.fill_gaps.to_string(), source(a.fill_gaps)),
+        (
+            "cache",
+            a.cache_path.clone().unwrap_or_default(),
+            source(a.cache_path.is_some()),
+        ),
+        (
+            "cache max age (days)",
+            a.cache_max_age.to_string(),
+            source(flag("--cache-max-age")),
+        ),
+        (
+            "since last run",
+            a.since_last_run.to_string(),
+            source(a.since_last_run),
+        ),
+        (
+            "reset state",
+            a.reset_state.to_string(),
+            source(a.reset_state),
+        ),
+        (
+            "state file",
+            r.state_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            source(flag("--state-file")),
+        ),
+        (
+            "playlists",
+            a.playlists.join(", "),
+            source(!a.playlists.is_empty()),
+        ),
+        (
+            "summary only if nonempty",
+            a.summary_only_if_nonempty.to_string(),
+            source(a.summary_only_if_nonempty),
+        ),
+        ("porcelain", a.porcelain.to_string(), source(a.porcelain)),
+        (
+            "raw seconds",
+            a.raw_seconds.to_string(),
+            source(a.raw_seconds),
+        ),
+        (
+            "total to file descriptor",
+            a.total_fd.map(|fd| fd.to_string()).unwrap_or_default(),
+            source(a.total_fd.is_some()),
+        ),
+        ("skip confirmation", a.yes.to_string(), source(a.yes)),
+        (
+            "maximum retries",
+            a.max_retries_total
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            source(a.max_retries_total.is_some()),
+        ),
+        (
+            "user agent",
+            String::from(
+                a.user_agent
+                    .as_deref()
+                    .unwrap_or(yt_api_videosum::USER_AGENT),
+            ),
+            source(a.user_agent.is_some()),
+        ),
+        (
+            "maximum quota",
+            a.max_quota.map(|n| n.to_string()).unwrap_or_default(),
+            source(a.max_quota.is_some()),
+        ),
+        ("verbosity", a.verbose.to_string(), source(a.verbose > 0)),
+        ("by hour", a.by_hour.to_string(), source(a.by_hour)),
+        (
+            "captions report",
+            a.captions_report.to_string(),
+            source(a.captions_report),
+        ),
+        (
+            "captions column",
+            a.captions_column.to_string(),
+            source(a.captions_column),
+        ),
+        (
+            "quality report",
+            a.quality_report.to_string(),
+            source(a.quality_report),
+        ),
+        (
+            "definition column",
+            a.definition_column.to_string(),
+            source(a.definition_column),
+        ),
+        ("rate", a.rate.to_string(), source(a.rate)),
+        (
+            "round",
+            a.round.map(|r| r.to_string()).unwrap_or_default(),
+            source(a.round.is_some()),
+        ),
+        (
+            "immersive report",
+            a.immersive_report.to_string(),
+            source(a.immersive_report),
+        ),
+        (
+            "immersive columns",
+            a.immersive_columns.to_string(),
+            source(a.immersive_columns),
+        ),
+        (
+            "equivalent",
+            a.equivalent
+                .as_ref()
+                .map(|refs| {
+                    refs.iter()
+                        .map(|r| r.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default(),
+            source(flag("--equivalent") || flag("--equivalent-units")),
+        ),
+        ("local time", a.local_time.to_string(), source(a.local_time)),
+        (
+            "first match",
+            a.first_match.to_string(),
+            source(a.first_match),
+        ),
+        (
+            "verify handle",
+            a.verify_handle.to_string(),
+            source(a.verify_handle),
+        ),
+        (
+            "excluded region",
+            a.exclude_region_blocked.clone().unwrap_or_default(),
+            source(a.exclude_region_blocked.is_some()),
+        ),
+        (
+            "strict public",
+            a.strict_public.to_string(),
+            source(a.strict_public),
+        ),
+        (
+            "title filter",
+            a.title_filter
+                .as_ref()
+                .map(|f| {
+                    format!(
+                        "any of [{}], all of [{}]",
+                        f.any.join(", "),
+                        f.all.join(", ")
+                    )
+                })
+                .unwrap_or_default(),
+            source(a.title_filter.is_some()),
+        ),
+        (
+            "resume from",
+            a.resume_from.clone().unwrap_or_default(),
+            source(a.resume_from.is_some()),
+        ),
+        (
+            "diff with",
+            a.diff_path.clone().unwrap_or_default(),
+            source(a.diff_path.is_some()),
+        ),
+        (
+            "skipped report",
+            a.skipped_path.clone().unwrap_or_default(),
+            source(a.skipped_path.is_some()),
+        ),
+        (
+            "interactive",
+            r.interactive.to_string(),
+            source(flag("--interactive") || flag("--non-interactive")),
+        ),
+        ("color", format!("{:?}", a.color), source(flag("--color"))),
+        (
+            "minimum videos",
+            a.min_videos.map(|n| n.to_string()).unwrap_or_default(),
+            source(a.min_videos.is_some()),
+        ),
+    ];
+    Ok(entries.into())
+}
+
+//The settings of a run not about its outputs, the same for each channel of a multi-channel run
+fn base_config(a: &Args, r: &Resolved, channel_name: String) -> yt_api_videosum::Config {
+    let status = r.status;
+    yt_api_videosum::Config {
+        start_date: r.start_date.date(),
+        end_date: r.end_date.date(),
+        format: r.format,
+        footer: r.footer,
+        sort: a.sort,
+        sort_desc: a.sort_desc,
+        stream: a.stream,
+        summary_only_if_nonempty: a.summary_only_if_nonempty,
+        status,
+        lang: a.lang,
+        date_format: r.date_format.clone(),
+        titles: a.titles,
+        count_only: a.count_only,
+        with_stats: a.with_stats,
+        thumbnail: a.thumbnail,
+        template: a.template.clone(),
+        fill_gaps: a.fill_gaps,
+        cache: a.cache_path.as_ref().map(|p| {
+            yt_api_videosum::cache::ChannelCache::new(
+                p.clone(),
+                chrono::TimeDelta::days(a.cache_max_age),
+            )
+        }),
+        playlists: a.playlists.clone(),
+        confirm: match a.yes || !r.interactive {
+            true => None,
+            false => Some(Box::new(move |e| {
+                confirm_estimate(e, &mut std::io::stdin().lock(), status)
+            })),
+        },
+        pick: channel_pick(r.interactive, status),
+        max_quota: a.max_quota,
+        min_videos: a.min_videos,
+        retries: yt_api_videosum::Retries {
+            total: a.max_retries_total,
+            ..Default::default()
+        },
+        verbose: match std::env::var_os("RUST_LOG").is_some() {
+            true => yt_api_videosum::verbose::Verbose::log_records(),
+            false => yt_api_videosum::verbose::Verbose::new(a.verbose),
+        },
+        by_hour: a.by_hour,
+        captions_report: a.captions_report,
+        captions_column: a.captions_column,
+        quality_report: a.quality_report,
+        definition_column: a.definition_column,
+        immersive_report: a.immersive_report,
+        immersive_columns: a.immersive_columns,
+        rate: a.rate,
+        round: a.round,
+        equivalent: a.equivalent.clone(),
+        first_match: a.first_match,
+        verify_handle: a.verify_handle,
+        exclude_region_blocked: a.exclude_region_blocked.clone(),
+        strict_public: a.strict_public,
+        title_filter: a.title_filter.clone(),
+        verify_output: a.verify_output,
+        progress_bar: r.interactive,
+        cancel: Some(&INTERRUPTED),
+        client: http_client(a.user_agent.as_deref()),
+        ..yt_api_videosum::Config::new(r.key.clone(), channel_name)
+    }
+}
+
+fn run_multi(
+    a: Args,
+    r: Resolved,
+    channels: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (start_date, end_date) = (r.start_date.date(), r.end_date.date());
+    let merged = match a.merge_output {
+        Some(ref path) => Some(create_output(
+            path,
+            "",
+            start_date,
+            end_date,
+            r.output_mode,
+            r.interactive,
+            r.status,
+        )?),
+        None => None,
+    };
+    compare_channels(
+        channels,
+        start_date,
+        end_date,
+        a.porcelain,
+        a.round,
+        merged,
+        |channel_name| base_config(&a, &r, channel_name),
+    )
+}
+
+fn run_single(a: Args, r: Resolved) -> Result<(), Box<dyn std::error::Error>> {
+    let status = r.status;
+    let (start_date, end_date) = (r.start_date.date(), r.end_date.date());
+
+    //Before the output is opened, which may be the same file
+    let resume = match a.resume_from {
+        Some(ref path) => {
+            let videos = read_csv_file(path)?;
+            statusln!(
+                status,
+                "Read {} videos to resume with from '{}'.",
+                videos.len(),
+                path
+            );
+            videos
+        }
+        None => Vec::new(),
+    };
+    let diff = match a.diff_path {
+        Some(ref path) => {
+            let videos = read_csv_file(path)?;
+            statusln!(
+                status,
+                "Read {} videos to compare with from '{}'.",
+                videos.len(),
+                path
+            );
+            Some(videos)
+        }
+        None => None,
+    };
+
+    //Opened before any quota is spent, so an invalid one fails early
+    let total_out: Option<Box<dyn std::io::Write>> = match (a.raw_seconds, a.total_fd) {
+        (true, Some(fd)) => Some(Box::new(output_from_fd(fd)?)),
+        (true, None) => Some(Box::new(std::io::stdout())),
+        (false, _) => None,
+    };
 
     /* Setup output file writer */
 
-    let output = File::create("output.txt")?;
+    let compress = a.compress || yt_api_videosum::output::is_compressed_path(&a.output_path);
+    let Outputs {
+        output,
+        destination,
+        pending,
+        extra_outputs,
+        buckets_output,
+        months_output,
+        skipped_output,
+    } = open_outputs(&OutputSettings {
+        path: &a.output_path,
+        extra_paths: &a.extra_paths,
+        buckets_path: a.buckets_path.as_deref(),
+        months_path: a.months_path.as_deref(),
+        skipped_path: a.skipped_path.as_deref(),
+        fd: a.output_fd,
+        //Without titles there is nothing worth writing
+        enabled: !a.count_only && !a.no_output,
+        mode: r.output_mode,
+        compress,
+        channel_name: &r.channel_name,
+        start_date,
+        end_date,
+        interactive: r.interactive,
+        status,
+    })?;
 
     /* Config done, lib call */
-    yt_api_videosum::run(yt_api_videosum::Config {
-        key,
-        channel_name,
-        start_date: match start_date {
-            OptionalDate::Date(d) => Some(d),
+    let config = yt_api_videosum::Config {
+        output,
+        compress,
+        buckets_output,
+        months_output,
+        skipped_output,
+        extra_outputs,
+        pending: pending.clone(),
+        destination,
+        resume,
+        diff,
+        state: match (a.since_last_run, r.state_path.clone()) {
+            (true, Some(path)) => Some(yt_api_videosum::state::LastRun {
+                path,
+                channel: r.channel_name.clone(),
+            }),
             _ => None,
         },
-        end_date: match end_date {
-            OptionalDate::Date(d) => Some(d),
-            _ => None,
+        ..base_config(&a, &r, r.channel_name.clone())
+    };
+    if !a.porcelain && !a.raw_seconds {
+        return note_partial(yt_api_videosum::run(config), pending.as_ref(), status);
+    }
+    let result = report(config, &r.channel_name, total_out);
+    note_partial(result, pending.as_ref(), status)
+}
+
+//Exactly one line on stdout with '--porcelain', or only the total with '--raw-seconds', even if fetching the videos
+//failed, while an error before (e.g. a missing key file) is only displayed on stderr
+fn report(
+    mut config: yt_api_videosum::Config,
+    channel_name: &str,
+    mut total_out: Option<Box<dyn std::io::Write>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (status, min_videos) = (config.status, config.min_videos);
+    let (start_date, end_date) = (config.start_date, config.end_date);
+    let reports = yt_api_videosum::ReportOptions::take(&mut config);
+    let result = yt_api_videosum::run_collect(config);
+    if let Ok(ref summary) = result {
+        yt_api_videosum::print_reports(summary, &reports, status);
+    }
+    let line = yt_api_videosum::report::porcelain_line(
+        &format!("@{}", channel_name),
+        result.as_ref().map_err(|_| ()),
+        start_date,
+        end_date,
+    );
+    write_result(
+        total_out.as_mut().map(|out| out as &mut dyn std::io::Write),
+        &mut std::io::stdout(),
+        result.as_ref().ok().map(|s| s.total_seconds()),
+        &line,
+    )?;
+    result.and_then(|s| {
+        yt_api_videosum::check_min_videos(&s, min_videos)?;
+        yt_api_videosum::check_warnings(&s)
+    })
+}
+
+//With '--raw-seconds' only the total of a successful run, on its own stream, otherwise the porcelain line on stdout
+fn write_result(
+    total_out: Option<&mut dyn std::io::Write>,
+    stdout: &mut dyn std::io::Write,
+    total_seconds: Option<i64>,
+    porcelain_line: &str,
+) -> std::io::Result<()> {
+    match (total_out, total_seconds) {
+        (Some(out), Some(total)) => writeln!(out, "{}", total),
+        (Some(_), None) => Ok(()),
+        (None, _) => writeln!(stdout, "{}", porcelain_line),
+    }
+}
+
+//Parsed as for a run, then any option the command does not take is rejected
+fn parse_command_args(
+    command: Command,
+    args: &[String],
+) -> Result<Args, Box<dyn std::error::Error>> {
+    let a = parse_args(args).map_err(|e| match e.is::<Usage>() {
+        true => e,
+        false => Usage(e.to_string()).into(),
+    })?;
+    if let Some((name, _)) = a.given.iter().find(|(name, _)| !command.accepts(name)) {
+        return Err(Usage(format!(
+            "Option '{}' is not accepted by '{}'",
+            name,
+            command.name()
+        ))
+        .into());
+    }
+    yt_api_videosum::color::init(a.color);
+    Ok(a)
+}
+
+//Common to the commands requesting a channel
+fn command_config(
+    a: Args,
+    status: Status,
+) -> Result<yt_api_videosum::Config, Box<dyn std::error::Error>> {
+    let channel_name = a
+        .channel_name
+        .ok_or_else(|| Usage::missing("channel name"))?;
+    let channel_name = parse_channel(&channel_name)
+        .map_err(|e| format!("Invalid channel '{}': {}", channel_name, e))?;
+    let (key, _) = load_key(
+        a.key,
+        a.key_file.as_deref(),
+        a.key_profile.as_deref(),
+        status,
+    )?;
+    Ok(yt_api_videosum::Config {
+        status,
+        first_match: a.first_match,
+        playlists: a.playlists,
+        client: http_client(a.user_agent.as_deref()),
+        verbose: match std::env::var_os("RUST_LOG").is_some() {
+            true => yt_api_videosum::verbose::Verbose::log_records(),
+            false => yt_api_videosum::verbose::Verbose::new(a.verbose),
         },
-        output: Some(output),
+        ..yt_api_videosum::Config::new(key, channel_name)
+    })
+}
+
+fn resolve(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let status = message_status(false);
+    let config = command_config(parse_command_args(Command::Resolve, args)?, status)?;
+    let channel = yt_api_videosum::resolve_channel(&config, &config.channel_name)?;
+    println!("Channel: {}", channel.title);
+    println!("Channel ID: {}", channel.id);
+    println!("Uploads playlist: {}", channel.uploads_playlist_id);
+    println!(
+        "Public uploads playlist: {}",
+        yt_api_videosum::public_playlist_id(&channel.uploads_playlist_id)
+    );
+    Ok(())
+}
+
+fn stats(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let a = parse_command_args(Command::Stats, args)?;
+    let path = a.channel_name.ok_or_else(|| Usage::missing("CSV file"))?;
+    let videos = read_csv_file(&path)?;
+    message_status(false).print(format_args!(
+        "{}",
+        yt_api_videosum::report::offline_stats(&videos, a.lang)
+    ));
+    Ok(())
+}
+
+//An earlier output, '-' for stdin, decompressed if it ends with '.gz'
+fn read_csv_file(path: &str) -> Result<Vec<yt_api_videosum::Video>, Box<dyn std::error::Error>> {
+    let read = |reader: Box<dyn BufRead>| {
+        yt_api_videosum::read_csv(reader)
+            .map_err(|e| format!("Could not read the CSV file '{}': {}", path, e))
+    };
+    Ok(match path {
+        "-" => read(Box::new(std::io::stdin().lock()))?,
+        _ => {
+            let file = File::open(path)
+                .map_err(|e| format!("Could not open the CSV file '{}': {}", path, e))?;
+            match path.ends_with(".gz") {
+                true => read(Box::new(std::io::BufReader::new(
+                    flate2::read::GzDecoder::new(file),
+                )))?,
+                false => read(Box::new(std::io::BufReader::new(file)))?,
+            }
+        }
     })
 }
+
+fn estimate(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let status = message_status(false);
+    let a = parse_command_args(Command::Estimate, args)?;
+    let utc = Zone::Named(chrono_tz::Tz::UTC);
+    let date = |d: &OptionalDate, bound: Bound, what: &str| match d {
+        OptionalDate::None => Ok(None),
+        OptionalDate::Ask => Err(Usage::missing(what)),
+        OptionalDate::Some(s) => match parse_date(s, bound, &utc, status) {
+            Ok(d) => Ok(Some(d)),
+            Err(e) => Err(Usage(format!("Could not parse {} '{}': {}", what, s, e))),
+        },
+        OptionalDate::Date(d) => Ok(Some(*d)),
+    };
+    let start_date = date(&a.start_date, Bound::Start, "start timestamp")?;
+    let end_date = date(&a.end_date, Bound::End, "end timestamp")?;
+    let config = yt_api_videosum::Config {
+        start_date,
+        end_date,
+        ..command_config(a, status)?
+    };
+    let e = yt_api_videosum::run_estimate(config)?;
+    statusln!(status, "{}", estimate_message(&e));
+    Ok(())
+}
+
+fn estimate_message(e: &yt_api_videosum::Estimate) -> String {
+    format!(
+        "Videos to fetch: {}\n\
+        Quota cost: ~{} units ({} spent on the channel and the playlists, ~{} for the videos)\n\
+        Expected time: {}",
+        group_digits(e.videos),
+        group_digits(e.spent + e.units),
+        group_digits(e.spent),
+        group_digits(e.units),
+        expected_time(e.duration)
+    )
+}
+
+//The key and where it came from: the command line, the keyring or the key file
+fn load_key(
+    key: Option<String>,
+    key_file: Option<&str>,
+    key_profile: Option<&str>,
+    status: Status,
+) -> Result<(String, String), Box<dyn std::error::Error>> {
+    yt_api_videosum::keystore::resolve(
+        key,
+        //A key file given explicitly is meant to be used
+        || match key_file {
+            Some(_) => Ok(None),
+            None => yt_api_videosum::keystore::get(key_profile),
+        },
+        || {
+            let xdg_config_home = std::env::var("XDG_CONFIG_HOME").ok();
+            let home = std::env::var("HOME").ok();
+            let path = yt_api_videosum::keyfile::locate(
+                key_file,
+                &yt_api_videosum::keyfile::candidates(xdg_config_home.as_deref(), home.as_deref()),
+            )?;
+            statusln!(
+                status,
+                "Info: No API key supplied, trying '{}' file...",
+                path.display()
+            );
+            let loaded = yt_api_videosum::keyfile::read_key(&path, key_profile)?;
+            for w in loaded.warnings {
+                statusln!(status, "Warning: {}", w);
+            }
+            statusln!(status, "Successfully loaded API key.");
+            let source = match loaded.profile {
+                Some(p) => format!("file '{}', profile '{}'", path.display(), p),
+                None => format!("file '{}'", path.display()),
+            };
+            Ok((loaded.key, source))
+        },
+        status,
+    )
+}
+
+//An incomplete output is kept under the temporary name, to help figuring out what went wrong, unless it is empty
+fn note_partial<T>(
+    result: T,
+    pending: Option<&yt_api_videosum::output::Pending>,
+    status: Status,
+) -> T {
+    if let Some(p) = pending {
+        match p.discard_if_empty() {
+            Ok(true) => statusln!(
+                status,
+                "Note: The incomplete output was left in '{}'.",
+                p.partial
+            ),
+            Ok(false) => (),
+            Err(e) => statusln!(
+                status,
+                "Warning: Could not remove the empty output '{}': {}",
+                p.partial,
+                e
+            ),
+        }
+    }
+    result
+}
+
+//Options of the settings asked by the wizard
+const WIZARD_ASKS: &[&str] = &[
+    "-s",
+    "--start",
+    "-e",
+    "--end",
+    "-o",
+    "--output",
+    "--format",
+    "--exclude-region-blocked",
+    "--playlist",
+    "--channels-compare",
+    "--channels-file",
+    "--non-interactive",
+];
+
+//Asks for the settings, then runs with them as if given on the command line (besides the other options given)
+fn wizard(args: &[String], a: Args) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some((name, _)) = a
+        .given
+        .iter()
+        .find(|(name, _)| WIZARD_ASKS.contains(&name.as_str()))
+    {
+        return Err(Usage::conflict("--wizard", name).into());
+    }
+    if a.channel_name.is_some() {
+        return Err(Usage(String::from(
+            "The channel is asked by '--wizard', it cannot be given as an argument",
+        ))
+        .into());
+    }
+    yt_api_videosum::color::init(a.color);
+    let status = message_status(a.porcelain);
+    let (key, _) = load_key(
+        a.key,
+        a.key_file.as_deref(),
+        a.key_profile.as_deref(),
+        status,
+    )?;
+
+    let resolve = |name: &str| {
+        let handle = parse_channel(name)?;
+        let config = yt_api_videosum::Config {
+            status,
+            first_match: a.first_match,
+            client: http_client(a.user_agent.as_deref()),
+            ..yt_api_videosum::Config::new(key.clone(), handle.clone())
+        };
+        yt_api_videosum::resolve_channel(&config, &handle)
+            .map(|channel| (handle, channel.title))
+            .map_err(|e| e.to_string())
+    };
+    let mut w = yt_api_videosum::wizard::Wizard::new(resolve, Utc::now());
+    let mut input = std::io::stdin().lock();
+    while let Some(prompt) = w.prompt() {
+        match prompt.default {
+            Some(d) => statusln!(status.direct(), "{} [{}]", prompt.question, d),
+            None => statusln!(status.direct(), "{}", prompt.question),
+        }
+        let mut s = String::new();
+        //Ctrl-D
+        if input.read_line(&mut s)? == 0 {
+            return Err("Aborted, no settings were given".into());
+        }
+        match w.answer(&s) {
+            Ok(Some(message)) => statusln!(status, "{}", message),
+            Ok(None) => (),
+            Err(e) => statusln!(status, "Warning: {}", e),
+        }
+    }
+    drop(input);
+
+    let asked = w.command_line();
+    statusln!(
+        status,
+        "Equivalent command line:\n{}",
+        wizard_command(args, &asked)
+    );
+    let mut rerun = without_wizard(args, false);
+    rerun.extend(asked);
+    run(&rerun)
+}
+
+//The arguments besides '-i', and optionally the key, which should not end up in a script or the shell history
+fn without_wizard(args: &[String], hide_key: bool) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut options = args.iter().take_while(|e| *e != "--").peekable();
+    while let Some(e) = options.next() {
+        match e.as_str() {
+            "-i" | "--wizard" => (),
+            "-k" | "--key" if hide_key => {
+                if options.peek().is_some_and(|v| !v.starts_with('-')) {
+                    options.next();
+                }
+            }
+            _ if hide_key && e.starts_with("--key=") => (),
+            _ => kept.push(e.clone()),
+        }
+    }
+    kept
+}
+
+fn wizard_command(args: &[String], asked: &[String]) -> String {
+    let mut command = vec![String::from("yt_api_videosum")];
+    command.extend(without_wizard(args, true));
+    command.extend(asked.iter().cloned());
+    command
+        .iter()
+        .map(|e| yt_api_videosum::report::shell_quote(e))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn redact(key: &str) -> String {
+    format!("<redacted, {} characters>", key.chars().count())
+}
+
+//Name, value and where it came from
+type ExplainEntry<'a> = (&'static str, String, &'a str);
+
+//In aligned columns
+fn explain_table(entries: &[ExplainEntry]) -> String {
+    let name_width = entries.iter().map(|e| e.0.len()).max().unwrap_or(0);
+    let value_width = entries
+        .iter()
+        .map(|e| e.1.chars().count().max(1))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::from("Resolved configuration:\n");
+    for (name, value, source) in entries {
+        let value = match value.is_empty() {
+            true => "-",
+            false => value.as_str(),
+        };
+        out.push_str(&format!(
+            "  {:<nw$}  {:<vw$}  ({})\n",
+            name,
+            value,
+            source,
+            nw = name_width,
+            vw = value_width
+        ));
+    }
+    out
+}
+
+//Command line mistakes, displayed as a warning pointing to the help
+#[derive(Debug)]
+struct Usage(String);
+impl Usage {
+    fn missing(what: &str) -> Self {
+        Self(format!("Missing {}!", what))
+    }
+
+    fn conflict(a: &str, b: &str) -> Self {
+        Self(format!(
+            "The '{}' and '{}' options cannot be used together",
+            a, b
+        ))
+    }
+}
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for Usage {}
+
+//Every option name accepted, for suggesting the closest one on a typo
+const OPTIONS: &[&str] = &[
+    "-k",
+    "--key",
+    "-K",
+    "--key-file",
+    "--key-profile",
+    "--store-key",
+    "-s",
+    "--start",
+    "-e",
+    "--end",
+    "-o",
+    "--output",
+    "--output-fd",
+    "--no-output",
+    "--force",
+    "--append",
+    "--no-clobber",
+    "--compress",
+    "--verify-output",
+    "--format",
+    "--template",
+    "--footer",
+    "--no-footer",
+    "--sort",
+    "--desc",
+    "--stream",
+    "--summary-only-if-nonempty",
+    "--porcelain",
+    "--raw-seconds",
+    "--total-fd",
+    "--group-by-duration-bucket",
+    "--monthly-csv",
+    "--fill-gaps",
+    "--cache",
+    "--cache-max-age",
+    "--since-last-run",
+    "--reset-state",
+    "--state-file",
+    "--playlist",
+    "--yes",
+    "--max-quota",
+    "--max-retries-total",
+    "--user-agent",
+    "--min-videos",
+    "-v",
+    "--verbose",
+    "-vv",
+    "--by-hour",
+    "--captions-report",
+    "--captions-column",
+    "--quality-report",
+    "--definition-column",
+    "--immersive-report",
+    "--rate",
+    "--round",
+    "--immersive-columns",
+    "--equivalent",
+    "--equivalent-units",
+    "--local-time",
+    "--first-match",
+    "--verify-handle",
+    "--exclude-region-blocked",
+    "--strict-public",
+    "--title-any",
+    "--title-all",
+    "--resume-from",
+    "--diff-file",
+    "--skipped-report",
+    "--interactive",
+    "--non-interactive",
+    "--color",
+    "--explain",
+    "--explain-only",
+    "--count-only",
+    "--channel-stats",
+    "--print-playlist-id",
+    "--check-key",
+    "--with-stats",
+    "--with-thumbnail",
+    "-i",
+    "--wizard",
+    "--strip-emoji",
+    "--ascii-titles",
+    "--date-format",
+    "--timezone",
+    "--lang",
+    "--channels-compare",
+    "--channels-file",
+    "--merge-output",
+    "-h",
+    "--help",
+    "-V",
+    "--version",
+];
+
+enum Arg {
+    Opt(String),
+    Positional(String),
+}
+
+//Splits '--option=value', and treats everything after '--' as positional
+struct ArgParser<'a> {
+    args: &'a [String],
+    i: usize,
+    inline: Option<String>,
+    positional_only: bool,
+    given: Vec<(String, Option<String>)>,
+}
+impl<'a> ArgParser<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self {
+            args,
+            i: 0,
+            inline: None,
+            positional_only: false,
+            given: Vec::new(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Arg>, Usage> {
+        if self.inline.is_some() {
+            let name = &self.given[self.given.len() - 1].0;
+            return Err(Usage(format!("Option '{}' does not take a value!", name)));
+        }
+        let Some(e) = self.args.get(self.i) else {
+            return Ok(None);
+        };
+        self.i += 1;
+
+        if self.positional_only || !e.starts_with('-') || e == "-" {
+            return Ok(Some(Arg::Positional(e.clone())));
+        }
+        if e == "--" {
+            self.positional_only = true;
+            return self.next();
+        }
+        let (name, inline) = match e.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n, Some(String::from(v))),
+            _ => (e.as_str(), None),
+        };
+        self.inline = inline;
+        self.given.push((String::from(name), None));
+        Ok(Some(Arg::Opt(String::from(name))))
+    }
+
+    //The inline value, or else the next argument unless it looks like an option, for a value that may be left out
+    fn value(&mut self) -> Option<String> {
+        self.optional(|s| !s.starts_with('-'))
+    }
+
+    //The inline value, or else the next argument whatever it is, so e.g. a key may start with a '-'
+    fn required(&mut self, what: &str) -> Result<String, Usage> {
+        let v = match self.inline.take() {
+            Some(v) => v,
+            None => {
+                let s = self.args.get(self.i).ok_or_else(|| Usage::missing(what))?;
+                self.i += 1;
+                s.clone()
+            }
+        };
+        if let Some(g) = self.given.last_mut() {
+            g.1 = Some(v.clone());
+        }
+        Ok(v)
+    }
+
+    //The inline value, or else the next argument only if accepted, so one left out does not take e.g. the channel
+    fn optional(&mut self, accept: impl Fn(&str) -> bool) -> Option<String> {
+        match self.inline.is_some() || self.args.get(self.i).is_some_and(|s| accept(s)) {
+            true => self.required("").ok(),
+            false => None,
+        }
+    }
+}
+
+fn suggest(name: &str) -> Option<&'static str> {
+    if !name.starts_with("--") {
+        return None;
+    }
+    OPTIONS
+        .iter()
+        .filter(|o| o.starts_with("--"))
+        .map(|o| (edit_distance(name, o), *o))
+        .filter(|(d, _)| *d <= 2)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, o)| o)
+}
+
+//Levenshtein distance, with a single row kept
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diag + (ca != *cb) as usize)
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diag = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+struct Args {
+    key: Option<String>,
+    key_file: Option<String>,
+    key_profile: Option<String>,
+    store_key: bool,
+    start_date: OptionalDate,
+    end_date: OptionalDate,
+    channel_name: Option<String>,
+    output_path: String,
+    output_given: bool,
+    output_fd: Option<i32>,
+    no_output: bool,
+    extra_paths: Vec<String>,
+    output_mode: OutputMode,
+    no_clobber: bool,
+    compress: bool,
+    format: Option<yt_api_videosum::output::Format>,
+    footer: Option<bool>,
+    template: Option<yt_api_videosum::template::Template>,
+    sort: Option<yt_api_videosum::SortKey>,
+    sort_desc: bool,
+    stream: bool,
+    summary_only_if_nonempty: bool,
+    compare: Option<Vec<String>>,
+    channels_file: Option<String>,
+    merge_output: Option<String>,
+    porcelain: bool,
+    raw_seconds: bool,
+    total_fd: Option<i32>,
+    lang: yt_api_videosum::lang::Lang,
+    date_format: Option<String>,
+    timezone: Option<String>,
+    titles: yt_api_videosum::TitleOptions,
+    count_only: bool,
+    channel_stats: bool,
+    print_playlist_id: bool,
+    check_key: bool,
+    buckets_path: Option<String>,
+    months_path: Option<String>,
+    fill_gaps: bool,
+    cache_path: Option<String>,
+    cache_max_age: i64,
+    since_last_run: bool,
+    reset_state: bool,
+    state_path: Option<String>,
+    playlists: Vec<String>,
+    yes: bool,
+    max_quota: Option<u64>,
+    max_retries_total: Option<u32>,
+    user_agent: Option<String>,
+    min_videos: Option<usize>,
+    verbose: u8,
+    by_hour: bool,
+    captions_report: bool,
+    captions_column: bool,
+    quality_report: bool,
+    definition_column: bool,
+    immersive_report: bool,
+    immersive_columns: bool,
+    rate: bool,
+    round: Option<yt_api_videosum::Rounding>,
+    equivalent: Option<Vec<yt_api_videosum::equivalent::Reference>>,
+    local_time: bool,
+    first_match: bool,
+    verify_handle: bool,
+    exclude_region_blocked: Option<String>,
+    strict_public: bool,
+    title_filter: Option<yt_api_videosum::TitleFilter>,
+    resume_from: Option<String>,
+    diff_path: Option<String>,
+    skipped_path: Option<String>,
+    verify_output: bool,
+    //Whether to prompt for missing inputs, detected from stdin if not given
+    interactive: Option<bool>,
+    color: yt_api_videosum::color::Choice,
+    explain: bool,
+    explain_only: bool,
+    with_stats: bool,
+    thumbnail: Option<yt_api_videosum::ThumbnailQuality>,
+    wizard: bool,
+    //Option names as given, with their value if any
+    given: Vec<(String, Option<String>)>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, Box<dyn std::error::Error>> {
+    let mut a = Args {
+        key: None,
+        key_file: None,
+        key_profile: None,
+        store_key: false,
+        start_date: OptionalDate::None,
+        end_date: OptionalDate::None,
+        channel_name: None,
+        output_path: String::from(yt_api_videosum::output::DEFAULT_PATH),
+        output_given: false,
+        output_fd: None,
+        no_output: false,
+        extra_paths: Vec::new(),
+        output_mode: OutputMode::Protect,
+        no_clobber: false,
+        compress: false,
+        format: None,
+        footer: None,
+        template: None,
+        sort: None,
+        sort_desc: false,
+        stream: false,
+        summary_only_if_nonempty: false,
+        compare: None,
+        channels_file: None,
+        merge_output: None,
+        porcelain: false,
+        raw_seconds: false,
+        total_fd: None,
+        lang: yt_api_videosum::lang::Lang::En,
+        date_format: None,
+        timezone: None,
+        titles: yt_api_videosum::TitleOptions::default(),
+        count_only: false,
+        channel_stats: false,
+        print_playlist_id: false,
+        check_key: false,
+        buckets_path: None,
+        months_path: None,
+        fill_gaps: false,
+        cache_path: None,
+        cache_max_age: yt_api_videosum::cache::DEFAULT_MAX_AGE_DAYS,
+        since_last_run: false,
+        reset_state: false,
+        state_path: None,
+        playlists: Vec::new(),
+        yes: false,
+        max_quota: None,
+        max_retries_total: None,
+        user_agent: None,
+        min_videos: None,
+        verbose: 0,
+        by_hour: false,
+        captions_report: false,
+        captions_column: false,
+        quality_report: false,
+        definition_column: false,
+        immersive_report: false,
+        immersive_columns: false,
+        rate: false,
+        round: None,
+        equivalent: None,
+        local_time: false,
+        first_match: false,
+        verify_handle: false,
+        exclude_region_blocked: None,
+        strict_public: false,
+        title_filter: None,
+        resume_from: None,
+        diff_path: None,
+        skipped_path: None,
+        verify_output: false,
+        interactive: None,
+        color: yt_api_videosum::color::Choice::Auto,
+        explain: false,
+        explain_only: false,
+        with_stats: false,
+        thumbnail: None,
+        wizard: false,
+        given: Vec::new(),
+    };
+    let date = |v: Option<String>| match v {
+        Some(s) if !s.is_empty() => OptionalDate::Some(s),
+        _ => OptionalDate::Ask,
+    };
+
+    let mut p = ArgParser::new(args);
+    while let Some(arg) = p.next()? {
+        let name = match arg {
+            Arg::Opt(name) => name,
+            Arg::Positional(s) => match a.channel_name {
+                None => {
+                    a.channel_name = Some(s);
+                    continue;
+                }
+                Some(_) => return Err(Usage(format!("Unexpected argument '{}'!", s)).into()),
+            },
+        };
+
+        match name.as_str() {
+            "-K" | "--key-file" => a.key_file = Some(p.required("key file path")?),
+            "--key-profile" => a.key_profile = Some(p.required("key profile")?),
+            "--store-key" => a.store_key = true,
+            "-k" | "--key" => a.key = Some(p.required("API key")?),
+            "-s" | "--start" => a.start_date = date(p.value()),
+            "-e" | "--end" => a.end_date = date(p.value()),
+            "-o" | "--output" => match p.required("output path")? {
+                s if !s.is_empty() => {
+                    match a.output_given {
+                        true => a.extra_paths.push(s),
+                        false => a.output_path = s,
+                    }
+                    a.output_given = true;
+                }
+                _ => return Err(Usage::missing("output path").into()),
+            },
+            "--output-fd" => {
+                let s = p.required("file descriptor")?;
+                a.output_fd = Some(
+                    s.parse()
+                        .ok()
+                        .filter(|fd| *fd > 0)
+                        .ok_or(format!("Invalid file descriptor '{}'", s))?,
+                );
+            }
+            "--no-output" => a.no_output = true,
+            "--force" => a.output_mode = OutputMode::Force,
+            "--append" => a.output_mode = OutputMode::Append,
+            "--no-clobber" => a.no_clobber = true,
+            "--compress" => a.compress = true,
+            "--format" => a.format = Some(p.required("output format")?.parse()?),
+            "--template" => a.template = Some(p.required("template")?.parse()?),
+            "--footer" => a.footer = Some(true),
+            "--no-footer" => a.footer = Some(false),
+            "--sort" => a.sort = Some(p.required("sort key")?.parse()?),
+            "--desc" => a.sort_desc = true,
+            "--stream" => a.stream = true,
+            "--summary-only-if-nonempty" => a.summary_only_if_nonempty = true,
+            "--porcelain" => a.porcelain = true,
+            "--raw-seconds" => a.raw_seconds = true,
+            "--total-fd" => {
+                let s = p.required("file descriptor")?;
+                a.total_fd = Some(
+                    s.parse()
+                        .ok()
+                        .filter(|fd| *fd > 0)
+                        .ok_or(format!("Invalid file descriptor '{}'", s))?,
+                );
+            }
+            "--group-by-duration-bucket" => {
+                a.buckets_path = Some(p.required("bucket output path")?)
+            }
+            "--monthly-csv" => a.months_path = Some(p.required("monthly output path")?),
+            "--fill-gaps" => a.fill_gaps = true,
+            "--cache" => a.cache_path = Some(p.required("cache path")?),
+            "--cache-max-age" => {
+                let s = p.required("cache age")?;
+                a.cache_max_age = s
+                    .parse()
+                    .map_err(|e| format!("Invalid cache age '{}': {}", s, e))?;
+            }
+            "--since-last-run" => a.since_last_run = true,
+            "--reset-state" => a.reset_state = true,
+            "--state-file" => a.state_path = Some(p.required("state file path")?),
+            "--playlist" => a.playlists.extend(
+                p.required("playlist ID")?
+                    .split(',')
+                    .map(|p| String::from(p.trim()))
+                    .filter(|p| !p.is_empty()),
+            ),
+            "--yes" => a.yes = true,
+            "--by-hour" => a.by_hour = true,
+            "--captions-report" => a.captions_report = true,
+            "--captions-column" => a.captions_column = true,
+            "--quality-report" => a.quality_report = true,
+            "--definition-column" => a.definition_column = true,
+            "--immersive-report" => a.immersive_report = true,
+            "--rate" => a.rate = true,
+            "--round" => a.round = Some(p.required("rounding unit")?.parse()?),
+            "--immersive-columns" => a.immersive_columns = true,
+            "--equivalent" => {
+                a.equivalent
+                    .get_or_insert_with(yt_api_videosum::equivalent::defaults);
+            }
+            "--equivalent-units" => {
+                a.equivalent = Some(yt_api_videosum::equivalent::parse_references(
+                    &p.required("units")?,
+                )?)
+            }
+            "--local-time" => a.local_time = true,
+            "--first-match" => a.first_match = true,
+            "--verify-handle" => a.verify_handle = true,
+            "--verify-output" => a.verify_output = true,
+            "--exclude-region-blocked" => {
+                let s = p.required("region")?;
+                if s.len() != 2 || !s.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(format!(
+                        "Invalid region '{}', expected a two-letter country code like 'DE'",
+                        s
+                    )
+                    .into());
+                }
+                a.exclude_region_blocked = Some(s.to_ascii_uppercase());
+            }
+            "--strict-public" => a.strict_public = true,
+            "--resume-from" => a.resume_from = Some(p.required("resume CSV path")?),
+            "--diff-file" => a.diff_path = Some(p.required("diff CSV path")?),
+            "--skipped-report" => a.skipped_path = Some(p.required("skipped report path")?),
+            "--title-any" => a
+                .title_filter
+                .get_or_insert_with(Default::default)
+                .any
+                .extend(yt_api_videosum::TitleFilter::parse_terms(
+                    &p.required("title terms")?,
+                )?),
+            "--title-all" => a
+                .title_filter
+                .get_or_insert_with(Default::default)
+                .all
+                .extend(yt_api_videosum::TitleFilter::parse_terms(
+                    &p.required("title terms")?,
+                )?),
+            "--interactive" => a.interactive = Some(true),
+            "--non-interactive" => a.interactive = Some(false),
+            "--color" => a.color = p.required("color choice")?.parse()?,
+            "-v" | "--verbose" => a.verbose += 1,
+            "-vv" => a.verbose += 2,
+            "--max-quota" => {
+                let s = p.required("quota units")?;
+                a.max_quota = Some(
+                    s.parse()
+                        .map_err(|e| format!("Invalid quota units '{}': {}", s, e))?,
+                );
+            }
+            "--max-retries-total" => {
+                let s = p.required("retry count")?;
+                a.max_retries_total = Some(
+                    s.parse()
+                        .map_err(|e| format!("Invalid retry count '{}': {}", s, e))?,
+                );
+            }
+            "--user-agent" => {
+                let s = p.required("user agent")?;
+                //Not a valid header value otherwise
+                if s.trim().is_empty() || s.chars().any(|c| c.is_control()) {
+                    return Err(format!("Invalid user agent '{}'", s.escape_debug()).into());
+                }
+                a.user_agent = Some(s);
+            }
+            "--min-videos" => {
+                let s = p.required("minimum video count")?;
+                a.min_videos = Some(
+                    s.parse()
+                        .map_err(|e| format!("Invalid minimum video count '{}': {}", s, e))?,
+                );
+            }
+            "--explain" => a.explain = true,
+            "--explain-only" => a.explain_only = true,
+            "--count-only" => a.count_only = true,
+            "--channel-stats" => a.channel_stats = true,
+            "--print-playlist-id" => a.print_playlist_id = true,
+            "--check-key" => a.check_key = true,
+            "--with-stats" => a.with_stats = true,
+            "--with-thumbnail" => {
+                let quality =
+                    p.optional(|s| s.parse::<yt_api_videosum::ThumbnailQuality>().is_ok());
+                a.thumbnail = Some(match quality {
+                    Some(s) => s.parse()?,
+                    None => yt_api_videosum::ThumbnailQuality::High,
+                });
+            }
+            "-i" | "--wizard" => a.wizard = true,
+            "--strip-emoji" => a.titles.strip_emoji = true,
+            "--ascii-titles" => a.titles.ascii = true,
+            "--date-format" => a.date_format = Some(p.required("date format")?),
+            "--timezone" => a.timezone = Some(p.required("timezone")?),
+            "--lang" => a.lang = p.required("language")?.parse()?,
+            "--channels-file" => a.channels_file = Some(p.required("channels file path")?),
+            "--merge-output" => a.merge_output = Some(p.required("merged output path")?),
+            "--channels-compare" => match p.required("channel list")? {
+                s if !s.trim().is_empty() => {
+                    a.compare = Some(
+                        s.split(',')
+                            .filter(|c| !c.trim().is_empty())
+                            .map(|c| {
+                                parse_channel(c)
+                                    .map_err(|e| format!("Invalid channel '{}': {}", c.trim(), e))
+                            })
+                            .collect::<Result<_, _>>()?,
+                    );
+                }
+                _ => return Err(Usage::missing("channel list").into()),
+            },
+            _ => {
+                return Err(match suggest(&name) {
+                    Some(o) => Usage(format!("Unknown option '{}', did you mean '{}'?", name, o)),
+                    None => Usage(format!("Unknown option '{}'!", name)),
+                }
+                .into())
+            }
+        }
+    }
+
+    check_given(&p.given)?;
+    a.given = p.given;
+    Ok(a)
+}
+
+//Long names of the short options, so a repeated option is found in either form
+const SHORT_NAMES: &[(&str, &str)] = &[
+    ("-k", "--key"),
+    ("-K", "--key-file"),
+    ("-s", "--start"),
+    ("-e", "--end"),
+    ("-o", "--output"),
+    ("-v", "--verbose"),
+    ("-i", "--wizard"),
+];
+
+//Adding up, or collecting their values
+const REPEATABLE: &[&str] = &[
+    "--output",
+    "--verbose",
+    "--playlist",
+    "--title-any",
+    "--title-all",
+];
+
+//Separated by '|' in the usage, the ones with other effects are checked with the rest of the settings
+const ALTERNATIVES: &[(&str, &str)] = &[
+    ("--force", "--append"),
+    ("--footer", "--no-footer"),
+    ("--equivalent", "--equivalent-units"),
+    ("--interactive", "--non-interactive"),
+    ("--explain", "--explain-only"),
+    ("--verbose", "-vv"),
+];
+
+//Repeated or conflicting options, named as they were given
+fn check_given(given: &[(String, Option<String>)]) -> Result<(), Usage> {
+    let mut seen: Vec<(&str, &str)> = Vec::new();
+    for (name, _) in given {
+        let long = SHORT_NAMES
+            .iter()
+            .find(|(short, _)| short == name)
+            .map_or(name.as_str(), |(_, long)| long);
+        if let Some((_, first)) = seen.iter().find(|(l, _)| *l == long) {
+            if REPEATABLE.contains(&long) {
+                continue;
+            }
+            return Err(Usage(match first == name {
+                true => format!("Option '{}' is given more than once", name),
+                false => format!(
+                    "Option '{}' is given more than once, also as '{}'",
+                    first, name
+                ),
+            }));
+        }
+        for (a, b) in ALTERNATIVES {
+            let other = match long {
+                l if l == *a => b,
+                l if l == *b => a,
+                _ => continue,
+            };
+            if let Some((_, first)) = seen.iter().find(|(l, _)| l == other) {
+                return Err(Usage::conflict(first, name));
+            }
+        }
+        seen.push((long, name));
+    }
+    Ok(())
+}
+
+//Options answered without looking at any other argument, with the help of the command
+fn early_exit(args: &[String], help: &str) -> Option<String> {
+    let options = || args.iter().take_while(|e| *e != "--");
+    if options().any(|e| e == "-h" || e == "--help") {
+        Some(String::from(help))
+    } else if options().any(|e| e == "-V" || e == "--version") {
+        Some(format!(
+            "{} {}",
+            env!("CARGO_PKG_NAME"),
+            yt_api_videosum::version()
+        ))
+    } else {
+        None
+    }
+}
+
+//Of the first output, by the extension of its path if not given, with a warning if it is unknown
+fn output_format(
+    given: Option<yt_api_videosum::output::Format>,
+    path: Option<&str>,
+) -> (yt_api_videosum::output::Format, Option<String>) {
+    use yt_api_videosum::output::Format;
+    match (given, path) {
+        (Some(format), _) => (format, None),
+        (None, None) => (Format::Csv, None),
+        (None, Some(path)) => match Format::from_path(path) {
+            Some(format) => (format, None),
+            None => (
+                Format::Csv,
+                Some(format!(
+                    "Cannot infer the format of output '{}' from its extension, writing CSV",
+                    path
+                )),
+            ),
+        },
+    }
+}
+
+//Additional output as "path", or "format:path" to override the format inferred from the extension
+fn parse_target(
+    target: &str,
+) -> Result<(yt_api_videosum::output::Format, &str), Box<dyn std::error::Error>> {
+    if let Some((prefix, path)) = target.split_once(':') {
+        if let Ok(format) = prefix.parse() {
+            return Ok((format, path));
+        }
+    }
+    match yt_api_videosum::output::Format::from_path(target) {
+        Some(format) => Ok((format, target)),
+        None => Err(format!(
+            "Cannot infer the format of output '{}' from its extension, use e.g. 'csv:{}'",
+            target, target
+        )
+        .into()),
+    }
+}
+
+//With the default user agent if none is given
+fn http_client(user_agent: Option<&str>) -> Box<dyn yt_api_videosum::HttpClient> {
+    Box::new(yt_api_videosum::UreqClient {
+        user_agent: String::from(user_agent.unwrap_or(yt_api_videosum::USER_AGENT)),
+    })
+}
+
+//Logged if RUST_LOG is set, as the logger is only initialized then
+fn message_status(porcelain: bool) -> Status {
+    match (std::env::var_os("RUST_LOG").is_some(), porcelain) {
+        (true, stderr) => Status::Log { stderr },
+        (false, true) => Status::Stderr,
+        (false, false) => Status::Stdout,
+    }
+}
+
+//Each channel is run with the config of a single channel run given its name, without the outputs
+fn compare_channels(
+    channels: Vec<String>,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    porcelain: bool,
+    round: Option<yt_api_videosum::Rounding>,
+    merged: Option<(String, File)>,
+    base: impl Fn(String) -> yt_api_videosum::Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let status = message_status(porcelain);
+    let mut summaries = Vec::new();
+    let mut failed = Vec::new();
+    for channel_name in channels {
+        if INTERRUPTED.load(std::sync::atomic::Ordering::SeqCst) {
+            statusln!(
+                status,
+                "Warning: Skipping channel '@{}': Interrupted",
+                channel_name
+            );
+            failed.push(format!("@{}", channel_name));
+            continue;
+        }
+        statusln!(status, "Channel '@{}':", channel_name);
+        let config = base(channel_name.clone());
+        let min_videos = config.min_videos;
+        let result = yt_api_videosum::run_collect(config).and_then(|summary| {
+            yt_api_videosum::check_min_videos(&summary, min_videos)?;
+            Ok(summary)
+        });
+        match result {
+            Ok(summary) => summaries.push((format!("@{}", channel_name), summary)),
+            Err(e) => {
+                statusln!(
+                    status,
+                    "Warning: Skipping channel '@{}': {}",
+                    channel_name,
+                    e
+                );
+                failed.push(format!("@{}", channel_name));
+            }
+        }
+    }
+
+    statusln!(status);
+    let table = yt_api_videosum::report::compare_table(&summaries, round);
+    match porcelain {
+        true => {
+            status.print(format_args!("{}", table));
+            for (name, summary) in &summaries {
+                println!(
+                    "{}",
+                    yt_api_videosum::report::porcelain_line(
+                        name,
+                        Ok(summary),
+                        start_date,
+                        end_date
+                    )
+                );
+            }
+            for name in &failed {
+                println!(
+                    "{}",
+                    yt_api_videosum::report::porcelain_line(name, Err(()), start_date, end_date)
+                );
+            }
+        }
+        false => print!("{}", table),
+    }
+
+    if let Some((path, file)) = merged {
+        yt_api_videosum::output::write_merged(std::io::BufWriter::new(file), &summaries)?;
+        statusln!(status, "Merged output written to '{}'.", path);
+    }
+    let warnings = summaries
+        .iter()
+        .map(|(_, s)| s.warning_count())
+        .sum::<u32>();
+    match (summaries.is_empty(), failed.len() as u32 + warnings) {
+        (true, _) => Err("None of the channels could be queried".into()),
+        (false, 0) => Ok(()),
+        (false, n) => Err(yt_api_videosum::Partial(n).into()),
+    }
+}
+
+//Timezone of the dates without an offset
+enum Zone {
+    Named(chrono_tz::Tz),
+    Local,
+}
+
+//Displays the warning of a local time resolved around a daylight saving time transition
+fn parse_date(s: &str, bound: Bound, zone: &Zone, status: Status) -> Result<DateTime<Utc>, String> {
+    let now = Utc::now();
+    let parsed = match zone {
+        Zone::Named(tz) => yt_api_videosum::dates::parse_date(s, bound, now, tz)?,
+        Zone::Local => yt_api_videosum::dates::parse_date(s, bound, now, &Local)?,
+    };
+    if let Some(w) = parsed.warning {
+        statusln!(status, "Warning: {}", w);
+    }
+    Ok(parsed.date)
+}
+
+//Above this, fetching would take a noticeable part of the default daily quota of 10000 units
+const CONFIRM_VIDEOS: u64 = 500;
+
+//Aborting at the end of the input (e.g. Ctrl-D or a closed pipe), as for the other prompts
+fn confirm_estimate(
+    estimate: &yt_api_videosum::Estimate,
+    input: &mut impl BufRead,
+    status: Status,
+) -> bool {
+    if estimate.videos <= CONFIRM_VIDEOS {
+        return true;
+    }
+    loop {
+        statusln!(status.direct(), "{}", confirm_message(estimate));
+        let mut s = String::new();
+        if !matches!(input.read_line(&mut s), Ok(n) if n > 0) {
+            return false;
+        }
+        match s.trim() {
+            "" | "y" | "Y" => return true,
+            "n" | "N" => return false,
+            _ => continue,
+        }
+    }
+}
+
+fn confirm_message(estimate: &yt_api_videosum::Estimate) -> String {
+    format!(
+        "About to fetch details for {} videos (~{} quota units, {}). Continue? [Y/n]",
+        group_digits(estimate.videos),
+        group_digits(estimate.units),
+        expected_time(estimate.duration)
+    )
+}
+
+fn expected_time(duration: std::time::Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=89 => format!("~{} seconds", seconds),
+        _ => format!("~{} minutes", (seconds + 30) / 60),
+    }
+}
+
+//E.g. 1,862
+fn group_digits(n: u64) -> String {
+    let digits = n.to_string();
+    let mut s = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            s.push(',');
+        }
+        s.push(c);
+    }
+    s
+}
+
+//Everything needed to open the output files, once all other arguments are validated
+struct OutputSettings<'a> {
+    path: &'a str,
+    extra_paths: &'a [String],
+    buckets_path: Option<&'a str>,
+    months_path: Option<&'a str>,
+    skipped_path: Option<&'a str>,
+    fd: Option<i32>,
+    //Whether to write the output and the additional outputs
+    enabled: bool,
+    mode: OutputMode,
+    compress: bool,
+    channel_name: &'a str,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    interactive: bool,
+    status: Status,
+}
+
+struct Outputs {
+    output: Option<File>,
+    destination: yt_api_videosum::output::Destination,
+    pending: Option<yt_api_videosum::output::Pending>,
+    extra_outputs: Vec<yt_api_videosum::output::ExtraOutput>,
+    buckets_output: Option<File>,
+    months_output: Option<File>,
+    skipped_output: Option<(yt_api_videosum::output::Format, File)>,
+}
+
+//The targets are all validated before any file is touched, so an invalid one leaves everything as it was
+fn open_outputs(s: &OutputSettings) -> Result<Outputs, Box<dyn std::error::Error>> {
+    let targets = s
+        .extra_paths
+        .iter()
+        .filter(|_| s.enabled)
+        .map(|t| parse_target(t))
+        .collect::<Result<Vec<_>, _>>()?;
+    let compressed = (s.enabled && s.compress)
+        || targets
+            .iter()
+            .any(|(_, p)| yt_api_videosum::output::is_compressed_path(p));
+    if compressed && s.mode == OutputMode::Append {
+        return Err("Cannot append to a compressed output file".into());
+    }
+
+    let create = |path: &str| {
+        create_output(
+            path,
+            s.channel_name,
+            s.start_date,
+            s.end_date,
+            s.mode,
+            s.interactive,
+            s.status,
+        )
+    };
+
+    use yt_api_videosum::output::Destination;
+    let (output, pending, destination) = match (s.enabled, s.fd) {
+        (false, _) => (None, None, Destination::default()),
+        (true, Some(fd)) => (
+            Some(output_from_fd(fd)?),
+            None,
+            match fd {
+                1 => Destination::Stdout,
+                _ => Destination::Fd(fd),
+            },
+        ),
+        (true, None) => {
+            let (path, mode) = resolve_output(
+                s.path,
+                s.channel_name,
+                s.start_date,
+                s.end_date,
+                s.mode,
+                s.interactive,
+                s.status,
+            )?;
+            match mode {
+                OutputMode::Append => (
+                    Some(open_output(&path, mode)?),
+                    None,
+                    Destination::Path(path),
+                ),
+                _ => {
+                    let pending = yt_api_videosum::output::Pending::new(&path);
+                    let file = open_output(&pending.partial, OutputMode::Force)?;
+                    (Some(file), Some(pending), Destination::Path(path))
+                }
+            }
+        }
+    };
+
+    let mut extra_outputs = Vec::new();
+    for (format, path) in targets {
+        let (path, file) = create(path)?;
+        extra_outputs.push(yt_api_videosum::output::ExtraOutput { path, format, file });
+    }
+
+    let buckets_output = match s.buckets_path {
+        Some(path) => Some(create(path)?.1),
+        None => None,
+    };
+    let months_output = match s.months_path {
+        Some(path) => Some(create(path)?.1),
+        None => None,
+    };
+    let skipped_output = match s.skipped_path {
+        Some(path) => {
+            let (path, file) = create(path)?;
+            use yt_api_videosum::output::Format;
+            match Format::from_path(&path) {
+                Some(Format::Json) => Some((Format::Json, file)),
+                _ => Some((Format::Csv, file)),
+            }
+        }
+        None => None,
+    };
+
+    Ok(Outputs {
+        output,
+        destination,
+        pending,
+        extra_outputs,
+        buckets_output,
+        months_output,
+        skipped_output,
+    })
+}
+
+fn create_output(
+    template: &str,
+    channel_name: &str,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    mode: OutputMode,
+    interactive: bool,
+    status: Status,
+) -> Result<(String, File), Box<dyn std::error::Error>> {
+    let (path, mode) = resolve_output(
+        template,
+        channel_name,
+        start_date,
+        end_date,
+        mode,
+        interactive,
+        status,
+    )?;
+    let file = open_output(&path, mode)?;
+    Ok((path, file))
+}
+
+//Handle of a channel given with or without the '@', or as its URL
+fn parse_channel(s: &str) -> Result<String, String> {
+    let s = s.trim();
+    let handle = match s.find("youtube.com/") {
+        Some(i) => {
+            let path = &s[(i + "youtube.com/".len())..];
+            let path = &path[..path.find(['/', '?', '#']).unwrap_or(path.len())];
+            match path.starts_with('@') {
+                true => path,
+                false if s.contains("youtube.com/channel/") => {
+                    return Err(String::from(
+                        "Channel IDs are not supported, use the handle of the channel",
+                    ))
+                }
+                false => {
+                    return Err(String::from(
+                        "Expected a channel URL like 'https://www.youtube.com/@name'",
+                    ))
+                }
+            }
+        }
+        None => s,
+    };
+    let handle = handle.trim_start_matches('@');
+    if handle.is_empty() {
+        Err(String::from("Empty name supplied"))
+    } else if handle.contains(|c: char| c.is_whitespace() || c == '@' || c == ',') {
+        Err(String::from("Invalid character supplied"))
+    } else {
+        Ok(String::from(handle))
+    }
+}
+
+//Channels of '--channels-file' in order, each once, with the invalid lines described separately
+fn read_channels(reader: impl BufRead) -> std::io::Result<(Vec<String>, Vec<String>)> {
+    let mut channels = Vec::<String>::new();
+    let mut invalid = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let entry = line.split('#').next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        match parse_channel(entry) {
+            Ok(c) if channels.contains(&c) => invalid.push(format!(
+                "line {}: Channel '@{}' is already listed",
+                i + 1,
+                c
+            )),
+            Ok(c) => channels.push(c),
+            Err(e) => invalid.push(format!(
+                "line {}: Invalid channel '{}': {}",
+                i + 1,
+                entry,
+                e
+            )),
+        }
+    }
+    Ok((channels, invalid))
+}
+
+//Asked in interactive mode, listing them all
+fn channel_pick(interactive: bool, status: Status) -> Option<yt_api_videosum::Pick> {
+    match interactive {
+        true => Some(Box::new(move |candidates| {
+            pick_channel(candidates, &mut std::io::stdin().lock(), status)
+        })),
+        false => None,
+    }
+}
+
+//Index of the one chosen, or None to abort, also at the end of the input
+fn pick_channel(
+    candidates: &[yt_api_videosum::Candidate],
+    input: &mut impl BufRead,
+    status: Status,
+) -> Option<usize> {
+    statusln!(status.direct(), "More than one channel found:");
+    for (i, c) in candidates.iter().enumerate() {
+        statusln!(status.direct(), "  {}. {}", i + 1, c);
+    }
+    loop {
+        statusln!(
+            status.direct(),
+            "Pick one [1-{}], or leave it empty to abort:",
+            candidates.len()
+        );
+        let mut s = String::new();
+        if input.read_line(&mut s).ok()? == 0 {
+            return None;
+        }
+        match s.trim() {
+            "" => return None,
+            t => match t.parse::<usize>() {
+                Ok(n) if (1..=candidates.len()).contains(&n) => return Some(n - 1),
+                _ => statusln!(status, "Warning: Invalid choice '{}'", t),
+            },
+        }
+    }
+}
+
+//Line typed in answer to the question, or an error naming the missing input if there is no one to ask
+//An answer making the range invalid is asked again, the other messages wait for both dates
+fn check_answer(start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> Result<(), String> {
+    yt_api_videosum::dates::check_range(start, end, Utc::now()).map(|_| ())
+}
+
+fn ask(
+    question: &str,
+    missing: &str,
+    interactive: bool,
+    input: &mut impl BufRead,
+    status: Status,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !interactive {
+        return Err(Usage(format!("{} (not asked in non-interactive mode)", missing)).into());
+    }
+    statusln!(status.direct(), "{}", question);
+    let mut s = String::new();
+    //Ctrl-D, or the end of a piped input
+    if input.read_line(&mut s)? == 0 {
+        return Err(format!("{} (end of input reached while asking)", missing).into());
+    }
+    Ok(s)
+}
+
+//Asked again until a valid name is given
+fn ask_channel(
+    interactive: bool,
+    input: &mut impl BufRead,
+    status: Status,
+) -> Result<String, Box<dyn std::error::Error>> {
+    loop {
+        let name = ask(
+            "Channel name:",
+            "Missing channel name, give it as the 'channel_name' argument",
+            interactive,
+            input,
+            status,
+        )?;
+        match parse_channel(&name) {
+            Ok(_) if !name.is_ascii() => {
+                statusln!(status, "Warning: Invalid character supplied!")
+            }
+            Ok(name) => return Ok(name),
+            Err(e) => statusln!(status, "Warning: {}!", e),
+        }
+    }
+}
+
+//None for no limit, if the answer is empty or 'none'; checked against the other bound if it is given
+fn ask_date(
+    bound: Bound,
+    other: Option<DateTime<Utc>>,
+    zone: &Zone,
+    interactive: bool,
+    input: &mut impl BufRead,
+    status: Status,
+) -> Result<Option<DateTime<Utc>>, Box<dyn std::error::Error>> {
+    let (question, missing) = match bound {
+        Bound::Start => (
+            "Filter to dates starting from (empty or 'none' for no limit):",
+            "Missing start date, give it as the value of '-s'",
+        ),
+        Bound::End => (
+            "Filter to dates ending at (empty or 'none' for no limit):",
+            "Missing end date, give it as the value of '-e'",
+        ),
+    };
+    loop {
+        let s = ask(question, missing, interactive, input, status)?;
+        let s = s.trim();
+        if s.is_empty() || s.eq_ignore_ascii_case("none") {
+            return Ok(None);
+        }
+        match parse_date(s, bound, zone, status) {
+            Ok(d) => {
+                let checked = match bound {
+                    Bound::Start => check_answer(Some(d), other),
+                    Bound::End => check_answer(other, Some(d)),
+                };
+                match checked {
+                    Ok(()) => return Ok(Some(d)),
+                    Err(e) => statusln!(status, "Warning: {}", e),
+                }
+            }
+            Err(e) => statusln!(status, "Warning: Could not parse timestamp '{}': {}", s, e),
+        }
+    }
+}
+
+//Expanded path, with the mode to open it once an existing file is confirmed to be replaced or appended
+fn resolve_output(
+    template: &str,
+    channel_name: &str,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    mut mode: OutputMode,
+    interactive: bool,
+    status: Status,
+) -> Result<(String, OutputMode), Box<dyn std::error::Error>> {
+    let path = yt_api_videosum::output::expand_path(
+        template,
+        channel_name,
+        Utc::now(),
+        start_date,
+        end_date,
+    )?;
+
+    if mode == OutputMode::NoClobber && std::fs::metadata(&path).is_ok() {
+        return Err(format!(
+            "Output file '{}' already exists, and '--no-clobber' is given (use '--force' instead to overwrite it)",
+            path
+        )
+        .into());
+    }
+    let exists = std::fs::metadata(&path).is_ok_and(|m| m.len() > 0);
+    if exists && mode == OutputMode::Protect {
+        if !interactive {
+            return Err(format!(
+                "Output file '{}' already exists, use '--force' to overwrite or '--append' to append to it",
+                path
+            )
+            .into());
+        }
+        loop {
+            statusln!(
+                status,
+                "Output file '{}' already exists. Overwrite, append or abort? [o/a/N]",
+                path
+            );
+            let mut s = String::new();
+            std::io::stdin().read_line(&mut s)?;
+            match s.trim() {
+                "o" | "O" => mode = OutputMode::Force,
+                "a" | "A" => mode = OutputMode::Append,
+                "" | "n" | "N" => return Err("Aborted, output file left untouched".into()),
+                _ => continue,
+            }
+            break;
+        }
+    }
+    Ok((path, mode))
+}
+
+#[cfg(unix)]
+fn output_from_fd(fd: i32) -> Result<File, Box<dyn std::error::Error>> {
+    use std::os::fd::{BorrowedFd, FromRawFd};
+
+    //The standard streams are still used by the rest of the program, so only a duplicate of them is closed
+    if fd <= 2 {
+        //Safety: the standard streams are kept open for the whole run, only borrowed to duplicate them
+        let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+        return borrowed
+            .try_clone_to_owned()
+            .map(File::from)
+            .map_err(|e| format!("Could not use file descriptor {} as output: {}", fd, e).into());
+    }
+    //Safety: the descriptor is handed over by the caller (typically the shell) for the program to own
+    let file = unsafe { File::from_raw_fd(fd) };
+    match file.metadata() {
+        Ok(_) => Ok(file),
+        Err(e) => {
+            //Not open, so there is nothing to close
+            std::mem::forget(file);
+            Err(format!("Could not use file descriptor {} as output: {}", fd, e).into())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn output_from_fd(_fd: i32) -> Result<File, Box<dyn std::error::Error>> {
+    Err("The '--output-fd' option is only supported on Unix".into())
+}
+
+fn open_output(path: &str, mode: OutputMode) -> Result<File, Box<dyn std::error::Error>> {
+    //Readable too, for '--verify-output'
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true).write(true);
+    match mode {
+        //Also if it was created since the check
+        OutputMode::NoClobber => options.create_new(true),
+        _ => options.create(true).truncate(mode != OutputMode::Append),
+    };
+    let file = options.open(path);
+    match file {
+        Ok(mut f) => {
+            f.seek(std::io::SeekFrom::End(0))?;
+            Ok(f)
+        }
+        Err(e) => Err(format!("Could not create output file '{}': {}", path, e))?,
+    }
+}
+
+#[cfg(test)]
+mod main_test {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn parse_date_test() {
+        let zone = Zone::Named(chrono_tz::Tz::UTC);
+        let date = |s| {
+            Ok(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let tests = [
+            ("2024-01-01T00:00:00Z", date("2024-01-01T00:00:00Z")),
+            ("2024-01-01T02:00:00+02:00", date("2024-01-01T00:00:00Z")),
+            ("@1704067200", date("2024-01-01T00:00:00Z")),
+            ("@0", date("1970-01-01T00:00:00Z")),
+            ("@-86400", date("1969-12-31T00:00:00Z")),
+            ("@", Err(())),
+            ("@1.5", Err(())),
+            ("@ 1", Err(())),
+            ("@abc", Err(())),
+            ("@99999999999999999", Err(())),
+            ("@99999999999999999999", Err(())),
+            ("1704067200", Err(())),
+            ("2024-01-01", date("2024-01-01T00:00:00Z")),
+            ("2024-01-01 00:00", date("2024-01-01T00:00:00Z")),
+            ("2024-01-01 25:00", Err(())),
+        ];
+
+        for (s, r) in tests {
+            assert_eq!(
+                parse_date(s, Bound::Start, &zone, Status::Stderr).map_err(|_| ()),
+                r,
+                "input=\"{}\"",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn explain_test() {
+        let key = "AIzaSyA-secret-key";
+        let entries = [
+            ("key", redact(key), "file 'config/key.txt'"),
+            ("channel", String::from("@foo"), "command line"),
+            ("start", String::new(), "default"),
+        ];
+        let table = explain_table(&entries);
+
+        assert!(!table.contains("secret"));
+        assert_eq!(
+            table,
+            "Resolved configuration:\n\
+            \x20 key      <redacted, 18 characters>  (file 'config/key.txt')\n\
+            \x20 channel  @foo                       (command line)\n\
+            \x20 start    -                          (default)\n"
+        );
+    }
+
+    #[test]
+    fn parse_args_test() {
+        use OptionalDate::{Ask, None as Unset};
+        let some = |s: &str| Some(String::from(s));
+        let date = |s: &str| OptionalDate::Some(String::from(s));
+        let out = yt_api_videosum::output::DEFAULT_PATH;
+        let tests = [
+            //Forms accepted before the long names
+            (vec!["foo"], Ok((None, Unset, Unset, some("foo"), out))),
+            (
+                vec![
+                    "-k",
+                    "KEY",
+                    "-s",
+                    "2024-01-01T00:00:00Z",
+                    "-e",
+                    "-o",
+                    "a.csv",
+                    "foo",
+                ],
+                Ok((
+                    some("KEY"),
+                    date("2024-01-01T00:00:00Z"),
+                    Ask,
+                    some("foo"),
+                    "a.csv",
+                )),
+            ),
+            (vec!["-s", "", "-e", ""], Ok((None, Ask, Ask, None, out))),
+            (
+                vec!["-k", "", "-s", "@1704067200", "foo"],
+                Ok((some(""), date("@1704067200"), Unset, some("foo"), out)),
+            ),
+            (
+                vec!["--force", "--sort", "date", "--desc", "foo"],
+                Ok((None, Unset, Unset, some("foo"), out)),
+            ),
+            //Long names and inline values
+            (
+                vec![
+                    "--key",
+                    "KEY",
+                    "--start=2024-01-01T00:00:00Z",
+                    "--end=",
+                    "--output=a=b.csv",
+                ],
+                Ok((
+                    some("KEY"),
+                    date("2024-01-01T00:00:00Z"),
+                    Ask,
+                    None,
+                    "a=b.csv",
+                )),
+            ),
+            (
+                vec!["--key=-dash", "--end", "@0"],
+                Ok((some("-dash"), Unset, date("@0"), None, out)),
+            ),
+            (vec!["-k=KEY"], Err("Unknown option '-k=KEY'!")),
+            //Channel anywhere, or after '--'
+            (
+                vec!["foo", "-k", "KEY"],
+                Ok((some("KEY"), Unset, Unset, some("foo"), out)),
+            ),
+            (
+                vec!["-s", "-k", "KEY", "foo", "-o", "a.csv"],
+                Ok((some("KEY"), Ask, Unset, some("foo"), "a.csv")),
+            ),
+            (
+                vec!["-k", "KEY", "--", "-foo"],
+                Ok((some("KEY"), Unset, Unset, some("-foo"), out)),
+            ),
+            //Values taken whatever they start with, besides the dates that may be left out
+            (
+                vec!["-k", "-KEY", "-o", "-out.csv", "foo"],
+                Ok((some("-KEY"), Unset, Unset, some("foo"), "-out.csv")),
+            ),
+            (
+                vec!["-k", "-s", "-s", "--end=-1704067200", "@-minus"],
+                Ok((some("-s"), Ask, date("-1704067200"), some("@-minus"), out)),
+            ),
+            (
+                vec!["--", "--help"],
+                Ok((None, Unset, Unset, some("--help"), out)),
+            ),
+            //Mistakes
+            (vec!["foo", "bar"], Err("Unexpected argument 'bar'!")),
+            (
+                vec!["--formt", "csv"],
+                Err("Unknown option '--formt', did you mean '--format'?"),
+            ),
+            (
+                vec!["--explainonly"],
+                Err("Unknown option '--explainonly', did you mean '--explain-only'?"),
+            ),
+            (vec!["--frobnicate"], Err("Unknown option '--frobnicate'!")),
+            (vec!["-x"], Err("Unknown option '-x'!")),
+            (
+                vec!["--force=yes"],
+                Err("Option '--force' does not take a value!"),
+            ),
+            (vec!["-o"], Err("Missing output path!")),
+            (vec!["--output="], Err("Missing output path!")),
+            (vec!["--sort"], Err("Missing sort key!")),
+            (
+                vec!["-k", "-s", "@1704067200", "foo"],
+                Err("Unexpected argument 'foo'!"),
+            ),
+            (vec!["foo", "-k"], Err("Missing API key!")),
+            (vec!["-minusbrand"], Err("Unknown option '-minusbrand'!")),
+        ];
+
+        for (argv, r) in tests {
+            let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+            let parsed = parse_args(&argv)
+                .map(|a| {
+                    (
+                        a.key,
+                        a.start_date,
+                        a.end_date,
+                        a.channel_name,
+                        a.output_path,
+                    )
+                })
+                .map_err(|e| {
+                    assert!(e.is::<Usage>(), "{:?}", argv);
+                    e.to_string()
+                });
+            let r = r
+                .map(|(k, s, e, c, o)| (k, s, e, c, String::from(o)))
+                .map_err(String::from);
+            assert_eq!(parsed, r, "{:?}", argv);
+        }
+
+        let argv: Vec<String> = ["--format=json", "foo", "--cache-max-age=-1", "--force"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let a = parse_args(&argv).unwrap();
+        assert_eq!(a.cache_max_age, -1);
+        assert_eq!(a.thumbnail, None);
+
+        //The quality may be left out, without taking the channel in its place
+        use yt_api_videosum::ThumbnailQuality;
+        let tests = [
+            (
+                vec!["--with-thumbnail", "foo"],
+                Some(ThumbnailQuality::High),
+            ),
+            (
+                vec!["--with-thumbnail", "maxres", "foo"],
+                Some(ThumbnailQuality::Maxres),
+            ),
+            (
+                vec!["foo", "--with-thumbnail=medium"],
+                Some(ThumbnailQuality::Medium),
+            ),
+            (
+                vec!["foo", "--with-thumbnail"],
+                Some(ThumbnailQuality::High),
+            ),
+        ];
+        for (argv, thumbnail) in tests {
+            let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+            let a = parse_args(&argv).unwrap();
+            assert_eq!(a.thumbnail, thumbnail, "{:?}", argv);
+            assert_eq!(a.channel_name.as_deref(), Some("foo"), "{:?}", argv);
+        }
+        assert_eq!(
+            a.given,
+            [
+                (String::from("--format"), some("json")),
+                (String::from("--cache-max-age"), some("-1")),
+                (String::from("--force"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_test() {
+        let tests = [
+            (
+                vec!["-s", "2024", "-s", "2025", "foo"],
+                Err("Option '-s' is given more than once"),
+            ),
+            (
+                vec!["--start=2024", "-s", "foo"],
+                Err("Option '--start' is given more than once, also as '-s'"),
+            ),
+            (vec!["-k", "A", "--key", "B"], Err("also as '--key'")),
+            (
+                vec!["--sort", "date", "--sort=title"],
+                Err("'--sort' is given more"),
+            ),
+            (
+                vec!["-vv", "-vv"],
+                Err("Option '-vv' is given more than once"),
+            ),
+            (
+                vec!["--force", "foo", "--append"],
+                Err("The '--force' and '--append' options cannot be used together"),
+            ),
+            (
+                vec!["--no-footer", "--footer"],
+                Err("'--no-footer' and '--footer'"),
+            ),
+            (
+                vec!["--equivalent-units", "h=1", "--equivalent"],
+                Err("'--equivalent-units' and '--equivalent'"),
+            ),
+            (
+                vec!["--interactive", "--non-interactive"],
+                Err("'--interactive' and '--non-interactive'"),
+            ),
+            (
+                vec!["--explain-only", "--explain"],
+                Err("'--explain-only' and '--explain'"),
+            ),
+            (vec!["--verbose", "-vv"], Err("'--verbose' and '-vv'")),
+            //Adding up
+            (vec!["-v", "--verbose", "-v"], Ok(())),
+            (vec!["-o", "a.csv", "--output", "b.json"], Ok(())),
+            (vec!["--playlist", "PL1", "--playlist=PL2"], Ok(())),
+            (
+                vec!["--title-any", "a", "--title-any", "b", "--title-all", "c"],
+                Ok(()),
+            ),
+            (vec!["--append", "--no-clobber", "-s", "-e"], Ok(())),
+            //Only options are counted
+            (vec!["--", "-s", "-s"], Err("Unexpected argument '-s'!")),
+        ];
+        for (argv, expected) in tests {
+            let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+            match (parse_args(&argv), expected) {
+                (Ok(_), Ok(())) => (),
+                (Err(e), Err(msg)) => assert!(e.to_string().contains(msg), "{:?}: {}", argv, e),
+                (Ok(_), Err(msg)) => panic!("{:?}: parsed, expected {:?}", argv, msg),
+                (Err(e), Ok(())) => panic!("{:?}: {}", argv, e),
+            }
+        }
+    }
+
+    #[test]
+    fn base_config_test() {
+        let config = |argv: &[&str]| {
+            let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+            let a = parse_args(&argv).unwrap();
+            check_conflicts(&a).unwrap();
+            let mut r = resolve_options(&a, Status::Stderr).unwrap();
+            r.key = String::from("KEY");
+            base_config(&a, &r, String::from("foo"))
+        };
+
+        //The settings of a single channel run, for each channel compared
+        let c = config(&[
+            "--channels-compare",
+            "foo,bar",
+            "--non-interactive",
+            "--strict-public",
+            "--title-any",
+            "ep",
+            "--exclude-region-blocked",
+            "DE",
+            "--playlist",
+            "PLx",
+            "--first-match",
+            "--cache",
+            "cache.json",
+            "--max-quota",
+            "100",
+            "--min-videos",
+            "2",
+            "-s",
+            "2024-01-01",
+        ]);
+        assert_eq!(c.channel_name, "foo");
+        assert!(c.strict_public);
+        assert_eq!(c.title_filter.unwrap().any, ["ep"]);
+        assert_eq!(c.exclude_region_blocked.as_deref(), Some("DE"));
+        assert_eq!(c.playlists, ["PLx"]);
+        assert!(c.first_match);
+        assert!(c.cache.is_some());
+        assert_eq!(c.max_quota, Some(100));
+        assert_eq!(c.min_videos, Some(2));
+        assert_eq!(
+            c.start_date.map(|d| d.to_rfc3339()).as_deref(),
+            Some("2024-01-01T00:00:00+00:00")
+        );
+        assert!(c.confirm.is_none());
+
+        let c = config(&["--channels-compare", "foo,bar", "--interactive"]);
+        assert!(c.confirm.is_some());
+        assert!(c.title_filter.is_none());
+        let c = config(&["--channels-compare", "foo,bar", "--interactive", "--yes"]);
+        assert!(c.confirm.is_none());
+    }
+
+    #[test]
+    fn channels_file_config_test() {
+        let path = std::env::temp_dir().join(format!(
+            "yt_api_videosum_channels_config_{}",
+            std::process::id()
+        ));
+        std::fs::write(&path, "# Subscriptions\n@foo\n\nbar # second\n").unwrap();
+        let argv = |extra: &[&str]| {
+            let mut argv = vec!["-k", "KEY", "--channels-file", path.to_str().unwrap()];
+            argv.extend_from_slice(extra);
+            argv.iter().map(|s| s.to_string()).collect::<Vec<_>>()
+        };
+
+        let mut a = parse_args(&argv(&[
+            "--non-interactive",
+            "--title-all",
+            "tutorial",
+            "--strict-public",
+        ]))
+        .unwrap();
+        check_conflicts(&a).unwrap();
+        a.compare =
+            Some(read_channels_file(a.channels_file.as_ref().unwrap(), Status::Stderr).unwrap());
+        let r = resolve_options(&a, Status::Stderr).unwrap();
+        let channels = a.compare.clone().unwrap();
+        assert_eq!(channels, ["foo", "bar"]);
+        //The subtotal of each channel is filtered the same way
+        for channel in channels {
+            let c = base_config(&a, &r, channel.clone());
+            assert_eq!(c.channel_name, channel);
+            assert_eq!(c.title_filter.unwrap().all, ["tutorial"]);
+            assert!(c.strict_public);
+        }
+
+        let e = run(&argv(&["-o", "out.csv"])).unwrap_err();
+        assert!(e.is::<Usage>());
+        assert_eq!(
+            e.to_string(),
+            "The '-o' and '--channels-file' options cannot be used together"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn total_fd_test() {
+        let tests = [
+            (vec!["--raw-seconds", "--total-fd", "3"], Ok(Some(3))),
+            (vec!["--raw-seconds", "--total-fd=1"], Ok(Some(1))),
+            (vec!["--raw-seconds"], Ok(None)),
+            (vec!["--total-fd", "0"], Err("Invalid file descriptor '0'")),
+            (
+                vec!["--total-fd", "out"],
+                Err("Invalid file descriptor 'out'"),
+            ),
+            (vec!["--total-fd"], Err("Missing file descriptor!")),
+        ];
+        for (argv, r) in tests {
+            let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+            let parsed = parse_args(&argv)
+                .map(|a| a.total_fd)
+                .map_err(|e| e.to_string());
+            assert_eq!(parsed, r.map_err(String::from), "{:?}", argv);
+        }
+
+        //Only the total on its stream, nothing on stdout
+        let (mut total, mut stdout) = (Vec::new(), Vec::new());
+        write_result(Some(&mut total), &mut stdout, Some(4231), "status=ok").unwrap();
+        assert_eq!(
+            (total.as_slice(), stdout.as_slice()),
+            (&b"4231\n"[..], &b""[..])
+        );
+        let (mut total, mut stdout) = (Vec::new(), Vec::new());
+        write_result(Some(&mut total), &mut stdout, None, "status=error").unwrap();
+        assert_eq!((total.as_slice(), stdout.as_slice()), (&b""[..], &b""[..]));
+        let mut stdout = Vec::new();
+        write_result(None, &mut stdout, Some(4231), "status=ok").unwrap();
+        assert_eq!(stdout, b"status=ok\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_from_fd_test() {
+        use std::io::Read;
+        use std::os::fd::IntoRawFd;
+
+        let (mut reader, writer) = std::io::pipe().unwrap();
+        let mut out = output_from_fd(writer.into_raw_fd()).unwrap();
+        write_result(Some(&mut out), &mut std::io::sink(), Some(60), "").unwrap();
+        drop(out);
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "60\n");
+
+        //A standard stream is duplicated, so it stays open once the output is dropped
+        drop(output_from_fd(2).unwrap());
+        assert!(output_from_fd(2).unwrap().metadata().is_ok());
+    }
+
+    #[test]
+    fn confirm_estimate_test() {
+        let latency = std::time::Duration::from_millis(100);
+        let tests = [
+            (501, "", false),
+            (501, "\n", true),
+            (501, "y\n", true),
+            (501, "N\n", false),
+            (501, "maybe\nY\n", true),
+            (501, "maybe\n", false),
+            (500, "", true),
+        ];
+        for (videos, input, confirmed) in tests {
+            let estimate = yt_api_videosum::estimate(videos, 3, latency);
+            assert_eq!(
+                confirm_estimate(&estimate, &mut input.as_bytes(), Status::Stderr),
+                confirmed,
+                "videos={} input={:?}",
+                videos,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn confirm_message_test() {
+        let tests = [
+            (1862, 150, "About to fetch details for 1,862 videos (~1,862 quota units, ~5 minutes). Continue? [Y/n]"),
+            (501, 100, "About to fetch details for 501 videos (~501 quota units, ~50 seconds). Continue? [Y/n]"),
+            (1234567, 0, "About to fetch details for 1,234,567 videos (~1,234,567 quota units, ~0 seconds). Continue? [Y/n]"),
+        ];
+        for (videos, latency, message) in tests {
+            let estimate =
+                yt_api_videosum::estimate(videos, 3, std::time::Duration::from_millis(latency));
+            assert_eq!(confirm_message(&estimate), message);
+        }
+
+        let estimate = yt_api_videosum::estimate(1862, 38, std::time::Duration::from_millis(150));
+        assert_eq!(
+            estimate_message(&estimate),
+            "Videos to fetch: 1,862\n\
+            Quota cost: ~1,900 units (38 spent on the channel and the playlists, ~1,862 for the videos)\n\
+            Expected time: ~5 minutes"
+        );
+    }
+
+    #[test]
+    fn wizard_command_test() {
+        let args = |a: &[&str]| a.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+        let asked = args(&["--start=30d", "--output=my report.csv", "@foo"]);
+
+        let tests = [
+            (
+                args(&["-i"]),
+                "yt_api_videosum --start=30d '--output=my report.csv' @foo",
+            ),
+            (
+                args(&["-k", "KEY", "--wizard", "--yes", "-v"]),
+                "yt_api_videosum --yes -v --start=30d '--output=my report.csv' @foo",
+            ),
+            (
+                args(&["--key=KEY", "-i", "-K", "key.txt", "-k", "--lang", "hu"]),
+                "yt_api_videosum -K key.txt --lang hu --start=30d '--output=my report.csv' @foo",
+            ),
+        ];
+        for (a, command) in tests {
+            assert_eq!(wizard_command(&a, &asked), command, "args={:?}", a);
+        }
+        assert_eq!(
+            without_wizard(&args(&["-k", "KEY", "-i", "--yes"]), false),
+            args(&["-k", "KEY", "--yes"])
+        );
+    }
+
+    #[test]
+    fn command_test() {
+        let args = |a: &[&str]| a.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+
+        let tests = [
+            (
+                args(&["stats", "out.csv"]),
+                Command::Stats,
+                args(&["out.csv"]),
+            ),
+            (
+                args(&["resolve", "-k", "KEY", "foo"]),
+                Command::Resolve,
+                args(&["-k", "KEY", "foo"]),
+            ),
+            (args(&["estimate"]), Command::Estimate, args(&[])),
+            (args(&["run", "stats"]), Command::Run, args(&["stats"])),
+            (args(&["@stats"]), Command::Run, args(&["@stats"])),
+            (
+                args(&["-k", "KEY", "stats"]),
+                Command::Run,
+                args(&["-k", "KEY", "stats"]),
+            ),
+            (args(&[]), Command::Run, args(&[])),
+        ];
+        for (a, command, rest) in tests {
+            assert_eq!(
+                split_command(&a),
+                (command, rest.as_slice()),
+                "args={:?}",
+                a
+            );
+        }
+
+        let tests = [
+            (
+                Command::Resolve,
+                args(&["-k", "KEY", "--first-match", "foo"]),
+                None,
+            ),
+            (
+                Command::Resolve,
+                args(&["-k", "KEY", "-o", "out.csv", "foo"]),
+                Some("-o"),
+            ),
+            (Command::Stats, args(&["--lang=hu", "out.csv"]), None),
+            (Command::Stats, args(&["-k", "KEY", "out.csv"]), Some("-k")),
+            (
+                Command::Estimate,
+                args(&["-s", "2024-01", "--playlist", "PL1", "foo"]),
+                None,
+            ),
+            (Command::Estimate, args(&["--yes", "foo"]), Some("--yes")),
+            (Command::Run, args(&["-o", "out.csv", "--yes", "foo"]), None),
+        ];
+        for (command, a, rejected) in tests {
+            let result = parse_command_args(command, &a);
+            match rejected {
+                None => assert!(result.is_ok(), "{:?} args={:?}", command, a),
+                Some(o) => {
+                    let e = result.err().unwrap();
+                    assert!(e.is::<Usage>());
+                    assert_eq!(
+                        e.to_string(),
+                        format!("Option '{}' is not accepted by '{}'", o, command.name())
+                    );
+                }
+            }
+        }
+
+        //Before any request or key lookup
+        for a in [
+            args(&["resolve", "-k", "KEY"]),
+            args(&["stats"]),
+            args(&["estimate", "-k", "KEY", "-s", "foo"]),
+            args(&["estimate", "-k", "KEY", "-e", "foo"]),
+            args(&["stats", "--count-only", "out.csv"]),
+        ] {
+            assert_eq!(exit_code(&dispatch(&a)), EXIT_USAGE, "{:?}", a);
+        }
+    }
+
+    #[test]
+    fn parse_channel_test() {
+        let tests = [
+            ("foo", Ok("foo")),
+            ("  @foo\n", Ok("foo")),
+            ("https://www.youtube.com/@foo", Ok("foo")),
+            ("https://youtube.com/@foo/videos?x=1", Ok("foo")),
+            ("youtube.com/@foo#about", Ok("foo")),
+            ("", Err("Empty name supplied")),
+            ("@", Err("Empty name supplied")),
+            ("foo bar", Err("Invalid character supplied")),
+            ("a,b", Err("Invalid character supplied")),
+            (
+                "https://www.youtube.com/channel/UCxyz",
+                Err("Channel IDs are not supported, use the handle of the channel"),
+            ),
+            (
+                "https://www.youtube.com/watch?v=abc",
+                Err("Expected a channel URL like 'https://www.youtube.com/@name'"),
+            ),
+        ];
+
+        for (s, r) in tests {
+            assert_eq!(
+                parse_channel(s).as_deref(),
+                r.map_err(String::from).as_deref(),
+                "s={:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn read_channels_test() {
+        let file = "\
+            # My subscriptions\n\
+            \n\
+            @first\n\
+            \x20 second   # trailing comment\n\
+            https://www.youtube.com/@third/videos\n\
+            not valid\n\
+            \t\n\
+            @first\n\
+            https://www.youtube.com/channel/UCxyz\n\
+            fourth";
+        let (channels, invalid) = read_channels(file.as_bytes()).unwrap();
+
+        assert_eq!(channels, ["first", "second", "third", "fourth"]);
+        assert_eq!(invalid.len(), 3);
+        assert!(invalid[0].starts_with("line 6: Invalid channel 'not valid': "));
+        assert_eq!(invalid[1], "line 8: Channel '@first' is already listed");
+        assert!(invalid[2]
+            .starts_with("line 9: Invalid channel 'https://www.youtube.com/channel/UCxyz': "));
+
+        assert_eq!(read_channels(&b""[..]).unwrap(), (vec![], vec![]));
+    }
+
+    #[test]
+    fn ask_test() {
+        let missing = "Missing start date, give it as the value of '-s'";
+        let e = ask("Start:", missing, false, &mut &b""[..], Status::Stderr).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Missing start date, give it as the value of '-s' (not asked in non-interactive mode)"
+        );
+        //Never read, so a waiting stdin would not block either
+        let mut input = &b"2024-01-01\n"[..];
+        assert!(ask("Start:", missing, false, &mut input, Status::Stderr).is_err());
+        assert_eq!(input.len(), 11);
+
+        let answer = ask("Start:", missing, true, &mut input, Status::Stderr).unwrap();
+        assert_eq!(answer, "2024-01-01\n");
+        let e = ask("Start:", missing, true, &mut input, Status::Stderr).unwrap_err();
+        assert!(e
+            .to_string()
+            .ends_with("(end of input reached while asking)"));
+    }
+
+    #[test]
+    fn pick_channel_test() {
+        let candidates: Vec<yt_api_videosum::Candidate> = ["abc", "def", "ghi"]
+            .iter()
+            .map(|id| yt_api_videosum::Candidate {
+                id: format!("UC{}", id),
+                title: id.to_uppercase(),
+                custom_url: Some(format!("@{}", id)),
+            })
+            .collect();
+        let tests = [
+            ("1\n", Some(0)),
+            (" 3 \n", Some(2)),
+            ("0\n4\nabc\n2\n", Some(1)),
+            ("\n", None),
+            ("5\n", None),
+            ("", None),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(
+                pick_channel(&candidates, &mut input.as_bytes(), Status::Stderr),
+                expected,
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn ask_date_test() {
+        let utc = Zone::Named(chrono_tz::Tz::UTC);
+        let date = |s: &str| {
+            Some(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let end = date("2024-03-01T00:00:00Z");
+        let tests = [
+            (
+                "2024-01-01\n",
+                Bound::Start,
+                Ok(date("2024-01-01T00:00:00Z")),
+            ),
+            ("\n", Bound::Start, Ok(None)),
+            ("none\n", Bound::Start, Ok(None)),
+            (" None \n", Bound::End, Ok(None)),
+            //Asked again after an invalid answer, or one after the other bound
+            (
+                "soon\n2024-02\n",
+                Bound::Start,
+                Ok(date("2024-02-01T00:00:00Z")),
+            ),
+            ("2024-04\n\n", Bound::Start, Ok(None)),
+            (
+                "2024-01\n2024-02-15\n",
+                Bound::End,
+                Ok(date("2024-02-15T23:59:59Z")),
+            ),
+            ("", Bound::Start, Err("end of input")),
+            ("soon\n", Bound::End, Err("end of input")),
+        ];
+        for (input, bound, expected) in tests {
+            let other = match bound {
+                Bound::Start => end,
+                Bound::End => date("2024-02-01T00:00:00Z"),
+            };
+            let result = ask_date(
+                bound,
+                other,
+                &utc,
+                true,
+                &mut input.as_bytes(),
+                Status::Stderr,
+            );
+            match (result, expected) {
+                (Ok(d), Ok(e)) => assert_eq!(d, e, "{:?}", input),
+                (Err(e), Err(msg)) => assert!(e.to_string().contains(msg), "{:?}: {}", input, e),
+                (r, _) => panic!("{:?}: unexpected {:?}", input, r),
+            }
+        }
+    }
+
+    #[test]
+    fn ask_channel_test() {
+        let tests = [
+            ("@foo\n", Ok("foo")),
+            ("\n\nbar\n", Ok("bar")),
+            ("Ch\u{e9}\nbaz\n", Ok("baz")),
+            ("-minusbrand\n", Ok("-minusbrand")),
+            ("\n", Err("Missing channel name")),
+            ("", Err("end of input")),
+        ];
+        for (input, expected) in tests {
+            let result = ask_channel(true, &mut input.as_bytes(), Status::Stderr);
+            match (result, expected) {
+                (Ok(name), Ok(e)) => assert_eq!(name, e, "{:?}", input),
+                (Err(e), Err(msg)) => assert!(e.to_string().contains(msg), "{:?}: {}", input, e),
+                (r, _) => panic!("{:?}: unexpected {:?}", input, r),
+            }
+        }
+    }
+
+    #[test]
+    fn usage_status_test() {
+        let tests = [
+            (vec!["--bogus", "channel"], Status::Stdout),
+            (vec!["--porcelain", "--bogus", "channel"], Status::Stderr),
+            (
+                vec!["-k", "KEY", "--bogus", "--raw-seconds"],
+                Status::Stderr,
+            ),
+            (
+                vec!["run", "--raw-seconds", "-s", "yesterday-ish"],
+                Status::Stderr,
+            ),
+            (vec!["--bogus", "--", "--porcelain"], Status::Stdout),
+        ];
+        for (argv, r) in tests {
+            let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+            assert_eq!(usage_status(&argv), r, "{:?}", argv);
+        }
+    }
+
+    #[test]
+    fn exit_code_test() {
+        let args = |a: &[&str]| a.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+        let tests = [
+            args(&["--bogus", "channel"]),
+            args(&["-k", "KEY", "--output-fd", "none", "channel"]),
+            args(&["-k", "KEY", "--local-time", "--timezone", "UTC", "channel"]),
+            args(&["-k", "KEY", "-s", "yesterday-ish", "channel"]),
+            args(&["-k", "KEY", "--no-output", "-o", "out.csv", "channel"]),
+            args(&["-k", "KEY", "--non-interactive"]),
+            args(&[
+                "-k",
+                "KEY",
+                "--since-last-run",
+                "-s",
+                "2024-01-01",
+                "channel",
+            ]),
+            args(&["-k", "KEY", "--no-clobber", "--force", "channel"]),
+            args(&["-i", "-k", "KEY", "-s", "2024-01-01"]),
+            args(&["--wizard", "-k", "KEY", "channel"]),
+            args(&["-k", "KEY", "--count-only", "--title-any", "ep", "channel"]),
+            args(&["-k", "KEY", "--count-only", "--with-thumbnail", "channel"]),
+            args(&["-k", "KEY", "--with-thumbnail=huge", "channel"]),
+            args(&[
+                "-k",
+                "KEY",
+                "--resume-from",
+                "out.csv",
+                "--channels-compare",
+                "a,b",
+            ]),
+            args(&[
+                "-k",
+                "KEY",
+                "--resume-from",
+                "out.csv",
+                "--strict-public",
+                "channel",
+            ]),
+            args(&["-k", "KEY", "--title-all", " , ", "channel"]),
+            args(&["-k", "KEY", "--user-agent", " ", "channel"]),
+            args(&["-k", "KEY", "--fill-gaps", "channel"]),
+            args(&["-k", "KEY", "--stream", "--sort", "date", "channel"]),
+            args(&[
+                "-k", "KEY", "--stream", "-o", "a.csv", "-o", "b.m3u", "channel",
+            ]),
+            args(&["-k", "KEY", "--stream", "--format", "json", "channel"]),
+            args(&["-k", "KEY", "--stream", "-o", "a.jsonl", "channel"]),
+            args(&["-k", "KEY", "--check-key", "channel"]),
+            args(&["-k", "KEY", "--check-key", "--channel-stats"]),
+            args(&[
+                "-k",
+                "KEY",
+                "--count-only",
+                "--diff-file",
+                "a.csv",
+                "channel",
+            ]),
+            args(&[
+                "-k",
+                "KEY",
+                "--skipped-report",
+                "skipped.csv",
+                "--channels-compare",
+                "a,b",
+            ]),
+            args(&["-k", "KEY", "--channels-compare", "a,b", "-o", "out.csv"]),
+            args(&["-k", "KEY", "--channels-compare", "a,b", "--rate"]),
+            args(&["-k", "KEY", "--channels-compare", "a,b", "--lang", "hu"]),
+            args(&["-k", "KEY", "--raw-seconds", "--porcelain", "channel"]),
+            args(&["-k", "KEY", "--total-fd", "3", "channel"]),
+            args(&["-k", "KEY", "--raw-seconds", "--total-fd", "0", "channel"]),
+            args(&[
+                "-k",
+                "KEY",
+                "--raw-seconds",
+                "--output-fd",
+                "3",
+                "--total-fd",
+                "3",
+                "channel",
+            ]),
+            args(&["resolve", "-k", "KEY", "--user-agent=a\nb", "channel"]),
+            args(&[
+                "-k",
+                "KEY",
+                "-s",
+                "2024-06-01",
+                "-e",
+                "2024-01-01",
+                "channel",
+            ]),
+        ];
+        for a in tests {
+            assert_eq!(exit_code(&run(&a)), EXIT_USAGE, "{:?}", a);
+        }
+
+        assert_eq!(exit_code(&Ok(())), 0);
+        assert_eq!(exit_code(&Err("Invalid API key".into())), EXIT_ERROR);
+        assert_eq!(
+            exit_code(&Err(yt_api_videosum::Partial(2).into())),
+            EXIT_PARTIAL
+        );
+    }
+
+    #[test]
+    fn early_exit_test() {
+        let args = |a: &[&str]| a.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+        let version = format!("yt-api-videosum {}", yt_api_videosum::version());
+
+        let tests = [
+            (args(&["-V"]), Some(version.as_str())),
+            (args(&["--version"]), Some(version.as_str())),
+            (args(&["-s", "-o", "out.csv", "-V"]), Some(version.as_str())),
+            (args(&["-V", "-h"]), Some(DESC)),
+            (args(&["--help"]), Some(DESC)),
+            (args(&["channel"]), None),
+            (args(&["--", "-h"]), None),
+            (args(&[]), None),
+        ];
+
+        for (a, r) in tests {
+            assert_eq!(early_exit(&a, DESC).as_deref(), r, "args={:?}", a);
+        }
+        assert_eq!(
+            early_exit(&args(&["-h"]), STATS_DESC).as_deref(),
+            Some(STATS_DESC)
+        );
+    }
+
+    #[test]
+    fn parse_target_test() {
+        use yt_api_videosum::output::Format;
+
+        let tests = [
+            ("report.csv", Ok((Format::Csv, "report.csv"))),
+            ("report.JSON", Ok((Format::Json, "report.JSON"))),
+            ("report.json.gz", Ok((Format::Json, "report.json.gz"))),
+            ("dir.d/report.md", Ok((Format::Markdown, "dir.d/report.md"))),
+            ("list.m3u8", Ok((Format::M3u, "list.m3u8"))),
+            ("json:report.txt", Ok((Format::Json, "report.txt"))),
+            ("csv:report.json", Ok((Format::Csv, "report.json"))),
+            ("C:\\report.md", Ok((Format::Markdown, "C:\\report.md"))),
+            ("report.txt", Err(())),
+            ("report", Err(())),
+            ("xml:report.xml", Err(())),
+        ];
+
+        for (t, r) in tests {
+            assert_eq!(parse_target(t).map_err(|_| ()), r, "target=\"{}\"", t);
+        }
+    }
+
+    #[test]
+    fn output_format_test() {
+        use yt_api_videosum::output::Format;
+        let tests = [
+            (None, None, Format::Csv, false),
+            (None, Some("report.csv"), Format::Csv, false),
+            (None, Some("report.json"), Format::Json, false),
+            (None, Some("report.JSONL.gz"), Format::Json, false),
+            (None, Some("{channel}.md"), Format::Markdown, false),
+            (None, Some("list.m3u8"), Format::M3u, false),
+            (None, Some("report.tsv"), Format::Csv, true),
+            (None, Some("report"), Format::Csv, true),
+            (Some(Format::Json), Some("report.csv"), Format::Json, false),
+            (Some(Format::Csv), Some("report.txt"), Format::Csv, false),
+        ];
+
+        for (given, path, format, warned) in tests {
+            let (f, w) = output_format(given, path);
+            assert_eq!((f, w.is_some()), (format, warned), "{:?} {:?}", given, path);
+        }
+    }
+
+    #[test]
+    fn create_output_test() {
+        let dir = std::env::temp_dir().join(format!("yt_api_videosum_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .into();
+
+        let template = format!("{}/{{channel}}-{{start}}-{{end}}.csv", dir.display());
+        let (path, _) = create_output(
+            &template,
+            "You/Tube",
+            Some(start),
+            None,
+            OutputMode::Protect,
+            false,
+            Status::Stdout,
+        )
+        .unwrap();
+        assert_eq!(path, format!("{}/YouTube-20240101-all.csv", dir.display()));
+        assert!(std::path::Path::new(&path).is_file());
+
+        let template = format!("{}/{{unknown}}.csv", dir.display());
+        assert!(create_output(
+            &template,
+            "YouTube",
+            None,
+            None,
+            OutputMode::Force,
+            false,
+            Status::Stdout
+        )
+        .is_err());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn open_outputs_test() {
+        let dir = std::env::temp_dir().join(format!("yt_api_videosum_open_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = format!("{}/out.csv", dir.display());
+        let extra = format!("{}/extra.json", dir.display());
+        std::fs::write(&path, "existing").unwrap();
+        std::fs::write(&extra, "existing").unwrap();
+
+        let targets = [extra.clone(), format!("{}/extra.txt", dir.display())];
+        let new_target = [format!("{}/new.json", dir.display())];
+
+        let settings = |extra_paths, enabled| OutputSettings {
+            path: "",
+            extra_paths,
+            buckets_path: None,
+            months_path: None,
+            skipped_path: None,
+            fd: None,
+            enabled,
+            mode: OutputMode::Force,
+            compress: false,
+            channel_name: "YouTube",
+            start_date: None,
+            end_date: None,
+            interactive: false,
+            status: Status::Stdout,
+        };
+
+        //An invalid target is reported before anything is truncated
+        let mut s = settings(&targets, true);
+        s.path = &path;
+        assert!(open_outputs(&s).is_err());
+        s.extra_paths = &[];
+        s.mode = OutputMode::Append;
+        s.compress = true;
+        assert!(open_outputs(&s).is_err());
+        for p in [&path, &extra] {
+            assert_eq!(std::fs::read_to_string(p).unwrap(), "existing", "{}", p);
+        }
+
+        //Nothing is created or truncated with '--no-output'
+        let mut s = settings(&new_target, false);
+        let new = format!("{}/new.csv", dir.display());
+        s.path = &new;
+        let outputs = open_outputs(&s).unwrap();
+        assert!(outputs.output.is_none() && outputs.pending.is_none());
+        assert!(outputs.extra_outputs.is_empty());
+        s.path = &path;
+        assert!(open_outputs(&s).unwrap().output.is_none());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "existing");
+
+        //The final path is displayed, not the temporary one written first
+        let mut s = settings(&[], true);
+        s.path = &new;
+        let outputs = open_outputs(&s).unwrap();
+        assert_eq!(
+            outputs.destination,
+            yt_api_videosum::output::Destination::Path(new.clone())
+        );
+        assert_eq!(outputs.pending.unwrap().partial, format!("{}.partial", new));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn output_mode_test() {
+        let dir = std::env::temp_dir().join(format!("yt_api_videosum_mode_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = format!("{}/output.txt", dir.display());
+        let create = |mode| create_output(&path, "", None, None, mode, false, Status::Stdout);
+        let content = || std::fs::read_to_string(&path).unwrap();
+
+        //Missing or empty file is never protected
+        create(OutputMode::Protect).unwrap();
+        create(OutputMode::Protect)
+            .unwrap()
+            .1
+            .write_all(b"first")
+            .unwrap();
+        assert_eq!(content(), "first");
+
+        assert!(create(OutputMode::Protect).is_err());
+        assert_eq!(content(), "first");
+
+        let (_, mut f) = create(OutputMode::Append).unwrap();
+        assert_eq!(f.stream_position().unwrap(), 5);
+        f.write_all(b" second").unwrap();
+        assert_eq!(content(), "first second");
+
+        create(OutputMode::Force)
+            .unwrap()
+            .1
+            .write_all(b"third")
+            .unwrap();
+        assert_eq!(content(), "third");
+
+        //Not even an empty file is overwritten, nor asked about
+        let interactive = |mode| create_output(&path, "", None, None, mode, true, Status::Stdout);
+        let e = interactive(OutputMode::NoClobber).unwrap_err();
+        assert!(e.to_string().contains("'--no-clobber'"), "{}", e);
+        std::fs::write(&path, "").unwrap();
+        assert!(create(OutputMode::NoClobber).is_err());
+        std::fs::remove_file(&path).unwrap();
+        create(OutputMode::NoClobber)
+            .unwrap()
+            .1
+            .write_all(b"fourth")
+            .unwrap();
+        assert_eq!(content(), "fourth");
+        assert!(open_output(&path, OutputMode::NoClobber).is_err());
+        assert_eq!(content(), "fourth");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to write the videos as a Markdown table
+
+    The run metadata is written as an HTML comment before the table, so it is not rendered. Pipes in the titles are
+    escaped, and line breaks replaced by spaces, to keep each video in a single table row.
+*/
+
+use std::error::Error;
+use std::io::Write;
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use crate::output::{Metadata, RowWriter};
+use crate::Video;
+
+pub const HEADER: &str = "| Published | Title | Duration | Video |\n|---|---|---:|---|";
+
+pub fn escape(title: &str) -> String {
+    title
+        .split(['\r', '\n'])
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    count: usize,
+    total: TimeDelta,
+}
+impl<W: Write> Writer<W> {
+    pub fn new(mut out: W, metadata: Option<&Metadata>) -> Result<Self, Box<dyn Error>> {
+        if let Some(m) = metadata {
+            writeln!(out, "<!--")?;
+            for (k, v) in m.fields() {
+                writeln!(out, "{}={}", k, v)?;
+            }
+            writeln!(out, "-->")?;
+        }
+        writeln!(out, "{}", HEADER)?;
+        Ok(Self {
+            out,
+            count: 0,
+            total: TimeDelta::zero(),
+        })
+    }
+}
+impl<W: Write> RowWriter<W> for Writer<W> {
+    fn write(&mut self, video: &Video) -> Result<(), Box<dyn Error>> {
+        writeln!(
+            self.out,
+            "| {} | {} | {} | [{}]({}) |",
+            video.date.format("%Y-%m-%d"),
+            escape(&video.title),
+            crate::report::format_hms(video.delta),
+            video.id,
+            video.url()
+        )?;
+        self.count += 1;
+        self.total += video.delta;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, footer: Option<DateTime<Utc>>) -> Result<W, Box<dyn Error>> {
+        if let Some(generated) = footer {
+            writeln!(
+                self.out,
+                "\n{} videos, total {}, generated {}",
+                self.count,
+                crate::report::format_hms(self.total),
+                generated.to_rfc3339_opts(SecondsFormat::Secs, true)
+            )?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod markdown_test {
+    use super::*;
+
+    #[test]
+    fn escape_test() {
+        let tests = [
+            ("Plain", "Plain"),
+            ("A | B", "A \\| B"),
+            ("Back\\slash", "Back\\\\slash"),
+            ("Two\r\nlines", "Two lines"),
+        ];
+
+        for (t, r) in tests {
+            assert_eq!(escape(t), r);
+        }
+    }
+}
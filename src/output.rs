@@ -0,0 +1,628 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to resolve the output file
+
+    The output path may contain the following placeholders:
+    - {channel}  channel name, stripped of path separators and control characters
+    - {date}     run date (UTC) in YYYYMMDD format
+    - {start}    start date filter in YYYYMMDD format, or "all" if not given
+    - {end}      end date filter in YYYYMMDD format, or "all" if not given
+
+    When compressed, everything written to the file is gzip encoded. Only the videos are ever written to it, never the
+    responses of the API, so a run failing before the first video leaves the file empty (see Pending).
+
+    The CSV and M3U outputs start with '#'-prefixed metadata lines right after the first line (see Metadata), recording
+    the parameters of the run, while the JSON and Markdown outputs embed the same fields. The API key is never included.
+
+    Additional outputs are written at the end from the same collected videos, each on its own, so a failure with one
+    does not affect the others.
+
+    With a custom date format, the CSV output gets an additional leading 'date' column rendered in the given format and
+    timezone, while the 'publishedAt' column keeps the original RFC3339 UTC timestamp for machine use.
+*/
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::template::Template;
+use crate::Video;
+
+pub const DEFAULT_PATH: &str = "output.txt";
+
+//Where the output goes, as displayed in the messages
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Destination {
+    Path(String),
+    Fd(i32),
+    Stdout,
+    //A file given without telling where it is, so the messages claim no path
+    #[default]
+    Unnamed,
+}
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Destination::Path(p) => write!(f, "'{}'", p),
+            Destination::Fd(fd) => write!(f, "file descriptor {}", fd),
+            Destination::Stdout => write!(f, "stdout"),
+            Destination::Unnamed => write!(f, "the given file"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Csv,
+    M3u,
+    Template,
+    Json,
+    Markdown,
+}
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "m3u" => Ok(Format::M3u),
+            "template" => Ok(Format::Template),
+            "json" => Ok(Format::Json),
+            "md" => Ok(Format::Markdown),
+            _ => Err(format!(
+                "Invalid format '{}', expected 'csv', 'm3u', 'template', 'json' or 'md'",
+                s
+            )),
+        }
+    }
+}
+impl Format {
+    //By the extension, ignoring a trailing ".gz"
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path
+            .strip_suffix(".gz")
+            .unwrap_or(path)
+            .to_ascii_lowercase();
+        match path.rsplit_once('.')?.1 {
+            "csv" => Some(Format::Csv),
+            "m3u" | "m3u8" => Some(Format::M3u),
+            "json" | "jsonl" => Some(Format::Json),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DateFormat {
+    format: Option<String>,
+    timezone: Tz,
+}
+impl DateFormat {
+    //Validates both up front, so an invalid one fails before any network call
+    pub fn new(format: Option<&str>, timezone: Option<&str>) -> Result<Self, String> {
+        if let Some(f) = format {
+            if StrftimeItems::new(f).any(|i| matches!(i, Item::Error)) {
+                return Err(format!("Invalid date format '{}'", f));
+            }
+        }
+        let timezone = match timezone {
+            Some(t) => parse_timezone(t)?,
+            None => Tz::UTC,
+        };
+        Ok(Self {
+            format: format.map(String::from),
+            timezone,
+        })
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    pub fn render(&self, date: DateTime<Utc>) -> String {
+        let local = date.with_timezone(&self.timezone);
+        match self.format {
+            Some(ref f) => local.format(f).to_string(),
+            None => local.to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+}
+
+pub fn parse_timezone(s: &str) -> Result<Tz, String> {
+    s.parse().map_err(|_| {
+        format!(
+            "Unknown timezone '{}', expected an IANA name like 'Europe/Budapest'",
+            s
+        )
+    })
+}
+
+//Writes the videos one by one, so the output can be streamed while fetching
+pub(crate) trait RowWriter<W: Write> {
+    fn write(&mut self, video: &Video) -> Result<(), Box<dyn Error>>;
+    fn finish(self: Box<Self>, footer: Option<DateTime<Utc>>) -> Result<W, Box<dyn Error>>;
+}
+
+//Everything shaping the output besides the videos themselves
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Layout<'a> {
+    pub format: Format,
+    pub date: Option<&'a DateFormat>,
+    pub template: Option<&'a Template>,
+    pub metadata: Option<&'a Metadata>,
+    //A trailing 'has_captions' column, for CSV only
+    pub captions: bool,
+    //A trailing 'definition' column (after 'has_captions'), for CSV only
+    pub definition: bool,
+    //Trailing 'dimension' and 'projection' columns (after 'definition'), for CSV only
+    pub immersive: bool,
+    //A trailing 'thumbnail_url' column (after these), for CSV and a field for JSON
+    pub thumbnail: bool,
+}
+
+pub(crate) fn writer<'a, W: Write + 'a>(
+    out: W,
+    layout: &Layout<'a>,
+) -> Result<Box<dyn RowWriter<W> + 'a>, Box<dyn Error>> {
+    Ok(match layout.format {
+        Format::Csv => Box::new(crate::csv::Writer::new(
+            out,
+            layout.date.cloned(),
+            layout.metadata,
+            layout.captions,
+            layout.definition,
+            layout.immersive,
+            layout.thumbnail,
+        )?),
+        Format::M3u => Box::new(crate::m3u::Writer::new(out, layout.metadata)?),
+        Format::Template => Box::new(crate::template::Writer::new(
+            out,
+            layout
+                .template
+                .ok_or("Missing template for the 'template' format")?,
+            layout.date,
+        )),
+        Format::Json => Box::new(crate::json::Writer::new(out, layout.metadata)),
+        Format::Markdown => Box::new(crate::markdown::Writer::new(out, layout.metadata)?),
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    pub generated: DateTime<Utc>,
+    pub channel: String,
+    pub channel_id: String,
+    pub playlist_id: String,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub channel_videos: u64,
+    pub matched_videos: usize,
+}
+impl Metadata {
+    //One "#key=value" line each, values never contain line breaks
+    pub fn lines(&self) -> Vec<String> {
+        self.fields()
+            .into_iter()
+            .map(|(k, v)| format!("#{}={}", k, v))
+            .collect()
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let date = |d: Option<DateTime<Utc>>| match d {
+            Some(d) => d.to_rfc3339_opts(SecondsFormat::Secs, true),
+            None => String::from("-"),
+        };
+        [
+            ("tool", format!("yt-api-videosum {}", crate::version())),
+            ("generated", date(Some(self.generated))),
+            ("channel", format!("@{}", sanitize(&self.channel))),
+            ("channel_id", sanitize(&self.channel_id)),
+            ("playlist_id", sanitize(&self.playlist_id)),
+            ("start", date(self.start)),
+            ("end", date(self.end)),
+            ("channel_video_count", self.channel_videos.to_string()),
+            ("matched_video_count", self.matched_videos.to_string()),
+        ]
+        .into()
+    }
+}
+
+pub fn expand_path(
+    template: &str,
+    channel: &str,
+    run_date: DateTime<Utc>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<String, String> {
+    let bound = |d: Option<DateTime<Utc>>| match d {
+        Some(d) => d.format("%Y%m%d").to_string(),
+        None => String::from("all"),
+    };
+
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..].find('}').ok_or(format!(
+            "Unclosed placeholder in output path '{}'",
+            template
+        ))? + open;
+        match &rest[(open + 1)..close] {
+            "channel" => out.push_str(&sanitize(channel)),
+            "date" => out.push_str(&run_date.format("%Y%m%d").to_string()),
+            "start" => out.push_str(&bound(start)),
+            "end" => out.push_str(&bound(end)),
+            p => {
+                return Err(format!(
+                    "Unknown placeholder '{{{}}}' in output path, expected one of {{channel}}, {{date}}, {{start}}, {{end}}",
+                    p
+                ))
+            }
+        }
+        rest = &rest[(close + 1)..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+pub fn sanitize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !std::path::is_separator(*c) && *c != '\\' && !c.is_control())
+        .collect()
+}
+
+pub(crate) fn write_videos<W: Write>(
+    out: W,
+    videos: &[Video],
+    layout: &Layout,
+    footer: Option<DateTime<Utc>>,
+) -> Result<W, Box<dyn Error>> {
+    let mut w = writer(out, layout)?;
+    for v in videos {
+        w.write(v)?;
+    }
+    w.finish(footer)
+}
+
+//Single CSV of the videos of several channels, identified by the leading 'channel' column
+pub fn write_merged<W: Write>(
+    out: W,
+    summaries: &[(String, crate::Summary)],
+) -> Result<W, Box<dyn Error>> {
+    let channels: Vec<(&str, &[Video])> = summaries
+        .iter()
+        .map(|(name, summary)| (name.as_str(), summary.videos.as_slice()))
+        .collect();
+    crate::csv::write_merged(out, &channels)
+}
+
+pub struct ExtraOutput {
+    pub path: String,
+    pub format: Format,
+    pub file: File,
+}
+
+pub(crate) fn write_extra(
+    extra: ExtraOutput,
+    videos: &[Video],
+    layout: &Layout,
+    footer: Option<DateTime<Utc>>,
+) -> Result<u64, Box<dyn Error>> {
+    let mut sink = Sink::new(extra.file, is_compressed_path(&extra.path))?;
+    let layout = Layout {
+        format: extra.format,
+        ..*layout
+    };
+    write_videos(sink.rewrite()?, videos, &layout, footer)?.close()?;
+    Ok(sink.size()?)
+}
+
+//Output written under a temporary name next to the final one, and moved in place only once complete
+#[derive(Clone, Debug)]
+pub struct Pending {
+    pub partial: String,
+    pub path: String,
+}
+impl Pending {
+    pub fn new(path: &str) -> Self {
+        Self {
+            partial: format!("{}.partial", path),
+            path: String::from(path),
+        }
+    }
+
+    pub fn commit(&self) -> Result<(), Box<dyn Error>> {
+        std::fs::rename(&self.partial, &self.path).map_err(|e| {
+            format!(
+                "Could not move '{}' to '{}': {}",
+                self.partial, self.path, e
+            )
+            .into()
+        })
+    }
+
+    //Removes the temporary file if nothing was written to it, e.g. after a failure before the first video; true if kept
+    pub fn discard_if_empty(&self) -> std::io::Result<bool> {
+        match std::fs::metadata(&self.partial) {
+            Ok(m) if m.len() == 0 => std::fs::remove_file(&self.partial).map(|_| false),
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+pub fn is_compressed_path(path: &str) -> bool {
+    path.ends_with(".gz")
+}
+
+//Output file, where anything before the initial position (e.g. in append mode) is left untouched
+pub(crate) struct Sink {
+    file: File,
+    base: u64,
+    compress: bool,
+    //False for e.g. a pipe, which can only be written once
+    seekable: bool,
+    written: bool,
+}
+impl Sink {
+    pub fn new(mut file: File, compress: bool) -> std::io::Result<Self> {
+        let (base, seekable) = match file.stream_position() {
+            Ok(base) => (base, true),
+            Err(_) => (0, false),
+        };
+        Ok(Self {
+            file,
+            base,
+            compress,
+            seekable,
+            written: false,
+        })
+    }
+
+    //Discards everything written so far and starts over
+    pub fn rewrite(&mut self) -> std::io::Result<Stream> {
+        match self.seekable {
+            true => {
+                self.file.set_len(self.base)?;
+                self.file.seek(SeekFrom::Start(self.base))?;
+            }
+            false if self.written => {
+                return Err(std::io::Error::other(
+                    "The output is not seekable, so it cannot be written again",
+                ))
+            }
+            false => (),
+        }
+        self.written = true;
+        let file = self.file.try_clone()?;
+        Ok(match self.compress {
+            true => Stream::Gzip(GzEncoder::new(file, Compression::default())),
+            false => Stream::Plain(file),
+        })
+    }
+
+    //Everything written since the initial position, decompressed if needed; the file has to be opened for reading too
+    pub fn read_back(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut file = self.file.try_clone()?;
+        file.seek(SeekFrom::Start(self.base))?;
+        let mut data = Vec::new();
+        match self.compress {
+            true => flate2::read::GzDecoder::new(file).read_to_end(&mut data)?,
+            false => file.read_to_end(&mut data)?,
+        };
+        Ok(data)
+    }
+
+    pub fn sync(&self) -> std::io::Result<()> {
+        self.file.sync_all()
+    }
+
+    pub fn size(&self) -> std::io::Result<u64> {
+        Ok(self.file.metadata()?.len() - self.base)
+    }
+}
+
+pub(crate) enum Stream {
+    Plain(File),
+    Gzip(GzEncoder<File>),
+}
+impl Stream {
+    pub fn close(self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(mut f) => f.flush(),
+            Stream::Gzip(g) => g.finish().map(|_| ()),
+        }
+    }
+}
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(f) => f.write(buf),
+            Stream::Gzip(g) => g.write(buf),
+        }
+    }
+
+    //For gzip this completes the current block, so the data written so far can be decompressed
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(f) => f.flush(),
+            Stream::Gzip(g) => g.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod output_test {
+    use super::*;
+
+    #[test]
+    fn destination_test() {
+        assert_eq!(Destination::default(), Destination::Unnamed);
+        assert_eq!(
+            Destination::Path(String::from("reports/a b.csv")).to_string(),
+            "'reports/a b.csv'"
+        );
+        assert_eq!(Destination::Fd(3).to_string(), "file descriptor 3");
+        assert_eq!(Destination::Stdout.to_string(), "stdout");
+    }
+
+    #[test]
+    fn expand_test() {
+        let date = |s| {
+            Some(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let run = date("2025-01-10T12:34:56Z").unwrap();
+        let start = date("2024-01-01T00:00:00Z");
+        let end = date("2024-12-27T17:00:00+02:00");
+
+        let tests = [
+            ("output.txt", None, None, Ok("output.txt")),
+            (
+                "reports/{channel}-{date}.csv",
+                None,
+                None,
+                Ok("reports/YouTube-20250110.csv"),
+            ),
+            ("{start}_{end}", start, end, Ok("20240101_20241227")),
+            ("{start}_{end}", None, end, Ok("all_20241227")),
+            ("{channel}{channel}", None, None, Ok("YouTubeYouTube")),
+            ("{}", None, None, Err(())),
+            ("{chan}.csv", None, None, Err(())),
+            ("{Channel}.csv", None, None, Err(())),
+            ("{channel.csv", None, None, Err(())),
+            ("x}{date}", None, None, Ok("x}20250110")),
+        ];
+
+        for (t, s, e, r) in tests {
+            assert_eq!(
+                expand_path(t, "YouTube", run, s, e).map_err(|_| ()),
+                r.map(String::from),
+                "template=\"{}\"",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn discard_if_empty_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt_api_videosum_discard_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pending = Pending::new(dir.join("out.csv").to_str().unwrap());
+
+        assert!(!pending.discard_if_empty().unwrap());
+        File::create(&pending.partial).unwrap();
+        assert!(!pending.discard_if_empty().unwrap());
+        assert!(!std::path::Path::new(&pending.partial).exists());
+        std::fs::write(&pending.partial, "#publishedAt\n").unwrap();
+        assert!(pending.discard_if_empty().unwrap());
+        assert!(std::path::Path::new(&pending.partial).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compress_test() {
+        let dir = std::env::temp_dir().join(format!("yt_api_videosum_gz_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let content = "#publishedAt,title\n2024-01-02T03:04:05Z,\"a, b\"\n".repeat(100);
+
+        let write = |name: &str, compress| {
+            let path = dir.join(name);
+            let mut sink = Sink::new(File::create(&path).unwrap(), compress).unwrap();
+            let mut stream = sink.rewrite().unwrap();
+            stream.write_all(b"{\"discarded\": true}").unwrap();
+            stream.close().unwrap();
+            let mut stream = sink.rewrite().unwrap();
+            stream.write_all(content.as_bytes()).unwrap();
+            stream.close().unwrap();
+            assert_eq!(
+                sink.size().unwrap(),
+                std::fs::metadata(&path).unwrap().len()
+            );
+            std::fs::read(&path).unwrap()
+        };
+        let plain = write("output.txt", false);
+        let compressed = write("output.txt.gz", true);
+
+        let mut decompressed = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(compressed.as_slice()),
+            &mut decompressed,
+        )
+        .unwrap();
+        assert_eq!(decompressed.as_bytes(), plain);
+        assert_eq!(decompressed, content);
+        assert!(compressed.len() < plain.len());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn date_format_test() {
+        let date =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-12-31T23:30:00Z").unwrap());
+
+        let tests = [
+            (None, None, Ok("2024-12-31T23:30:00Z")),
+            (Some("%d.%m.%Y"), None, Ok("31.12.2024")),
+            (Some("%d.%m.%Y"), Some("Europe/Budapest"), Ok("01.01.2025")),
+            (
+                Some("%Y-%m-%d %H:%M"),
+                Some("America/New_York"),
+                Ok("2024-12-31 18:30"),
+            ),
+            (None, Some("Asia/Kolkata"), Ok("2025-01-01T05:00:00+05:30")),
+            (Some("%A, %B %e"), Some("UTC"), Ok("Tuesday, December 31")),
+            (Some("%Q"), None, Err(())),
+            (Some("%"), None, Err(())),
+            (None, Some("Mars/Olympus_Mons"), Err(())),
+            (None, Some("europe/budapest"), Err(())),
+        ];
+
+        for (f, t, r) in tests {
+            assert_eq!(
+                DateFormat::new(f, t)
+                    .map(|d| d.render(date))
+                    .map_err(|_| ()),
+                r.map(String::from),
+                "format={:?} timezone={:?}",
+                f,
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn sanitize_test() {
+        let tests = [
+            ("YouTube", "YouTube"),
+            ("../../etc/passwd", "....etcpasswd"),
+            ("a\\b", "ab"),
+            ("new\nline\ttab\u{7f}", "newlinetab"),
+            ("ünïcödé", "ünïcödé"),
+        ];
+
+        for (n, r) in tests {
+            assert_eq!(sanitize(n), r);
+        }
+    }
+}
@@ -13,6 +13,13 @@
     - Although it is not prohibited to use values exceeding their carry over points (e.g. "PT36H"), we are not supporting this except for the day field.
     - We are not supporting fractional values.
 
+    Recoverable forms:
+    Some malformed values still carry an unambiguous duration, 'repair_period' turns these into the strict format,
+    while 'parse_delta' itself stays strict. Tolerated are surrounding whitespace, lowercase letters, and a missing 'P' or 'PT'
+    designator (e.g. "15M33S", "T15M33S", "1DT2H"). A missing 'T' alone is not tolerated (e.g. "P15M" would be months
+    as per the standard). Anything else, like unknown characters, a missing number, or a value without any time or
+    day field, is definitely not a duration and is rejected.
+
 
     Excerpt from the YouTube API documentation:
 
@@ -130,6 +137,54 @@ pub fn parse_delta(period: &str) -> Option<TimeDelta> {
     }
 }
 
+//Inverse of parse_delta, with the day as the largest field and fractional seconds truncated
+pub fn format_delta_iso(delta: &TimeDelta) -> String {
+    debug_assert!(*delta >= TimeDelta::zero());
+    let total = delta.num_seconds().max(0);
+    let (days, rest) = (total / 86400, total % 86400);
+    let (hrs, min, sec) = (rest / 3600, rest / 60 % 60, rest % 60);
+
+    let mut out = String::from("P");
+    if days > 0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if rest > 0 || days == 0 {
+        out.push('T');
+        if hrs > 0 {
+            out.push_str(&format!("{}H", hrs));
+        }
+        if min > 0 {
+            out.push_str(&format!("{}M", min));
+        }
+        if sec > 0 || rest == 0 {
+            out.push_str(&format!("{}S", sec));
+        }
+    }
+
+    out
+}
+
+pub fn repair_period(period: &str) -> Option<String> {
+    let s = period.trim().to_ascii_uppercase();
+    if !s.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (marked, s) = match s.strip_prefix('P') {
+        Some(rest) => (true, rest),
+        None => (false, s.as_str()),
+    };
+    let repaired = match s.contains('T') {
+        true => format!("P{}", s),
+        //Day field only, otherwise time only, as "P15M" would be months
+        false if s.ends_with('D') => format!("P{}", s),
+        false if !marked && !s.contains('D') => format!("PT{}", s),
+        false => return None,
+    };
+
+    parse_delta(&repaired).map(|_| repaired)
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 enum Element {
     Period,
@@ -2476,4 +2531,89 @@ mod period_test {
             }
         }
     }
+
+    #[test]
+    fn repair_test() {
+        let tests = [
+            //strict forms are kept
+            ("PT15M33S", Some(TimeDelta::seconds(15 * 60 + 33))),
+            ("P1DT2H", Some(TimeDelta::hours(26))),
+            ("P1D", Some(TimeDelta::days(1))),
+            //tolerated
+            ("15M33S", Some(TimeDelta::seconds(15 * 60 + 33))),
+            ("T15M33S", Some(TimeDelta::seconds(15 * 60 + 33))),
+            ("33S", Some(TimeDelta::seconds(33))),
+            ("1H", Some(TimeDelta::hours(1))),
+            ("pt15m33s", Some(TimeDelta::seconds(15 * 60 + 33))),
+            (" PT15M33S\n", Some(TimeDelta::seconds(15 * 60 + 33))),
+            ("1DT2H", Some(TimeDelta::hours(26))),
+            ("1D", Some(TimeDelta::days(1))),
+            //rejected
+            ("", None),
+            ("PT", None),
+            ("P", None),
+            ("PTS", None),
+            ("15", None),
+            ("PT15", None),
+            ("PT15X", None),
+            ("PT15M33S!", None),
+            ("PT1.5S", None),
+            ("-PT15S", None),
+            ("PT 15S", None),
+            ("PT15S33M", None),
+            ("PT60S", None),
+            ("P1Y", None),
+            ("2024-01-01", None),
+            ("PT15M33SP", None),
+            ("T1D", None),
+            ("1T2H", None),
+            ("P15M33S", None),
+            ("P15M", None),
+            ("P1D2H", None),
+            ("1D2H3M", None),
+        ];
+
+        for (p, r) in tests {
+            let repaired = repair_period(p);
+            assert_eq!(
+                repaired.as_deref().and_then(parse_delta),
+                r,
+                "pattern=\"{}\"",
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn format_test() {
+        let tests = [
+            (0, "PT0S"),
+            (1, "PT1S"),
+            (60, "PT1M"),
+            (61, "PT1M1S"),
+            (3600, "PT1H"),
+            (3723, "PT1H2M3S"),
+            (3603, "PT1H3S"),
+            (86399, "PT23H59M59S"),
+            (86400, "P1D"),
+            (86401, "P1DT1S"),
+            (93600, "P1DT2H"),
+            (11 * 86400 + 22 * 3600 + 33 * 60 + 44, "P11DT22H33M44S"),
+            (400 * 86400, "P400D"),
+        ];
+
+        for (t, r) in tests {
+            let delta = TimeDelta::seconds(t);
+            assert_eq!(format_delta_iso(&delta), r);
+            assert_eq!(parse_delta(r), Some(delta), "pattern=\"{}\"", r);
+        }
+
+        assert_eq!(format_delta_iso(&TimeDelta::milliseconds(61999)), "PT1M1S");
+
+        //Every 7th second of a period covering all fields
+        for t in (0..(3 * 86400)).step_by(7) {
+            let delta = TimeDelta::seconds(t);
+            assert_eq!(parse_delta(&format_delta_iso(&delta)), Some(delta));
+        }
+    }
 }
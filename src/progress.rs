@@ -0,0 +1,303 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to display the progress of fetching the videos
+
+    On a terminal, a single line is redrawn in place (using carriage returns only, and at most LINE_WIDTH characters
+    wide) with the count, percentage, elapsed time and the estimated time left, which is based on the average latency of
+    the last RECENT requests. The line is erased once done. Otherwise, e.g. redirected to a file, a whole line is printed
+    at every tenth of the items (one for several tenths if there are fewer items), so logs stay readable and other
+    messages can come in between. Without any items, nothing is displayed.
+*/
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use chrono::TimeDelta;
+
+use crate::Status;
+
+const RECENT: usize = 20;
+const BAR_WIDTH: usize = 20;
+pub const LINE_WIDTH: usize = 79;
+
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: usize,
+    started: Instant,
+    recent: VecDeque<Duration>,
+    bar: bool,
+    //Length of the line drawn last, to be erased
+    drawn: usize,
+    status: Status,
+}
+impl Progress {
+    pub fn start(label: &str, total: usize, bar: bool, status: Status) -> Self {
+        let mut p = Self {
+            label: String::from(label),
+            total,
+            done: 0,
+            started: Instant::now(),
+            recent: VecDeque::with_capacity(RECENT),
+            bar,
+            drawn: 0,
+            status,
+        };
+        if total > 0 && bar {
+            p.draw();
+        }
+        p
+    }
+
+    //One more item done, which took the given time
+    pub fn tick(&mut self, latency: Duration) {
+        let before = self.done;
+        self.done += 1;
+        if self.recent.len() == RECENT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(latency);
+
+        match self.bar {
+            true => self.draw(),
+            false => {
+                if let Some(line) = step_line(&self.label, before, self.done, self.total) {
+                    self.status.print(format_args!("{}\n", line));
+                }
+            }
+        }
+    }
+
+    //Makes room for another message, the bar is redrawn by the next tick
+    pub fn clear(&mut self) {
+        if self.bar {
+            self.erase();
+        }
+    }
+
+    pub fn finish(mut self) {
+        if self.total == 0 {
+            return;
+        }
+        if self.bar {
+            self.erase();
+        }
+        self.status.print(format_args!(
+            "{}: {} done in {}.\n",
+            self.label,
+            self.done,
+            format_elapsed(self.started.elapsed())
+        ));
+    }
+
+    fn draw(&mut self) {
+        let remaining = self.total.saturating_sub(self.done);
+        let line = render(
+            &self.label,
+            self.done,
+            self.total,
+            self.started.elapsed(),
+            eta(self.recent.make_contiguous(), remaining),
+        );
+        //Padded to cover a longer previous line
+        self.status.print(format_args!(
+            "\r{:width$}",
+            line,
+            width = self.drawn.max(line.len())
+        ));
+        self.drawn = line.len();
+    }
+
+    fn erase(&mut self) {
+        if self.drawn > 0 {
+            self.status
+                .print(format_args!("\r{:width$}\r", "", width = self.drawn));
+            self.drawn = 0;
+        }
+    }
+}
+
+//Of the ten, once the given number of items is done
+fn steps(done: usize, total: usize) -> usize {
+    match total {
+        0 => 0,
+        _ => done.min(total) * 10 / total,
+    }
+}
+
+//E.g. "Querying video info: 30/300 (10%)", if the item done reached another step
+fn step_line(label: &str, before: usize, done: usize, total: usize) -> Option<String> {
+    (steps(done, total) > steps(before, total)).then(|| {
+        format!(
+            "{}: {}/{} ({}%)",
+            label,
+            done,
+            total,
+            steps(done, total) * 10
+        )
+    })
+}
+
+//Time left at the average of the recent latencies, unknown before the first one
+pub fn eta(recent: &[Duration], remaining: usize) -> Option<Duration> {
+    if recent.is_empty() {
+        return None;
+    }
+    let average = recent.iter().sum::<Duration>() / recent.len() as u32;
+    Some(average * remaining.try_into().unwrap_or(u32::MAX))
+}
+
+pub fn render(
+    label: &str,
+    done: usize,
+    total: usize,
+    elapsed: Duration,
+    eta: Option<Duration>,
+) -> String {
+    let ratio = match total {
+        0 => 1.0,
+        _ => done.min(total) as f64 / total as f64,
+    };
+    let filled = (ratio * BAR_WIDTH as f64) as usize;
+    let line = format!(
+        "{} [{}{}] {}/{} {:3}% {} elapsed, {} left",
+        label,
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        done,
+        total,
+        (ratio * 100.0) as u32,
+        format_elapsed(elapsed),
+        eta.map(format_elapsed).unwrap_or(String::from("?")),
+    );
+    //Keep the line from wrapping, which would break redrawing it
+    line.chars().take(LINE_WIDTH).collect()
+}
+
+//E.g. "4:05", with the hours only when needed
+fn format_elapsed(d: Duration) -> String {
+    let s = d.as_secs();
+    match s {
+        0..3600 => format!("{}:{:02}", s / 60, s % 60),
+        _ => crate::report::format_hms(TimeDelta::seconds(s as i64)),
+    }
+}
+
+#[cfg(test)]
+mod progress_test {
+    use super::*;
+
+    #[test]
+    fn eta_test() {
+        let ms = Duration::from_millis;
+        let tests = [
+            (vec![], 10, None),
+            (vec![ms(100)], 0, Some(ms(0))),
+            (vec![ms(100)], 10, Some(ms(1000))),
+            (vec![ms(100), ms(300)], 10, Some(ms(2000))),
+        ];
+        for (recent, remaining, eta_) in tests {
+            assert_eq!(eta(&recent, remaining), eta_, "recent={:?}", recent);
+        }
+
+        //Only the recent latencies count, so a slow start is forgotten
+        let mut p = Progress::start("x", 100, false, Status::Stderr);
+        p.tick(Duration::from_secs(60));
+        for _ in 0..RECENT {
+            p.tick(ms(100));
+        }
+        assert_eq!(eta(p.recent.make_contiguous(), 10), Some(ms(1000)));
+    }
+
+    #[test]
+    fn steps_test() {
+        for total in [1, 3, 9, 10, 11, 250] {
+            let printed: Vec<_> = (1..=total)
+                .map(|done| steps(done, total) - steps(done - 1, total))
+                .collect();
+            assert_eq!(printed.iter().sum::<usize>(), 10, "total={}", total);
+            assert_eq!(steps(total, total), 10);
+            if total >= 10 {
+                assert!(printed.iter().all(|n| *n <= 1), "total={}", total);
+            }
+        }
+        assert_eq!(
+            (1..=3)
+                .map(|d| steps(d, 3) - steps(d - 1, 3))
+                .collect::<Vec<_>>(),
+            [3, 3, 4]
+        );
+        assert_eq!(steps(0, 0), 0);
+        assert_eq!(steps(5, 3), 10);
+    }
+
+    #[test]
+    fn step_line_test() {
+        let lines = |total| {
+            (1..=total)
+                .filter_map(|done| step_line("Video info", done - 1, done, total))
+                .collect::<Vec<_>>()
+        };
+        let hundred = lines(100);
+        assert_eq!(hundred.len(), 10);
+        assert_eq!(hundred[0], "Video info: 10/100 (10%)");
+        assert_eq!(hundred[9], "Video info: 100/100 (100%)");
+        assert_eq!(
+            lines(3),
+            [
+                "Video info: 1/3 (30%)",
+                "Video info: 2/3 (60%)",
+                "Video info: 3/3 (100%)",
+            ]
+        );
+        assert_eq!(lines(25)[0], "Video info: 3/25 (10%)");
+        assert!(lines(0).is_empty());
+    }
+
+    #[test]
+    fn render_test() {
+        let s = Duration::from_secs;
+        let tests = [
+            (
+                0,
+                200,
+                s(0),
+                None,
+                "Video info [--------------------] 0/200   0% 0:00 elapsed, ? left",
+            ),
+            (
+                50,
+                200,
+                s(65),
+                Some(s(195)),
+                "Video info [#####---------------] 50/200  25% 1:05 elapsed, 3:15 left",
+            ),
+            (
+                200,
+                200,
+                s(3725),
+                Some(s(0)),
+                "Video info [####################] 200/200 100% 1:02:05 elapsed, 0:00 left",
+            ),
+            (
+                0,
+                0,
+                s(0),
+                None,
+                "Video info [####################] 0/0 100% 0:00 elapsed, ? left",
+            ),
+        ];
+        for (done, total, elapsed, eta, line) in tests {
+            assert_eq!(render("Video info", done, total, elapsed, eta), line);
+        }
+
+        let long = render(&"x".repeat(100), 1, 2, s(0), None);
+        assert_eq!(long.chars().count(), LINE_WIDTH);
+    }
+}
@@ -0,0 +1,1228 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to render reports derived from the collected videos
+*/
+
+use chrono::{DateTime, Datelike, SecondsFormat, TimeDelta, Timelike, Utc};
+use chrono_tz::Tz;
+
+use crate::{Definition, Dimension, Projection, Rounding, SkipReason, Skipped, Summary, Video};
+
+//Compact duration format for table cells, e.g. "123:04:05"
+pub fn format_hms(delta: TimeDelta) -> String {
+    let s = delta.num_seconds();
+    format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+}
+
+pub fn average(summary: &Summary) -> TimeDelta {
+    match summary.count {
+        0 => TimeDelta::zero(),
+        n => summary.total / n as i32,
+    }
+}
+
+//One row per channel, sorted by total runtime descending, with the average rounded if given
+pub fn compare_table(summaries: &[(String, Summary)], round: Option<Rounding>) -> String {
+    let mut rows: Vec<&(String, Summary)> = summaries.iter().collect();
+    rows.sort_by_key(|r| std::cmp::Reverse(r.1.total));
+
+    let mut table = vec![[
+        String::from("Channel"),
+        String::from("Videos"),
+        String::from("Total"),
+        String::from("Average"),
+        String::from("First"),
+        String::from("Last"),
+    ]];
+    for (name, summary) in rows {
+        let span = |f: fn(&Summary) -> Option<String>| f(summary).unwrap_or(String::from("-"));
+        table.push([
+            name.clone(),
+            summary.count.to_string(),
+            format_hms(summary.total),
+            format_hms(round.map_or(average(summary), |r| r.apply(average(summary)))),
+            span(|s| {
+                Some(
+                    s.videos
+                        .iter()
+                        .map(|v| v.date)
+                        .min()?
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                )
+            }),
+            span(|s| {
+                Some(
+                    s.videos
+                        .iter()
+                        .map(|v| v.date)
+                        .max()?
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                )
+            }),
+        ]);
+    }
+
+    let mut widths = [0; 6];
+    for row in &table {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in &table {
+        let mut line = String::new();
+        for (i, (w, cell)) in widths.iter().zip(row).enumerate() {
+            match i {
+                0 => line.push_str(&format!("{:<w$}", cell, w = w)),
+                _ => line.push_str(&format!("  {:>w$}", cell, w = w)),
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+//Lower bounds in seconds, each bucket runs until the next one, the last is open-ended
+pub const BUCKETS: [(&str, i64); 8] = [
+    ("<1m", 0),
+    ("1-5m", 60),
+    ("5-10m", 5 * 60),
+    ("10-20m", 10 * 60),
+    ("20-30m", 20 * 60),
+    ("30-60m", 30 * 60),
+    ("1-2h", 60 * 60),
+    (">2h", 2 * 60 * 60),
+];
+
+pub struct Bucket {
+    pub label: &'static str,
+    pub lower: TimeDelta,
+    pub upper: Option<TimeDelta>,
+    pub count: usize,
+    pub total: TimeDelta,
+}
+
+//All buckets, including the empty ones, so charts have a stable axis
+pub fn duration_buckets(videos: &[Video]) -> Vec<Bucket> {
+    let mut buckets: Vec<Bucket> = BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(i, (label, lower))| Bucket {
+            label,
+            lower: TimeDelta::seconds(*lower),
+            upper: BUCKETS.get(i + 1).map(|b| TimeDelta::seconds(b.1)),
+            count: 0,
+            total: TimeDelta::zero(),
+        })
+        .collect();
+    for v in videos {
+        if let Some(b) = buckets.iter_mut().rev().find(|b| v.delta >= b.lower) {
+            b.count += 1;
+            b.total += v.delta;
+        }
+    }
+    buckets
+}
+
+pub fn buckets_csv(buckets: &[Bucket]) -> String {
+    let mut out = String::from(
+        "#bucket_label,bucket_lower_seconds,bucket_upper_seconds,video_count,total_seconds\n",
+    );
+    for b in buckets {
+        out.push_str(&format!(
+            "{},{},{},{},{}\n",
+            b.label,
+            b.lower.num_seconds(),
+            b.upper
+                .map(|u| u.num_seconds().to_string())
+                .unwrap_or_default(),
+            b.count,
+            b.total.num_seconds()
+        ));
+    }
+    out
+}
+
+pub struct Month {
+    pub year: i32,
+    pub month: u32,
+    pub count: usize,
+    pub total: TimeDelta,
+}
+
+//Chronological by the month of publishing in the given timezone, with the empty ones in between if filling gaps
+pub fn month_buckets(videos: &[Video], timezone: Tz, fill_gaps: bool) -> Vec<Month> {
+    let mut months = std::collections::BTreeMap::new();
+    for v in videos {
+        let date = v.date.with_timezone(&timezone);
+        let m = months
+            .entry((date.year(), date.month()))
+            .or_insert((0, TimeDelta::zero()));
+        m.0 += 1;
+        m.1 += v.delta;
+    }
+    if let (true, Some(&first), Some(&last)) =
+        (fill_gaps, months.keys().next(), months.keys().next_back())
+    {
+        let mut month = first;
+        while month < last {
+            month = match month {
+                (y, 12) => (y + 1, 1),
+                (y, m) => (y, m + 1),
+            };
+            months.entry(month).or_insert((0, TimeDelta::zero()));
+        }
+    }
+    months
+        .into_iter()
+        .map(|((year, month), (count, total))| Month {
+            year,
+            month,
+            count,
+            total,
+        })
+        .collect()
+}
+
+pub fn months_csv(months: &[Month]) -> String {
+    let mut out = String::from("#year,month,video_count,total_seconds\n");
+    for m in months {
+        out.push_str(&format!(
+            "{},{},{},{}\n",
+            m.year,
+            m.month,
+            m.count,
+            m.total.num_seconds()
+        ));
+    }
+    out
+}
+
+//Video count and total runtime by the hour of publishing in the given timezone
+pub fn hour_buckets(videos: &[Video], timezone: Tz) -> [(usize, TimeDelta); 24] {
+    let mut hours = [(0, TimeDelta::zero()); 24];
+    for v in videos {
+        let h = &mut hours[v.date.with_timezone(&timezone).hour() as usize];
+        h.0 += 1;
+        h.1 += v.delta;
+    }
+    hours
+}
+
+pub fn hour_table(hours: &[(usize, TimeDelta); 24]) -> String {
+    let width = hours
+        .iter()
+        .map(|h| format_hms(h.1).len())
+        .max()
+        .unwrap_or(0)
+        .max("Total".len());
+    let mut out = format!("Hour  Videos  {:>w$}\n", "Total", w = width);
+    for (hour, (count, total)) in hours.iter().enumerate() {
+        out.push_str(&format!(
+            "{:02}    {:>6}  {:>w$}\n",
+            hour,
+            count,
+            format_hms(*total),
+            w = width
+        ));
+    }
+    out
+}
+
+//Videos with captions and their runtime, out of all, with the ones not known either way
+#[derive(Debug, PartialEq)]
+pub struct Captions {
+    pub videos: usize,
+    pub captioned: usize,
+    pub unknown: usize,
+    pub total: TimeDelta,
+    pub captioned_total: TimeDelta,
+}
+
+pub fn captions(videos: &[Video]) -> Captions {
+    let captioned = videos.iter().filter(|v| v.has_captions == Some(true));
+    Captions {
+        videos: videos.len(),
+        captioned: captioned.clone().count(),
+        unknown: videos.iter().filter(|v| v.has_captions.is_none()).count(),
+        total: videos
+            .iter()
+            .fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+        captioned_total: captioned.fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+    }
+}
+
+pub fn captions_line(c: &Captions) -> String {
+    let share = |part: i64, all: i64| match all {
+        0 => String::new(),
+        _ => format!(" ({:.1}%)", part as f64 * 100.0 / all as f64),
+    };
+    let mut line = format!(
+        "Videos with captions: {} of {}{}, {} of {} runtime{}",
+        c.captioned,
+        c.videos,
+        share(c.captioned as i64, c.videos as i64),
+        format_hms(c.captioned_total),
+        format_hms(c.total),
+        share(c.captioned_total.num_seconds(), c.total.num_seconds()),
+    );
+    if c.unknown > 0 {
+        line.push_str(&format!(", unknown for {}", c.unknown));
+    }
+    line.push('.');
+    line
+}
+
+//Videos in HD and in SD and their runtime, out of all, with the ones not known either way
+#[derive(Debug, PartialEq)]
+pub struct Quality {
+    pub videos: usize,
+    pub hd: usize,
+    pub sd: usize,
+    pub total: TimeDelta,
+    pub hd_total: TimeDelta,
+    pub sd_total: TimeDelta,
+}
+
+pub fn quality(videos: &[Video]) -> Quality {
+    let of = |d: Definition| videos.iter().filter(move |v| v.definition == Some(d));
+    let sum = |d: Definition| of(d).fold(TimeDelta::zero(), |acc, v| acc + v.delta);
+    Quality {
+        videos: videos.len(),
+        hd: of(Definition::Hd).count(),
+        sd: of(Definition::Sd).count(),
+        total: videos
+            .iter()
+            .fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+        hd_total: sum(Definition::Hd),
+        sd_total: sum(Definition::Sd),
+    }
+}
+
+//One line each for HD and SD
+pub fn quality_lines(q: &Quality) -> String {
+    let share = |part: i64, all: i64| match all {
+        0 => String::new(),
+        _ => format!(" ({:.1}%)", part as f64 * 100.0 / all as f64),
+    };
+    let line = |name: &str, count: usize, total: TimeDelta| {
+        format!(
+            "{} videos: {} of {}{}, {} of {} runtime{}",
+            name,
+            count,
+            q.videos,
+            share(count as i64, q.videos as i64),
+            format_hms(total),
+            format_hms(q.total),
+            share(total.num_seconds(), q.total.num_seconds()),
+        )
+    };
+    let mut out = format!(
+        "{}.\n{}",
+        line("HD", q.hd, q.hd_total),
+        line("SD", q.sd, q.sd_total)
+    );
+    let unknown = q.videos - q.hd - q.sd;
+    if unknown > 0 {
+        out.push_str(&format!(", unknown for {}", unknown));
+    }
+    out.push_str(".\n");
+    out
+}
+
+//Per week on average, over the span the videos were published in
+#[derive(Debug, PartialEq)]
+pub struct Rate {
+    //At least one, so a few videos on the same day do not make for an absurd rate
+    pub weeks: f64,
+    pub videos: f64,
+    pub hours: f64,
+}
+
+//None for less than two videos, with no span to speak of
+pub fn rate(summary: &Summary) -> Option<Rate> {
+    let (first, last) = summary.published?;
+    if summary.count < 2 {
+        return None;
+    }
+    let weeks =
+        ((last - first).num_seconds() as f64 / TimeDelta::weeks(1).num_seconds() as f64).max(1.0);
+    Some(Rate {
+        weeks,
+        videos: summary.count as f64 / weeks,
+        hours: summary.total.num_seconds() as f64 / 3600.0 / weeks,
+    })
+}
+
+//The content per week as a duration if it is to be rounded, in hours otherwise
+pub fn rate_line(summary: &Summary, round: Option<Rounding>) -> String {
+    let Some(r) = rate(summary) else {
+        return String::from("Publishing rate: Not enough videos, at least two are needed.");
+    };
+    let day = |d: DateTime<Utc>| d.format("%Y-%m-%d").to_string();
+    let (first, last) = summary.published.unwrap();
+    let weeks = match last - first < TimeDelta::weeks(1) {
+        true => String::from("less than a week, taken as one"),
+        false => format!("{:.1} weeks", r.weeks),
+    };
+    let content = match round {
+        Some(round) => {
+            let weekly = round.apply(TimeDelta::milliseconds((r.hours * 3_600_000.0) as i64));
+            format!(
+                "{} of content per week",
+                crate::dissect_delta(weekly, crate::TimeBase::Hours, crate::lang::Lang::En)
+            )
+        }
+        None => format!("{:.1} hours/week of content", r.hours),
+    };
+    format!(
+        "Publishing rate: {:.1} videos/week, {} (over {}, {} to {}).",
+        r.videos,
+        content,
+        weeks,
+        day(first),
+        day(last)
+    )
+}
+
+//E.g. "Skipped videos: 3 (2 missing, 1 title-filter).", in the order of the reasons, None if nothing was skipped
+pub fn skipped_line(skipped: &[Skipped]) -> Option<String> {
+    let mut reasons: Vec<SkipReason> = skipped.iter().map(|s| s.reason).collect();
+    reasons.sort();
+    let mut counts = Vec::<(SkipReason, usize)>::new();
+    for r in reasons {
+        match counts.last_mut() {
+            Some((last, n)) if *last == r => *n += 1,
+            _ => counts.push((r, 1)),
+        }
+    }
+    let counts: Vec<String> = counts.iter().map(|(r, n)| format!("{} {}", n, r)).collect();
+    (!skipped.is_empty())
+        .then(|| format!("Skipped videos: {} ({}).", skipped.len(), counts.join(", ")))
+}
+
+pub fn skipped_csv(skipped: &[Skipped]) -> String {
+    let mut out = String::from("#video_id,reason,detail\n");
+    for s in skipped {
+        out.push_str(&format!(
+            "{},{},{}\n",
+            crate::csv::escape(&s.id),
+            s.reason,
+            crate::csv::escape(&s.detail)
+        ));
+    }
+    out
+}
+
+pub fn skipped_json(skipped: &[Skipped]) -> String {
+    let items: Vec<_> = skipped
+        .iter()
+        .map(|s| {
+            serde_json::json!({
+                "video_id": s.id,
+                "reason": s.reason.to_string(),
+                "detail": s.detail,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&items).unwrap() + "\n"
+}
+
+//Videos in 360° and in 3D and their runtime, out of all, with the ones not known either way
+#[derive(Debug, PartialEq)]
+pub struct Immersive {
+    pub videos: usize,
+    pub spherical: usize,
+    pub three_d: usize,
+    //Neither the dimension nor the projection in the response
+    pub unknown: usize,
+    pub total: TimeDelta,
+    pub spherical_total: TimeDelta,
+    pub three_d_total: TimeDelta,
+}
+
+pub fn immersive(videos: &[Video]) -> Immersive {
+    let spherical = videos
+        .iter()
+        .filter(|v| v.projection == Some(Projection::Spherical));
+    let three_d = videos
+        .iter()
+        .filter(|v| v.dimension == Some(Dimension::ThreeD));
+    Immersive {
+        videos: videos.len(),
+        spherical: spherical.clone().count(),
+        three_d: three_d.clone().count(),
+        unknown: videos
+            .iter()
+            .filter(|v| v.dimension.is_none() && v.projection.is_none())
+            .count(),
+        total: videos
+            .iter()
+            .fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+        spherical_total: spherical.fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+        three_d_total: three_d.fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+    }
+}
+
+//One line each for 360° and 3D
+pub fn immersive_lines(i: &Immersive) -> String {
+    let share = |part: i64, all: i64| match all {
+        0 => String::new(),
+        _ => format!(" ({:.1}%)", part as f64 * 100.0 / all as f64),
+    };
+    let line = |name: &str, count: usize, total: TimeDelta| {
+        format!(
+            "{} videos: {} of {}{}, {} of {} runtime{}",
+            name,
+            count,
+            i.videos,
+            share(count as i64, i.videos as i64),
+            format_hms(total),
+            format_hms(i.total),
+            share(total.num_seconds(), i.total.num_seconds()),
+        )
+    };
+    let mut out = format!(
+        "{}.\n{}",
+        line("360°", i.spherical, i.spherical_total),
+        line("3D", i.three_d, i.three_d_total)
+    );
+    if i.unknown > 0 {
+        out.push_str(&format!(", unknown for {}", i.unknown));
+    }
+    out.push_str(".\n");
+    out
+}
+
+//Of the current videos against the ones of an earlier output, matched by ID
+pub struct Diff<'a> {
+    pub added: Vec<&'a Video>,
+    pub removed: Vec<&'a Video>,
+    //The current total minus the earlier one
+    pub change: TimeDelta,
+}
+
+pub fn diff<'a>(previous: &'a [Video], current: &'a [Video]) -> Diff<'a> {
+    use std::collections::HashSet;
+
+    let ids = |videos: &'a [Video]| videos.iter().map(|v| v.id.as_str()).collect::<HashSet<_>>();
+    let (before, after) = (ids(previous), ids(current));
+    let total = |videos: &[Video]| {
+        videos
+            .iter()
+            .fold(TimeDelta::zero(), |acc, v| acc + v.delta)
+    };
+    Diff {
+        added: current
+            .iter()
+            .filter(|v| !before.contains(v.id.as_str()))
+            .collect(),
+        removed: previous
+            .iter()
+            .filter(|v| !after.contains(v.id.as_str()))
+            .collect(),
+        change: total(current) - total(previous),
+    }
+}
+
+//The new and the removed videos one per line, then the change of the runtime
+pub fn diff_lines(d: &Diff, lang: crate::lang::Lang) -> String {
+    let mut out = String::new();
+    for (name, mark, videos) in [("New", '+', &d.added), ("Removed", '-', &d.removed)] {
+        out.push_str(&format!("{} videos: {}\n", name, videos.len()));
+        for v in videos.iter() {
+            out.push_str(&format!(
+                "  {} {} {} '{}'\n",
+                mark,
+                v.date.format("%Y-%m-%d"),
+                v.id,
+                v.title
+            ));
+        }
+    }
+    //Dissected without the sign, as it takes no negative durations
+    let sign = match d.change {
+        c if c > TimeDelta::zero() => "+",
+        c if c < TimeDelta::zero() => "-",
+        _ => "",
+    };
+    let change = d.change.abs();
+    out.push_str(&format!(
+        "Runtime change: {}{} seconds",
+        sign,
+        change.num_seconds()
+    ));
+    if change >= TimeDelta::minutes(1) {
+        out.push_str(&format!(
+            ", or {}{}",
+            sign,
+            crate::dissect_delta(change, crate::TimeBase::Hours, lang)
+        ));
+    }
+    out.push('\n');
+    out
+}
+
+//For the 'stats' command, from the videos of an earlier output
+pub fn offline_stats(videos: &[Video], lang: crate::lang::Lang) -> String {
+    let total = videos
+        .iter()
+        .fold(TimeDelta::zero(), |acc, v| acc + v.delta);
+    let mut out = format!(
+        "Videos: {}\n{}\n",
+        videos.len(),
+        crate::total_line(total, lang)
+    );
+    if videos.is_empty() {
+        return out;
+    }
+
+    let day = |d: DateTime<Utc>| d.format("%Y-%m-%d").to_string();
+    //The first one of equal ones, as in the output
+    let longest = videos.iter().rev().max_by_key(|v| v.delta).unwrap();
+    let shortest = videos.iter().min_by_key(|v| v.delta).unwrap();
+    let first = videos.iter().map(|v| v.date).min().unwrap();
+    let last = videos.iter().map(|v| v.date).max().unwrap();
+    out.push_str(&format!(
+        "Average: {}\nLongest: {} '{}'\nShortest: {} '{}'\nPublished: {} to {}\n",
+        format_hms(total / videos.len() as i32),
+        format_hms(longest.delta),
+        longest.title,
+        format_hms(shortest.delta),
+        shortest.title,
+        day(first),
+        day(last),
+    ));
+    out
+}
+
+//Keep the format stable, scripts depend on it
+pub fn porcelain_line(
+    channel: &str,
+    summary: Result<&Summary, ()>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> String {
+    let date = |d: Option<DateTime<Utc>>| match d {
+        Some(d) if d.num_seconds_from_midnight() == 0 && d.nanosecond() == 0 => {
+            d.format("%Y-%m-%d").to_string()
+        }
+        Some(d) => d.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        None => String::from("-"),
+    };
+    let (videos, total, status) = match summary {
+        Ok(s) => (
+            s.count.to_string(),
+            s.total.num_seconds().to_string(),
+            //The totals are given in any case, but only an 'ok' one is complete
+            match (s.interrupted, s.warning_count(), s.count) {
+                (true, _, _) => "interrupted",
+                (false, 1.., _) => "partial",
+                (false, 0, 0) => "empty",
+                (false, 0, _) => "ok",
+            },
+        ),
+        Err(()) => (String::from("-"), String::from("-"), "error"),
+    };
+
+    format!(
+        "channel={} videos={} total_seconds={} start={} end={} status={}",
+        shell_quote(channel),
+        videos,
+        total,
+        date(start),
+        date(end),
+        status
+    )
+}
+
+//For '--channel-stats', in the same form as porcelain_line()
+pub fn stats_porcelain_line(stats: &crate::ChannelStats) -> String {
+    format!(
+        "channel={} subscribers={} videos={} views={} title={}",
+        shell_quote(&format!("@{}", stats.handle)),
+        stats
+            .subscriber_count
+            .map(|n| n.to_string())
+            .unwrap_or(String::from("-")),
+        stats.video_count,
+        stats.view_count,
+        shell_quote(&stats.title)
+    )
+}
+
+pub fn shell_quote(s: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "@%+-./:=_,".contains(c);
+    if !s.is_empty() && s.chars().all(plain) {
+        String::from(s)
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod report_test {
+    use super::*;
+    use crate::{ChannelInfo, Video};
+    use chrono::{DateTime, Utc};
+
+    fn summary(videos: &[(&str, &str)]) -> Summary {
+        let videos: Vec<Video> = videos
+            .iter()
+            .map(|(date, duration)| {
+                let date = DateTime::<Utc>::from(DateTime::parse_from_rfc3339(date).unwrap());
+                Video::new(date, String::new(), String::new(), duration.to_string()).unwrap()
+            })
+            .collect();
+        let published = videos
+            .iter()
+            .map(|v| v.date)
+            .min()
+            .zip(videos.iter().map(|v| v.date).max());
+        Summary {
+            channel: ChannelInfo {
+                id: String::new(),
+                title: String::new(),
+                uploads_playlist_id: String::new(),
+                video_count: videos.len() as u64,
+            },
+            count: videos.len(),
+            total: videos
+                .iter()
+                .fold(TimeDelta::zero(), |acc, v| acc + v.delta),
+            views: None,
+            videos,
+            output: None,
+            extra_outputs: Vec::new(),
+            retries: 0,
+            warnings: 0,
+            uploads: None,
+            published,
+            skipped: Vec::new(),
+            interrupted: false,
+        }
+    }
+
+    #[test]
+    fn offline_stats_test() {
+        let mut s = summary(&[
+            ("2024-03-01T10:00:00Z", "PT1M"),
+            ("2024-01-15T10:00:00Z", "PT1H"),
+            ("2024-02-01T10:00:00Z", "PT29M"),
+        ]);
+        for (v, t) in s.videos.iter_mut().zip(["a", "b", "c"]) {
+            v.title = String::from(t);
+        }
+        assert_eq!(
+            offline_stats(&s.videos, crate::lang::Lang::En),
+            "Videos: 3\n\
+            Sum total: 5400 seconds, or 1 hour 30 minutes\n\
+            Average: 0:30:00\n\
+            Longest: 1:00:00 'b'\n\
+            Shortest: 0:01:00 'a'\n\
+            Published: 2024-01-15 to 2024-03-01\n"
+        );
+        assert_eq!(
+            offline_stats(&[], crate::lang::Lang::En),
+            "Videos: 0\nSum total: 0 seconds\n"
+        );
+    }
+
+    #[test]
+    fn porcelain_test() {
+        let date = |s| {
+            Some(DateTime::<Utc>::from(
+                DateTime::parse_from_rfc3339(s).unwrap(),
+            ))
+        };
+        let full = summary(&[
+            ("2024-03-01T10:00:00Z", "PT1H1S"),
+            ("2024-02-01T10:00:00Z", "PT30M"),
+        ]);
+        let empty = summary(&[]);
+        let mut partial = summary(&[("2024-03-01T10:00:00Z", "PT1H1S")]);
+        partial.warnings = 1;
+        let mut empty_partial = summary(&[]);
+        empty_partial.warnings = 2;
+        let mut interrupted = summary(&[("2024-03-01T10:00:00Z", "PT1H1S")]);
+        interrupted.interrupted = true;
+        interrupted.warnings = 1;
+
+        let tests = [
+            ("@foo", Ok(&full), date("2024-01-01T00:00:00Z"), None,
+                "channel=@foo videos=2 total_seconds=5401 start=2024-01-01 end=- status=ok"),
+            ("@foo", Ok(&empty), None, date("2024-01-01T12:30:00+02:00"),
+                "channel=@foo videos=0 total_seconds=0 start=- end=2024-01-01T10:30:00Z status=empty"),
+            ("@foo", Err(()), date("2024-01-01T00:00:00Z"), date("2024-12-31T00:00:00Z"),
+                "channel=@foo videos=- total_seconds=- start=2024-01-01 end=2024-12-31 status=error"),
+            ("@it's me", Ok(&full), None, None,
+                "channel='@it'\\''s me' videos=2 total_seconds=5401 start=- end=- status=ok"),
+            ("@foo", Ok(&partial), None, None,
+                "channel=@foo videos=1 total_seconds=3601 start=- end=- status=partial"),
+            ("@foo", Ok(&empty_partial), None, None,
+                "channel=@foo videos=0 total_seconds=0 start=- end=- status=partial"),
+            ("@foo", Ok(&interrupted), None, None,
+                "channel=@foo videos=1 total_seconds=3601 start=- end=- status=interrupted"),
+        ];
+
+        for (c, s, start, end, r) in tests {
+            assert_eq!(porcelain_line(c, s, start, end), r);
+        }
+    }
+
+    #[test]
+    fn stats_porcelain_test() {
+        let mut stats = crate::ChannelStats {
+            handle: String::from("foo"),
+            title: String::from("Foo's videos"),
+            subscriber_count: Some(1200),
+            video_count: 42,
+            view_count: 98765,
+        };
+        assert_eq!(
+            stats_porcelain_line(&stats),
+            "channel=@foo subscribers=1200 videos=42 views=98765 title='Foo'\\''s videos'"
+        );
+        stats.subscriber_count = None;
+        stats.title = String::from("Foo");
+        assert_eq!(
+            stats_porcelain_line(&stats),
+            "channel=@foo subscribers=- videos=42 views=98765 title=Foo"
+        );
+    }
+
+    #[test]
+    fn buckets_test() {
+        let s = summary(&[
+            ("2024-01-01T00:00:00Z", "PT0S"),
+            ("2024-01-01T00:00:00Z", "PT59S"),
+            ("2024-01-01T00:00:00Z", "PT1M"),
+            ("2024-01-01T00:00:00Z", "PT4M59S"),
+            ("2024-01-01T00:00:00Z", "PT30M"),
+            ("2024-01-01T00:00:00Z", "PT2H"),
+            ("2024-01-01T00:00:00Z", "P1DT1S"),
+        ]);
+
+        assert_eq!(
+            buckets_csv(&duration_buckets(&s.videos)),
+            "#bucket_label,bucket_lower_seconds,bucket_upper_seconds,video_count,total_seconds\n\
+            <1m,0,60,2,59\n\
+            1-5m,60,300,2,359\n\
+            5-10m,300,600,0,0\n\
+            10-20m,600,1200,0,0\n\
+            20-30m,1200,1800,0,0\n\
+            30-60m,1800,3600,1,1800\n\
+            1-2h,3600,7200,0,0\n\
+            >2h,7200,,2,93601\n"
+        );
+        assert!(duration_buckets(&[]).iter().all(|b| b.count == 0));
+    }
+
+    #[test]
+    fn months_test() {
+        let s = summary(&[
+            ("2024-03-10T00:00:00Z", "PT1M"),
+            ("2023-11-30T23:30:00Z", "PT10M"),
+            ("2024-03-01T12:00:00Z", "PT2M"),
+            ("2024-01-15T00:00:00Z", "PT1H"),
+        ]);
+        let tests = [
+            (
+                Tz::UTC,
+                false,
+                "#year,month,video_count,total_seconds\n\
+                2023,11,1,600\n\
+                2024,1,1,3600\n\
+                2024,3,2,180\n",
+            ),
+            (
+                Tz::UTC,
+                true,
+                "#year,month,video_count,total_seconds\n\
+                2023,11,1,600\n\
+                2023,12,0,0\n\
+                2024,1,1,3600\n\
+                2024,2,0,0\n\
+                2024,3,2,180\n",
+            ),
+            //Already December there
+            (
+                Tz::Europe__Berlin,
+                true,
+                "#year,month,video_count,total_seconds\n\
+                2023,12,1,600\n\
+                2024,1,1,3600\n\
+                2024,2,0,0\n\
+                2024,3,2,180\n",
+            ),
+        ];
+        for (timezone, fill_gaps, expected) in tests {
+            assert_eq!(
+                months_csv(&month_buckets(&s.videos, timezone, fill_gaps)),
+                expected,
+                "{} {}",
+                timezone,
+                fill_gaps
+            );
+        }
+        assert!(month_buckets(&[], Tz::UTC, true).is_empty());
+    }
+
+    #[test]
+    fn captions_test() {
+        let mut s = summary(&[
+            ("2024-01-01T00:00:00Z", "PT1H"),
+            ("2024-01-01T00:00:00Z", "PT30M"),
+            ("2024-01-01T00:00:00Z", "PT20M"),
+            ("2024-01-01T00:00:00Z", "PT10M"),
+        ]);
+        for (v, c) in s
+            .videos
+            .iter_mut()
+            .zip([Some(true), Some(false), Some(true), None])
+        {
+            v.has_captions = c;
+        }
+
+        let c = captions(&s.videos);
+        assert_eq!(
+            c,
+            Captions {
+                videos: 4,
+                captioned: 2,
+                unknown: 1,
+                total: TimeDelta::hours(2),
+                captioned_total: TimeDelta::minutes(80),
+            }
+        );
+        assert_eq!(
+            captions_line(&c),
+            "Videos with captions: 2 of 4 (50.0%), 1:20:00 of 2:00:00 runtime (66.7%), unknown for 1."
+        );
+        assert_eq!(
+            captions_line(&captions(&[])),
+            "Videos with captions: 0 of 0, 0:00:00 of 0:00:00 runtime."
+        );
+    }
+
+    #[test]
+    fn quality_test() {
+        let mut s = summary(&[
+            ("2024-01-01T00:00:00Z", "PT1H"),
+            ("2024-01-01T00:00:00Z", "PT30M"),
+            ("2024-01-01T00:00:00Z", "PT20M"),
+            ("2024-01-01T00:00:00Z", "PT10M"),
+        ]);
+        for (v, d) in s.videos.iter_mut().zip([
+            Some(Definition::Hd),
+            Some(Definition::Sd),
+            Some(Definition::Hd),
+            None,
+        ]) {
+            v.definition = d;
+        }
+
+        let q = quality(&s.videos);
+        assert_eq!(
+            q,
+            Quality {
+                videos: 4,
+                hd: 2,
+                sd: 1,
+                total: TimeDelta::hours(2),
+                hd_total: TimeDelta::minutes(80),
+                sd_total: TimeDelta::minutes(30),
+            }
+        );
+        assert_eq!(
+            quality_lines(&q),
+            "HD videos: 2 of 4 (50.0%), 1:20:00 of 2:00:00 runtime (66.7%).\n\
+            SD videos: 1 of 4 (25.0%), 0:30:00 of 2:00:00 runtime (25.0%), unknown for 1.\n"
+        );
+        assert_eq!(
+            quality_lines(&quality(&[])),
+            "HD videos: 0 of 0, 0:00:00 of 0:00:00 runtime.\nSD videos: 0 of 0, 0:00:00 of 0:00:00 runtime.\n"
+        );
+    }
+
+    #[test]
+    fn diff_test() {
+        let videos = |list: &[(&str, &str)]| {
+            let mut s = summary(
+                &list
+                    .iter()
+                    .map(|(_, duration)| ("2024-01-01T00:00:00Z", *duration))
+                    .collect::<Vec<_>>(),
+            );
+            for (v, (id, _)) in s.videos.iter_mut().zip(list) {
+                v.id = id.to_string();
+                v.title = format!("Video {}", id);
+            }
+            s.videos
+        };
+        let previous = videos(&[("a", "PT10M"), ("b", "PT1H"), ("c", "PT5M")]);
+        let tests = [
+            (
+                videos(&[("a", "PT10M"), ("b", "PT1H"), ("c", "PT5M")]),
+                "New videos: 0\n\
+                Removed videos: 0\n\
+                Runtime change: 0 seconds\n",
+            ),
+            (
+                videos(&[("d", "PT1H30S"), ("a", "PT10M"), ("b", "PT1H")]),
+                "New videos: 1\n  \
+                + 2024-01-01 d 'Video d'\n\
+                Removed videos: 1\n  \
+                - 2024-01-01 c 'Video c'\n\
+                Runtime change: +3330 seconds, or +55 minutes 30 seconds\n",
+            ),
+            (
+                videos(&[("a", "PT10M")]),
+                "New videos: 0\n\
+                Removed videos: 2\n  \
+                - 2024-01-01 b 'Video b'\n  \
+                - 2024-01-01 c 'Video c'\n\
+                Runtime change: -3900 seconds, or -1 hour 5 minutes\n",
+            ),
+            (
+                videos(&[("a", "PT10M"), ("b", "PT1H"), ("c", "PT5M"), ("e", "PT30S")]),
+                "New videos: 1\n  \
+                + 2024-01-01 e 'Video e'\n\
+                Removed videos: 0\n\
+                Runtime change: +30 seconds\n",
+            ),
+        ];
+        for (current, expected) in tests {
+            let d = diff(&previous, &current);
+            assert_eq!(diff_lines(&d, crate::lang::Lang::En), expected);
+        }
+    }
+
+    #[test]
+    fn rate_test() {
+        let tests = [
+            (
+                vec![
+                    ("2024-01-01T00:00:00Z", "PT1H"),
+                    ("2024-01-15T00:00:00Z", "PT30M"),
+                    ("2024-01-29T00:00:00Z", "PT1H30M"),
+                    ("2024-01-08T00:00:00Z", "PT1H"),
+                ],
+                Some((4.0, 1.0, 1.0)),
+                "Publishing rate: 1.0 videos/week, 1.0 hours/week of content (over 4.0 weeks, 2024-01-01 to 2024-01-29).",
+            ),
+            (
+                vec![
+                    ("2024-03-01T10:00:00Z", "PT10M"),
+                    ("2024-03-02T10:00:00Z", "PT20M"),
+                    ("2024-03-01T12:00:00Z", "PT30M"),
+                ],
+                Some((1.0, 3.0, 1.0)),
+                "Publishing rate: 3.0 videos/week, 1.0 hours/week of content (over less than a week, taken as one, 2024-03-01 to 2024-03-02).",
+            ),
+            (
+                vec![("2024-03-01T10:00:00Z", "PT10M")],
+                None,
+                "Publishing rate: Not enough videos, at least two are needed.",
+            ),
+            (
+                vec![],
+                None,
+                "Publishing rate: Not enough videos, at least two are needed.",
+            ),
+        ];
+        for (videos, expected, line) in tests {
+            let s = summary(&videos);
+            assert_eq!(
+                rate(&s).map(|r| (r.weeks, r.videos, r.hours)),
+                expected,
+                "{:?}",
+                videos
+            );
+            assert_eq!(rate_line(&s, None), line);
+        }
+    }
+
+    #[test]
+    fn rounded_rate_test() {
+        //1905.67 seconds per week
+        let s = summary(&[
+            ("2024-01-01T00:00:00Z", "PT1H"),
+            ("2024-01-22T00:00:00Z", "PT35M17S"),
+        ]);
+        let tests = [
+            (Rounding::Second, "31 minutes 46 seconds"),
+            (Rounding::Minute, "32 minutes"),
+            (Rounding::Hour, "1 hour"),
+        ];
+        for (round, content) in tests {
+            assert_eq!(
+                rate_line(&s, Some(round)),
+                format!("Publishing rate: 0.7 videos/week, {} of content per week (over 3.0 weeks, 2024-01-01 to 2024-01-22).", content)
+            );
+        }
+    }
+
+    #[test]
+    fn skipped_test() {
+        let skipped = |id: &str, reason, detail: &str| Skipped {
+            id: String::from(id),
+            reason,
+            detail: String::from(detail),
+        };
+        let list = [
+            skipped(
+                "a",
+                SkipReason::TitleFilter,
+                "Not matching the title filter",
+            ),
+            skipped("b", SkipReason::Missing, "Not in the response"),
+            skipped(
+                "c",
+                SkipReason::UnparseableDuration,
+                "Could not parse 'duration' field 'PT1X'",
+            ),
+            skipped("d", SkipReason::Missing, "Not in the response, or deleted"),
+        ];
+
+        assert_eq!(
+            skipped_line(&list).as_deref(),
+            Some("Skipped videos: 4 (2 missing, 1 unparseable-duration, 1 title-filter).")
+        );
+        assert_eq!(skipped_line(&[]), None);
+
+        assert_eq!(
+            skipped_csv(&list[1..]),
+            "#video_id,reason,detail\n\
+             b,missing,Not in the response\n\
+             c,unparseable-duration,Could not parse 'duration' field 'PT1X'\n\
+             d,missing,\"Not in the response, or deleted\"\n"
+        );
+        assert_eq!(skipped_csv(&[]), "#video_id,reason,detail\n");
+
+        let json: serde_json::Value = serde_json::from_str(&skipped_json(&list[..2])).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "video_id": "a",
+                    "reason": "title-filter",
+                    "detail": "Not matching the title filter",
+                },
+                { "video_id": "b", "reason": "missing", "detail": "Not in the response" },
+            ])
+        );
+        assert_eq!(skipped_json(&[]), "[]\n");
+    }
+
+    #[test]
+    fn immersive_test() {
+        let mut s = summary(&[
+            ("2024-01-01T00:00:00Z", "PT1H"),
+            ("2024-01-01T00:00:00Z", "PT30M"),
+            ("2024-01-01T00:00:00Z", "PT20M"),
+            ("2024-01-01T00:00:00Z", "PT10M"),
+        ]);
+        for (v, (d, p)) in s.videos.iter_mut().zip([
+            (Some(Dimension::ThreeD), Some(Projection::Spherical)),
+            (Some(Dimension::TwoD), Some(Projection::Spherical)),
+            (Some(Dimension::TwoD), Some(Projection::Rectangular)),
+            (None, None),
+        ]) {
+            v.dimension = d;
+            v.projection = p;
+        }
+
+        let i = immersive(&s.videos);
+        assert_eq!(
+            i,
+            Immersive {
+                videos: 4,
+                spherical: 2,
+                three_d: 1,
+                unknown: 1,
+                total: TimeDelta::hours(2),
+                spherical_total: TimeDelta::minutes(90),
+                three_d_total: TimeDelta::hours(1),
+            }
+        );
+        assert_eq!(
+            immersive_lines(&i),
+            "360° videos: 2 of 4 (50.0%), 1:30:00 of 2:00:00 runtime (75.0%).\n\
+            3D videos: 1 of 4 (25.0%), 1:00:00 of 2:00:00 runtime (50.0%), unknown for 1.\n"
+        );
+        assert_eq!(
+            immersive_lines(&immersive(&[])),
+            "360° videos: 0 of 0, 0:00:00 of 0:00:00 runtime.\n3D videos: 0 of 0, 0:00:00 of 0:00:00 runtime.\n"
+        );
+    }
+
+    #[test]
+    fn hour_test() {
+        let s = summary(&[
+            ("2024-01-01T09:59:59Z", "PT1M"),
+            ("2024-07-01T09:00:00Z", "PT30M"),
+            ("2024-01-01T23:30:00Z", "PT1H"),
+        ]);
+
+        let utc = hour_buckets(&s.videos, Tz::UTC);
+        assert_eq!(utc[9], (2, TimeDelta::minutes(31)));
+        assert_eq!(utc[23], (1, TimeDelta::hours(1)));
+        assert_eq!(utc.iter().map(|h| h.0).sum::<usize>(), 3);
+
+        //Daylight saving time applies in July
+        let local = hour_buckets(&s.videos, chrono_tz::Europe::Budapest);
+        assert_eq!(local[10], (1, TimeDelta::minutes(1)));
+        assert_eq!(local[11], (1, TimeDelta::minutes(30)));
+        assert_eq!(local[0], (1, TimeDelta::hours(1)));
+
+        let table = hour_table(&utc);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 25);
+        assert_eq!(lines[0], "Hour  Videos    Total");
+        assert_eq!(lines[1], "00         0  0:00:00");
+        assert_eq!(lines[10], "09         2  0:31:00");
+        assert_eq!(lines[24], "23         1  1:00:00");
+    }
+
+    #[test]
+    fn compare_test() {
+        let summaries = [
+            (
+                String::from("@small"),
+                summary(&[("2024-05-01T10:00:00Z", "PT10M")]),
+            ),
+            (String::from("@empty"), summary(&[])),
+            (
+                String::from("@big"),
+                summary(&[
+                    ("2024-03-01T10:00:00Z", "PT1H1S"),
+                    ("2023-12-31T23:00:00Z", "P4DT1M"),
+                    ("2024-02-01T10:00:00Z", "PT30M"),
+                ]),
+            ),
+        ];
+
+        assert_eq!(
+            compare_table(&summaries, None),
+            "\
+Channel  Videos     Total   Average       First        Last
+@big          3  97:31:01  32:30:20  2023-12-31  2024-03-01
+@small        1   0:10:00   0:10:00  2024-05-01  2024-05-01
+@empty        0   0:00:00   0:00:00           -           -
+"
+        );
+        assert!(compare_table(&summaries, Some(Rounding::Minute)).contains(" 97:31:01  32:30:00 "));
+        assert!(compare_table(&summaries, Some(Rounding::Hour)).contains(" 97:31:01  33:00:00 "));
+    }
+}
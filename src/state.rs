@@ -0,0 +1,210 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to remember the newest video of the last successful run, for '--since-last-run'
+
+    The state file is JSON, by default at $XDG_CONFIG_HOME/yt-api-videosum/state.json (~/.config/... if the variable is
+    not set), with one entry per handle (in lowercase), e.g.:
+        {"version":1,"channels":{"somechannel":{"newest":"2024-03-01T00:00:00Z"}}}
+    A file of another version is taken as damaged, and is never overwritten, so the entries of the other channels are
+    not lost: each run only warns about it until it is fixed or removed.
+*/
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+pub const VERSION: u64 = 1;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct State {
+    channels: BTreeMap<String, DateTime<Utc>>,
+}
+
+//Where to record the run of the channel, see Config::state
+#[derive(Clone, Debug)]
+pub struct LastRun {
+    pub path: PathBuf,
+    pub channel: String,
+}
+
+pub fn default_path(xdg_config_home: Option<&str>, home: Option<&str>) -> Option<PathBuf> {
+    Some(crate::keyfile::config_dir(xdg_config_home, home)?.join("state.json"))
+}
+
+//Strictly after the newest video seen, so it is not fetched again
+pub fn start_after(newest: DateTime<Utc>) -> DateTime<Utc> {
+    newest + TimeDelta::seconds(1)
+}
+
+impl State {
+    //A missing file is an empty state, while a damaged one is an error
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.to_string()),
+        };
+        parse(&content)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let channels: serde_json::Map<String, serde_json::Value> = self
+            .channels
+            .iter()
+            .map(|(handle, newest)| {
+                (
+                    handle.clone(),
+                    serde_json::json!({
+                        "newest": newest.to_rfc3339_opts(SecondsFormat::Secs, true)
+                    }),
+                )
+            })
+            .collect();
+        let content = serde_json::json!({ "version": VERSION, "channels": channels });
+
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        //Replaced as a whole, so an interrupted write leaves the previous state
+        let partial = PathBuf::from(format!("{}.partial", path.display()));
+        std::fs::write(&partial, format!("{}\n", content)).map_err(|e| e.to_string())?;
+        std::fs::rename(&partial, path).map_err(|e| e.to_string())
+    }
+
+    pub fn newest(&self, channel: &str) -> Option<DateTime<Utc>> {
+        self.channels.get(&channel.to_lowercase()).copied()
+    }
+
+    pub fn set(&mut self, channel: &str, newest: DateTime<Utc>) {
+        self.channels.insert(channel.to_lowercase(), newest);
+    }
+
+    //Whether there was anything to forget
+    pub fn remove(&mut self, channel: &str) -> bool {
+        self.channels.remove(&channel.to_lowercase()).is_some()
+    }
+}
+
+fn parse(content: &str) -> Result<State, String> {
+    let json: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    match json.get("version").and_then(|v| v.as_u64()) {
+        Some(VERSION) => (),
+        Some(v) => return Err(format!("Unsupported version {}", v)),
+        None => return Err(String::from("Could not find 'version' field")),
+    }
+
+    let mut state = State::default();
+    let channels = json
+        .get("channels")
+        .and_then(|c| c.as_object())
+        .ok_or("Invalid 'channels' format")?;
+    for (handle, entry) in channels {
+        let newest = entry
+            .get("newest")
+            .and_then(|n| n.as_str())
+            .and_then(|n| DateTime::parse_from_rfc3339(n).ok())
+            .ok_or(format!("Invalid entry of '{}'", handle))?;
+        state.set(handle, newest.into());
+    }
+    Ok(state)
+}
+
+//Keeps the entries of the other channels, and leaves a damaged file as it is
+pub fn record(last_run: &LastRun, newest: DateTime<Utc>) -> Result<(), String> {
+    let mut state =
+        State::load(&last_run.path).map_err(|e| format!("{}, the file is left unchanged", e))?;
+    state.set(&last_run.channel, newest);
+    state.save(&last_run.path)
+}
+
+#[cfg(test)]
+mod state_test {
+    use super::*;
+
+    #[test]
+    fn default_path_test() {
+        let tests = [
+            (
+                Some("/xdg"),
+                Some("/home/u"),
+                Some("/xdg/yt-api-videosum/state.json"),
+            ),
+            (
+                None,
+                Some("/home/u"),
+                Some("/home/u/.config/yt-api-videosum/state.json"),
+            ),
+            (
+                Some("relative"),
+                Some("/home/u"),
+                Some("/home/u/.config/yt-api-videosum/state.json"),
+            ),
+            (None, Some(""), None),
+            (None, None, None),
+        ];
+        for (xdg, home, path) in tests {
+            assert_eq!(default_path(xdg, home), path.map(PathBuf::from));
+        }
+    }
+
+    #[test]
+    fn load_test() {
+        let dir =
+            std::env::temp_dir().join(format!("yt_api_videosum_state_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("sub").join("state.json");
+        let date = |s| DateTime::<Utc>::from(DateTime::parse_from_rfc3339(s).unwrap());
+
+        assert_eq!(State::load(&path), Ok(State::default()));
+        let mut state = State::default();
+        state.set("Foo", date("2024-03-01T10:00:00+01:00"));
+        state.set("bar", date("2024-01-01T00:00:00Z"));
+        state.save(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"channels\":{\"bar\":{\"newest\":\"2024-01-01T00:00:00Z\"},\
+            \"foo\":{\"newest\":\"2024-03-01T09:00:00Z\"}},\"version\":1}\n"
+        );
+
+        let mut loaded = State::load(&path).unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.newest("FOO"), Some(date("2024-03-01T09:00:00Z")));
+        assert!(loaded.remove("foo"));
+        assert!(!loaded.remove("foo"));
+        assert_eq!(loaded.newest("foo"), None);
+
+        let last_run = LastRun {
+            path: path.clone(),
+            channel: String::from("baz"),
+        };
+        record(&last_run, date("2025-01-01T00:00:00Z")).unwrap();
+        let loaded = State::load(&path).unwrap();
+        assert_eq!(loaded.newest("bar"), Some(date("2024-01-01T00:00:00Z")));
+        assert_eq!(loaded.newest("baz"), Some(date("2025-01-01T00:00:00Z")));
+
+        let damaged = [
+            "",
+            "{",
+            "{\"channels\":{}}",
+            "{\"version\":2,\"channels\":{}}",
+            "{\"version\":1}",
+            "{\"version\":1,\"channels\":{\"foo\":{\"newest\":\"yesterday\"}}}",
+        ];
+        for content in damaged {
+            std::fs::write(&path, content).unwrap();
+            assert!(State::load(&path).is_err(), "{:?}", content);
+            //Not replaced by a run, which would lose the entries of the other channels
+            assert!(record(&last_run, date("2025-01-01T00:00:00Z")).is_err());
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
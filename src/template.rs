@@ -0,0 +1,260 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to write the videos in a user-defined line format
+
+    The template is rendered once per video, placeholders are the column names in braces (see COLUMNS), and literal
+    braces are written as "{{" and "}}". The template is parsed up front, so an invalid one fails before any network
+    call.
+*/
+
+use std::error::Error;
+use std::io::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::output::{DateFormat, RowWriter};
+use crate::Video;
+
+pub const COLUMNS: [&str; 8] = [
+    "date",
+    "title",
+    "id",
+    "url",
+    "duration",
+    "duration_seconds",
+    "duration_hms",
+    "views",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Piece {
+    Text(String),
+    Column(&'static str),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template(Vec<Piece>);
+impl std::str::FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("Unclosed placeholder in template '{}'", s));
+                    }
+                    let column = COLUMNS.iter().find(|c| **c == name).ok_or_else(|| {
+                        format!(
+                            "Unknown placeholder '{{{}}}' in template, expected one of {}",
+                            name,
+                            COLUMNS.map(|c| format!("{{{}}}", c)).join(", ")
+                        )
+                    })?;
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Column(column));
+                }
+                '}' => {
+                    return Err(format!(
+                        "Unmatched '}}' in template '{}', use '}}}}' for a literal one",
+                        s
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+
+        Ok(Self(pieces))
+    }
+}
+impl Template {
+    pub fn render(&self, video: &Video, date: Option<&DateFormat>) -> String {
+        let mut out = String::new();
+        for p in &self.0 {
+            match p {
+                Piece::Text(t) => out.push_str(t),
+                Piece::Column(c) => out.push_str(&column(video, c, date)),
+            }
+        }
+        out
+    }
+}
+
+fn column(video: &Video, name: &str, date: Option<&DateFormat>) -> String {
+    match name {
+        "date" => match date {
+            Some(d) => d.render(video.date),
+            None => video.date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        },
+        "title" => video.title.clone(),
+        "id" => video.id.clone(),
+        "url" => video.url(),
+        "duration" => video.duration.clone(),
+        "duration_seconds" => video.delta.num_seconds().to_string(),
+        "duration_hms" => crate::report::format_hms(video.delta),
+        "views" => video.views.map(|v| v.to_string()).unwrap_or_default(),
+        _ => unreachable!("Column '{}' is not handled", name),
+    }
+}
+
+pub struct Writer<'a, W: Write> {
+    out: W,
+    template: &'a Template,
+    date: Option<&'a DateFormat>,
+    count: usize,
+    total: chrono::TimeDelta,
+}
+impl<'a, W: Write> Writer<'a, W> {
+    pub fn new(out: W, template: &'a Template, date: Option<&'a DateFormat>) -> Self {
+        Self {
+            out,
+            template,
+            date,
+            count: 0,
+            total: chrono::TimeDelta::zero(),
+        }
+    }
+}
+impl<W: Write> RowWriter<W> for Writer<'_, W> {
+    fn write(&mut self, video: &Video) -> Result<(), Box<dyn Error>> {
+        writeln!(self.out, "{}", self.template.render(video, self.date))?;
+        self.out.flush()?;
+        self.count += 1;
+        self.total += video.delta;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>, footer: Option<DateTime<Utc>>) -> Result<W, Box<dyn Error>> {
+        if let Some(generated) = footer {
+            writeln!(
+                self.out,
+                "{}",
+                crate::csv::footer_line(self.count, self.total, generated)
+            )?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod template_test {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let text = |s: &str| Piece::Text(String::from(s));
+        let col = Piece::Column;
+
+        let tests = [
+            ("", Ok(vec![])),
+            ("plain", Ok(vec![text("plain")])),
+            ("{title}", Ok(vec![col("title")])),
+            ("{date}{title}", Ok(vec![col("date"), col("title")])),
+            ("{id} end", Ok(vec![col("id"), text(" end")])),
+            ("start {id}", Ok(vec![text("start "), col("id")])),
+            ("{{title}}", Ok(vec![text("{title}")])),
+            ("{{{title}}}", Ok(vec![text("{"), col("title"), text("}")])),
+            ("a }} b {{", Ok(vec![text("a } b {")])),
+            (
+                "{date} | {duration_hms} | {title}",
+                Ok(vec![
+                    col("date"),
+                    text(" | "),
+                    col("duration_hms"),
+                    text(" | "),
+                    col("title"),
+                ]),
+            ),
+            ("{}", Err(())),
+            ("{Title}", Err(())),
+            ("{unknown}", Err(())),
+            ("{title", Err(())),
+            ("{title} {title", Err(())),
+            ("{", Err(())),
+            ("}", Err(())),
+            ("title}", Err(())),
+        ];
+
+        for (t, r) in tests {
+            assert_eq!(
+                t.parse::<Template>().map_err(|_| ()),
+                r.map(Template),
+                "template=\"{}\"",
+                t
+            );
+        }
+
+        let e = "{x}".parse::<Template>().unwrap_err();
+        assert!(
+            COLUMNS.iter().all(|c| e.contains(&format!("{{{}}}", c))),
+            "{}",
+            e
+        );
+    }
+
+    #[test]
+    fn render_test() {
+        let date =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-01-02T03:04:05Z").unwrap());
+        let video = Video::new(
+            date,
+            String::from("Some {title}"),
+            String::from("abc"),
+            String::from("PT1H2M3S"),
+        )
+        .unwrap();
+        let format = DateFormat::new(Some("%d.%m.%Y"), None).unwrap();
+
+        let tests = [
+            (
+                "{date} | {duration_hms} | {title}",
+                None,
+                "2024-01-02T03:04:05Z | 1:02:03 | Some {title}",
+            ),
+            ("{date}", Some(&format), "02.01.2024"),
+            ("{url}", None, "https://www.youtube.com/watch?v=abc"),
+            (
+                "{id}:{duration}={duration_seconds}s{views}",
+                None,
+                "abc:PT1H2M3S=3723s",
+            ),
+        ];
+
+        for (t, d, r) in tests {
+            assert_eq!(t.parse::<Template>().unwrap().render(&video, d), r);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module to sanitize video titles before output
+
+    Control characters (C0 and C1), bidirectional formatting characters (which could reorder the rest of a CSV line
+    when displayed) and invisible zero-width characters are always removed, with line breaks and tabs replaced by a
+    space. Optionally emoji are removed, or the title is transliterated to ASCII.
+
+    Only the displayed title is sanitized, the one received is kept next to it, and is written to the JSON output as
+    'title_raw', where the escaping keeps it from breaking the format.
+
+    The title filter keeps a video if its (sanitized) title contains any of the 'any' terms, and all of the 'all' terms,
+    both ignoring letter case. An empty list of terms does not exclude anything, so the two can be given separately or
+    together, e.g. any of "ep" and "part", but only the ones with "tutorial" too.
+*/
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TitleOptions {
+    pub strip_emoji: bool,
+    pub ascii: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TitleFilter {
+    pub any: Vec<String>,
+    pub all: Vec<String>,
+}
+impl TitleFilter {
+    pub fn matches(&self, title: &str) -> bool {
+        let title = title.to_lowercase();
+        let contains = |term: &String| title.contains(&term.to_lowercase());
+        (self.any.is_empty() || self.any.iter().any(contains)) && self.all.iter().all(contains)
+    }
+
+    //Comma-separated, ignoring the empty terms
+    pub fn parse_terms(s: &str) -> Result<Vec<String>, String> {
+        let terms: Vec<String> = s
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect();
+        match terms.is_empty() {
+            true => Err(format!("No title terms given in '{}'", s)),
+            false => Ok(terms),
+        }
+    }
+}
+
+pub fn sanitize(title: &str, options: TitleOptions) -> String {
+    let mut out = String::with_capacity(title.len());
+    for c in title.chars() {
+        if matches!(c, '\t' | '\n' | '\r') {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+        } else if !(c.is_control() || is_invisible(c) || (options.strip_emoji && is_emoji(c))) {
+            out.push(c);
+        }
+    }
+
+    let out = match options.ascii {
+        true => deunicode::deunicode_with_tofu(&out, ""),
+        false => out,
+    };
+    String::from(out.trim())
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(c,
+        '\u{200B}'..='\u{200F}' //zero-width space, (non-)joiner, LTR/RTL marks
+        | '\u{202A}'..='\u{202E}' //bidi embeddings and overrides
+        | '\u{2060}'..='\u{2064}' //word joiner, invisible operators
+        | '\u{2066}'..='\u{2069}' //bidi isolates
+        | '\u{061C}' //Arabic letter mark
+        | '\u{FEFF}' //zero-width no-break space (BOM)
+    )
+}
+
+//Approximation of the Extended_Pictographic property, including the modifiers used in emoji sequences
+fn is_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{1F000}'..='\u{1FAFF}' //pictographs, emoticons, flags, skin tones
+        | '\u{2600}'..='\u{27BF}' //miscellaneous symbols, dingbats
+        | '\u{2B05}'..='\u{2B07}' | '\u{2B1B}' | '\u{2B1C}' | '\u{2B50}' | '\u{2B55}'
+        | '\u{231A}' | '\u{231B}' | '\u{23E9}'..='\u{23FA}'
+        | '\u{FE0E}' | '\u{FE0F}' //variation selectors
+        | '\u{20E3}' //combining keycap
+        | '\u{E0020}'..='\u{E007F}' //tags
+    )
+}
+
+#[cfg(test)]
+mod title_test {
+    use super::*;
+
+    #[test]
+    fn sanitize_test() {
+        let plain = TitleOptions::default();
+        let emoji = TitleOptions {
+            strip_emoji: true,
+            ascii: false,
+        };
+        let ascii = TitleOptions {
+            strip_emoji: false,
+            ascii: true,
+        };
+        let both = TitleOptions {
+            strip_emoji: true,
+            ascii: true,
+        };
+
+        let tests = [
+            ("Plain title", plain, "Plain title"),
+            ("Two\nlines\r\n", plain, "Two lines"),
+            ("Tab\tseparated", plain, "Tab separated"),
+            ("Bell\u{7}\u{1b}[31mred", plain, "Bell[31mred"),
+            ("C1\u{85}\u{9b}control", plain, "C1control"),
+            //RTL override making "exe.txt" display as "txt.exe"
+            ("invoice\u{202E}txt.exe", plain, "invoicetxt.exe"),
+            (
+                "\u{2067}isolated\u{2069} \u{200F}mark",
+                plain,
+                "isolated mark",
+            ),
+            ("zero\u{200B}width\u{2060}\u{FEFF}", plain, "zerowidth"),
+            ("Family 👨\u{200D}👩\u{200D}👧", plain, "Family 👨👩👧"),
+            ("Party 🎉 time ✨", emoji, "Party  time"),
+            ("Keycap 1\u{FE0F}\u{20E3} ❤\u{FE0F}", emoji, "Keycap 1"),
+            ("Flag 🇭🇺!", emoji, "Flag !"),
+            ("Thumbs 👍🏽", emoji, "Thumbs"),
+            ("Ünnepi műsor", emoji, "Ünnepi műsor"),
+            ("Ünnepi műsor", ascii, "Unnepi musor"),
+            //combining characters
+            ("Cafe\u{301} noe\u{308}l", plain, "Cafe\u{301} noe\u{308}l"),
+            ("Cafe\u{301} noe\u{308}l", ascii, "Cafe noel"),
+            ("Z\u{336}a\u{334}l\u{335}g\u{337}o\u{338}", ascii, "Zalgo"),
+            ("Москва", ascii, "Moskva"),
+            ("Party 🎉", both, "Party"),
+            ("\u{202E}\u{200B}", plain, ""),
+        ];
+
+        for (t, o, r) in tests {
+            assert_eq!(sanitize(t, o), r, "title={:?} options={:?}", t, o);
+        }
+    }
+
+    #[test]
+    fn filter_test() {
+        let terms = |s: &str| TitleFilter::parse_terms(s).unwrap();
+        let filter = |any: Option<&str>, all: Option<&str>| TitleFilter {
+            any: any.map(terms).unwrap_or_default(),
+            all: all.map(terms).unwrap_or_default(),
+        };
+
+        let tests = [
+            (filter(None, None), "Anything", true),
+            (
+                filter(Some("Ep,Episode,Part"), None),
+                "Rust EPISODE 3",
+                true,
+            ),
+            (filter(Some("Ep,Episode,Part"), None), "Part two", true),
+            (filter(Some("Episode, Part"), None), "Ep. 3", false),
+            (filter(None, Some("rust,tutorial")), "Rust Tutorial 1", true),
+            (
+                filter(None, Some("rust,tutorial")),
+                "Rust livestream",
+                false,
+            ),
+            (filter(Some("ep,part"), Some("rust")), "Rust part 2", true),
+            (filter(Some("ep,part"), Some("rust")), "Go part 2", false),
+            (filter(Some("ep,part"), Some("rust")), "Rust intro", false),
+            (filter(Some("ÉLET"), None), "Az élet", true),
+        ];
+        for (f, title, matched) in tests {
+            assert_eq!(f.matches(title), matched, "{:?} title={:?}", f, title);
+        }
+
+        assert_eq!(
+            TitleFilter::parse_terms(" a ,, b,"),
+            Ok(vec![String::from("a"), String::from("b")])
+        );
+        assert!(TitleFilter::parse_terms(" , ").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module for the diagnostic messages of verbose mode, written apart from the status messages
+
+    Level 1 lists each request (with the API key redacted), its result and timing, the page tokens and the running
+    counts, level 2 adds the response sizes and the parse timing. Messages go to stderr unless another writer is
+    given, so the data on stdout stays clean. Without a writer they are log records instead, level 1 at debug and
+    level 2 at trace, enabled by the log filter (e.g. RUST_LOG=debug) rather than by the level.
+*/
+
+use std::cell::RefCell;
+use std::fmt::Arguments;
+use std::io::Write;
+
+pub struct Verbose {
+    level: u8,
+    out: Option<RefCell<Box<dyn Write>>>,
+}
+impl Verbose {
+    pub fn new(level: u8) -> Self {
+        Self::with_writer(level, Box::new(std::io::stderr()))
+    }
+
+    pub fn with_writer(level: u8, out: Box<dyn Write>) -> Self {
+        Self {
+            level,
+            out: Some(RefCell::new(out)),
+        }
+    }
+
+    pub fn log_records() -> Self {
+        Self {
+            level: 0,
+            out: None,
+        }
+    }
+
+    pub fn enabled(&self, level: u8) -> bool {
+        match self.out {
+            Some(_) => level <= self.level,
+            None => log::log_enabled!(record_level(level)),
+        }
+    }
+
+    pub fn log(&self, level: u8, args: Arguments) {
+        if self.enabled(level) {
+            match &self.out {
+                //Diagnostics must never fail the run
+                Some(out) => {
+                    let _ = writeln!(out.borrow_mut(), "{}", args);
+                }
+                None => log::log!(record_level(level), "{}", args),
+            }
+        }
+    }
+}
+
+fn record_level(level: u8) -> log::Level {
+    match level {
+        0 | 1 => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+//Replaces the value of the 'key' query parameter, in request addresses as well as in errors quoting them
+pub fn redact_key(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find("key=") {
+        let param = i > 0 && matches!(rest.as_bytes()[i - 1], b'?' | b'&');
+        let (head, tail) = rest.split_at(i + 4);
+        out.push_str(head);
+        rest = tail;
+        if param {
+            //API keys consist of letters, digits, '-' and '_'
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            out.push_str("***");
+            rest = &rest[end..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod verbose_test {
+    use super::*;
+
+    #[test]
+    fn redact_test() {
+        let tests = [
+            (
+                "https://a/b?part=id&key=SECRET",
+                "https://a/b?part=id&key=***",
+            ),
+            (
+                "https://a/b?key=SECRET&part=id",
+                "https://a/b?key=***&part=id",
+            ),
+            ("https://a/b?key=", "https://a/b?key=***"),
+            (
+                "https://a/b?key=S1: Connection refused (https://a/c?x=1&key=S2)",
+                "https://a/b?key=***: Connection refused (https://a/c?x=1&key=***)",
+            ),
+            (
+                "https://a/b?monkey=banana&pageToken=x",
+                "https://a/b?monkey=banana&pageToken=x",
+            ),
+            ("key=SECRET", "key=SECRET"),
+            ("", ""),
+        ];
+
+        for (s, r) in tests {
+            assert_eq!(redact_key(s), r);
+        }
+    }
+}
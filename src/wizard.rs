@@ -0,0 +1,382 @@
+/*
+    Created by Zoltan Kovari, 2024.
+
+    Licensed under the Apache License, Version 2.0
+    http://www.apache.org/licenses/LICENSE-2.0
+    (see LICENSE.txt)
+
+
+    Module of the steps of '--wizard', asking for the settings of a run one by one
+
+    Each step has a prompt with an optional default (taken for an empty answer), and an answer either moves on to the
+    next step, or is rejected with a message so the same step is asked again. The channel is resolved by the given
+    closure, and then confirmed by its title, a rejection going back to the channel. The answers are kept as typed, so
+    the equivalent command line runs the same, e.g. with a relative date counting back from the time of the run.
+
+    Reading the answers and displaying the prompts is up to the caller, so the steps can be tested without a terminal.
+*/
+
+use chrono::{DateTime, Utc};
+
+use crate::dates::Bound;
+use crate::output::{Format, DEFAULT_PATH};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Channel,
+    Confirm,
+    Start,
+    End,
+    Output,
+    Format,
+    Region,
+    Playlists,
+    Done,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Prompt {
+    pub question: String,
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers {
+    //Handle, without the '@'
+    pub channel: String,
+    pub title: String,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub output: Option<String>,
+    pub format: Option<String>,
+    pub region: Option<String>,
+    pub playlists: Vec<String>,
+}
+
+pub struct Wizard<R: FnMut(&str) -> Result<(String, String), String>> {
+    step: Step,
+    answers: Answers,
+    start_date: Option<DateTime<Utc>>,
+    //Of a channel answered, to a handle and the title to confirm
+    resolve: R,
+    now: DateTime<Utc>,
+}
+impl<R: FnMut(&str) -> Result<(String, String), String>> Wizard<R> {
+    pub fn new(resolve: R, now: DateTime<Utc>) -> Self {
+        Self {
+            step: Step::Channel,
+            answers: Answers::default(),
+            start_date: None,
+            resolve,
+            now,
+        }
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    pub fn answers(&self) -> &Answers {
+        &self.answers
+    }
+
+    //None once all steps are answered
+    pub fn prompt(&self) -> Option<Prompt> {
+        let prompt = |question: &str, default: Option<&str>| {
+            Some(Prompt {
+                question: String::from(question),
+                default: default.map(String::from),
+            })
+        };
+        match self.step {
+            Step::Channel => prompt("Channel name:", None),
+            Step::Confirm => Some(Prompt {
+                question: format!(
+                    "Found channel '{}' (@{}), is this the one?",
+                    self.answers.title, self.answers.channel
+                ),
+                default: Some(String::from("y")),
+            }),
+            Step::Start => prompt(
+                "Filter to dates starting from (UTC, empty for no limit):",
+                None,
+            ),
+            Step::End => prompt("Filter to dates ending at (UTC, empty for no limit):", None),
+            Step::Output => prompt("Output file:", Some(DEFAULT_PATH)),
+            Step::Format => prompt(
+                "Output format ('csv', 'm3u', 'json' or 'md'):",
+                Some(self.default_format()),
+            ),
+            Step::Region => prompt(
+                "Leave out the videos blocked in region (two-letter code, empty for none):",
+                None,
+            ),
+            Step::Playlists => prompt(
+                "Playlists to sum instead of the uploads (comma-separated IDs, empty for the uploads):",
+                None,
+            ),
+            Step::Done => None,
+        }
+    }
+
+    fn default_format(&self) -> &'static str {
+        let path = self.answers.output.as_deref().unwrap_or(DEFAULT_PATH);
+        match Format::from_path(path) {
+            Some(Format::M3u) => "m3u",
+            Some(Format::Json) => "json",
+            Some(Format::Markdown) => "md",
+            _ => "csv",
+        }
+    }
+
+    //A message to display if accepted (e.g. about a daylight saving time transition), or why it was not
+    pub fn answer(&mut self, input: &str) -> Result<Option<String>, String> {
+        let input = input.trim();
+        let given = (!input.is_empty()).then(|| String::from(input));
+        let mut message = None;
+        self.step = match self.step {
+            Step::Channel => {
+                let name = given.ok_or("Missing channel name")?;
+                let (channel, title) = (self.resolve)(&name)?;
+                self.answers.channel = channel;
+                self.answers.title = title;
+                Step::Confirm
+            }
+            Step::Confirm => match input {
+                "" | "y" | "Y" => Step::Start,
+                "n" | "N" => Step::Channel,
+                _ => return Err(String::from("Answer 'y' or 'n'")),
+            },
+            Step::Start => {
+                self.start_date = match given {
+                    Some(ref s) => {
+                        let parsed = crate::dates::parse_date(
+                            s,
+                            Bound::Start,
+                            self.now,
+                            &chrono_tz::Tz::UTC,
+                        )?;
+                        message = parsed.warning.map(|w| format!("Warning: {}", w));
+                        Some(parsed.date)
+                    }
+                    None => None,
+                };
+                self.answers.start = given;
+                Step::End
+            }
+            Step::End => {
+                let end_date = match given {
+                    Some(ref s) => {
+                        let parsed =
+                            crate::dates::parse_date(s, Bound::End, self.now, &chrono_tz::Tz::UTC)?;
+                        message = parsed.warning.map(|w| format!("Warning: {}", w));
+                        Some(parsed.date)
+                    }
+                    None => None,
+                };
+                if let Some(note) = crate::dates::check_range(self.start_date, end_date, self.now)?
+                {
+                    message = Some(note);
+                }
+                self.answers.end = given;
+                Step::Output
+            }
+            Step::Output => {
+                self.answers.output = given.filter(|p| p != DEFAULT_PATH);
+                Step::Format
+            }
+            Step::Format => {
+                let format = given.unwrap_or_else(|| String::from(self.default_format()));
+                match format.parse()? {
+                    Format::Template => {
+                        return Err(String::from(
+                            "The 'template' format needs '--template', give both on the command line instead",
+                        ))
+                    }
+                    //Unless the output is given without a '.csv' extension, as that would warn
+                    Format::Csv
+                        if self.answers.output.is_none()
+                            || self.answers.output.as_deref().and_then(Format::from_path)
+                                == Some(Format::Csv) =>
+                    {
+                        self.answers.format = None
+                    }
+                    _ => self.answers.format = Some(format),
+                }
+                Step::Region
+            }
+            Step::Region => {
+                if let Some(ref r) = given {
+                    if r.len() != 2 || !r.chars().all(|c| c.is_ascii_alphabetic()) {
+                        return Err(format!(
+                            "Invalid region '{}', expected a two-letter country code like 'DE'",
+                            r
+                        ));
+                    }
+                }
+                self.answers.region = given.map(|r| r.to_ascii_uppercase());
+                Step::Playlists
+            }
+            Step::Playlists => {
+                self.answers.playlists = input
+                    .split(',')
+                    .map(|p| String::from(p.trim()))
+                    .filter(|p| !p.is_empty())
+                    .collect();
+                Step::Done
+            }
+            Step::Done => return Err(String::from("All settings are given")),
+        };
+        Ok(message)
+    }
+
+    //Arguments running the same without the wizard, besides the ones it was started with
+    pub fn command_line(&self) -> Vec<String> {
+        let a = &self.answers;
+        let mut args = Vec::new();
+        let mut option = |name: &str, value: &Option<String>| {
+            if let Some(v) = value {
+                //The '=' form keeps e.g. a negative epoch from being taken as an option
+                args.push(format!("{}={}", name, v));
+            }
+        };
+        option("--start", &a.start);
+        option("--end", &a.end);
+        option("--output", &a.output);
+        option("--format", &a.format);
+        option("--exclude-region-blocked", &a.region);
+        if !a.playlists.is_empty() {
+            option("--playlist", &Some(a.playlists.join(",")));
+        }
+        args.push(format!("@{}", a.channel));
+        args
+    }
+}
+
+#[cfg(test)]
+mod wizard_test {
+    use super::*;
+
+    fn wizard() -> Wizard<impl FnMut(&str) -> Result<(String, String), String>> {
+        let now =
+            DateTime::<Utc>::from(DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z").unwrap());
+        Wizard::new(
+            |name: &str| match name.trim_start_matches('@') {
+                "missing" => Err(String::from("Could not find channel")),
+                n => Ok((String::from(n), format!("The {} Channel", n))),
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn steps_test() {
+        let mut w = wizard();
+        let tests = [
+            ("", Step::Channel, Err("Missing channel name")),
+            ("missing", Step::Channel, Err("Could not find")),
+            ("@foo", Step::Confirm, Ok(None)),
+            ("maybe", Step::Confirm, Err("'y' or 'n'")),
+            ("n", Step::Channel, Ok(None)),
+            ("bar", Step::Confirm, Ok(None)),
+            ("", Step::Start, Ok(None)),
+            ("soon", Step::Start, Err("")),
+            ("2024-07", Step::End, Ok(None)),
+            ("2024-01", Step::End, Err("is after the end date")),
+            (
+                "2024-08",
+                Step::Output,
+                Ok(Some("Warning: The date range lies")),
+            ),
+            ("report.json", Step::Format, Ok(None)),
+            ("template", Step::Format, Err("needs '--template'")),
+            ("", Step::Region, Ok(None)),
+            ("DEU", Step::Region, Err("two-letter")),
+            ("de", Step::Playlists, Ok(None)),
+            (" PL1, ,PL2 ", Step::Done, Ok(None)),
+        ];
+        for (input, step, result) in tests {
+            let asked = w.step();
+            match (w.answer(input), result) {
+                (Ok(m), Ok(expected)) => match (m, expected) {
+                    (None, None) => (),
+                    (Some(m), Some(e)) => assert!(m.starts_with(e), "{:?}: {}", input, m),
+                    (m, e) => panic!("{:?}: message {:?}, expected {:?}", input, m, e),
+                },
+                (Err(e), Err(msg)) => assert!(e.contains(msg), "{:?}: {}", input, e),
+                (r, _) => panic!("{:?} at {:?}: unexpected {:?}", input, asked, r),
+            }
+            assert_eq!(w.step(), step, "after {:?}", input);
+        }
+        assert_eq!(w.prompt(), None);
+        assert_eq!(
+            w.answers(),
+            &Answers {
+                channel: String::from("bar"),
+                title: String::from("The bar Channel"),
+                start: Some(String::from("2024-07")),
+                end: Some(String::from("2024-08")),
+                output: Some(String::from("report.json")),
+                format: Some(String::from("json")),
+                region: Some(String::from("DE")),
+                playlists: vec![String::from("PL1"), String::from("PL2")],
+            }
+        );
+        assert_eq!(
+            w.command_line(),
+            [
+                "--start=2024-07",
+                "--end=2024-08",
+                "--output=report.json",
+                "--format=json",
+                "--exclude-region-blocked=DE",
+                "--playlist=PL1,PL2",
+                "@bar",
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults_test() {
+        let mut w = wizard();
+        assert_eq!(w.prompt().unwrap().default, None);
+        w.answer("foo").unwrap();
+        assert_eq!(w.prompt().unwrap().default.as_deref(), Some("y"));
+        for _ in 0..3 {
+            w.answer("\n").unwrap();
+        }
+        assert_eq!(w.step(), Step::Output);
+        assert_eq!(w.prompt().unwrap().default.as_deref(), Some(DEFAULT_PATH));
+        w.answer("list.m3u8").unwrap();
+        assert_eq!(w.prompt().unwrap().default.as_deref(), Some("m3u"));
+        while w.prompt().is_some() {
+            w.answer("").unwrap();
+        }
+        assert_eq!(w.answers().format.as_deref(), Some("m3u"));
+        assert_eq!(
+            w.command_line(),
+            ["--output=list.m3u8", "--format=m3u", "@foo"]
+        );
+
+        let mut w = wizard();
+        while w.prompt().is_some() {
+            w.answer(if w.step() == Step::Channel { "foo" } else { "" })
+                .unwrap();
+        }
+        assert_eq!(w.command_line(), ["@foo"]);
+
+        let mut w = wizard();
+        while w.prompt().is_some() {
+            w.answer(match w.step() {
+                Step::Channel => "foo",
+                Step::Output => "report.txt",
+                _ => "",
+            })
+            .unwrap();
+        }
+        assert_eq!(
+            w.command_line(),
+            ["--output=report.txt", "--format=csv", "@foo"]
+        );
+    }
+}